@@ -0,0 +1,100 @@
+use crate::{Interval, Point3, Ray};
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Default)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    /// The empty box.
+    pub fn new() -> Aabb {
+        Aabb {
+            x: Interval::new(),
+            y: Interval::new(),
+            z: Interval::new(),
+        }
+    }
+
+    /// Box spanned by two opposite corners, in any order.
+    pub fn from(a: Point3, b: Point3) -> Aabb {
+        Aabb {
+            x: Interval::from(a.x().min(b.x()), a.x().max(b.x())),
+            y: Interval::from(a.y().min(b.y()), a.y().max(b.y())),
+            z: Interval::from(a.z().min(b.z()), a.z().max(b.z())),
+        }
+    }
+
+    /// Smallest box enclosing both `a` and `b`.
+    pub fn from_boxes(a: &Aabb, b: &Aabb) -> Aabb {
+        Aabb {
+            x: Interval::from_intervals(&a.x, &b.x),
+            y: Interval::from_intervals(&a.y, &b.y),
+            z: Interval::from_intervals(&a.z, &b.z),
+        }
+    }
+
+    /// Overlap of both boxes, possibly empty.
+    pub fn intersect(&self, other: &Aabb) -> Aabb {
+        let overlap =
+            |a: &Interval, b: &Interval| Interval::from(a.min.max(b.min), a.max.min(b.max));
+        Aabb {
+            x: overlap(&self.x, &other.x),
+            y: overlap(&self.y, &other.y),
+            z: overlap(&self.z, &other.z),
+        }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::from(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max),
+        )
+    }
+
+    /// Index of the widest axis: 0 for x, 1 for y, 2 for z.
+    pub fn longest_axis(&self) -> usize {
+        let (x, y, z) = (self.x.size(), self.y.size(), self.z.size());
+        if x > y && x > z {
+            0
+        } else if y > z {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn axis_interval(&self, n: usize) -> &Interval {
+        match n {
+            1 => &self.y,
+            2 => &self.z,
+            _ => &self.x,
+        }
+    }
+
+    /// Slab test: whether `r` passes through the box within `ray_t`.
+    pub fn hit(&self, r: Ray, ray_t: Interval) -> bool {
+        self.clip(r, ray_t).is_some()
+    }
+
+    /// Part of `ray_t` during which `r` is inside the box, if any.
+    pub fn clip(&self, r: Ray, ray_t: Interval) -> Option<Interval> {
+        let (mut t_min, mut t_max) = (ray_t.min, ray_t.max);
+        for axis in 0..3 {
+            let ax = self.axis_interval(axis);
+            let adinv = 1.0 / r.direction()[axis];
+            let t0 = (ax.min - r.origin()[axis]) * adinv;
+            let t1 = (ax.max - r.origin()[axis]) * adinv;
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max <= t_min {
+                return None;
+            }
+        }
+        Some(Interval::from(t_min, t_max))
+    }
+}
@@ -0,0 +1,203 @@
+use std::cell::RefCell;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::{write_exr, Color3, ExrChannel, Filter, Image, Material, Point3, SampleBuffer, Vec3};
+
+/// Broad class of a scattering event, used to split the beauty pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lobe {
+    Diffuse,
+    Specular,
+    Transmission,
+}
+
+/// What a single camera sample saw at its first hit.
+#[derive(Default)]
+pub struct AovSample {
+    pub hit: bool,
+    /// Distance along the view axis, or from the eye for panoramic projections.
+    pub depth: f64,
+    pub normal: Vec3,
+    pub albedo: Color3,
+    pub position: Point3,
+    /// The hit material, numbered by `HittableList::material_id`.
+    pub material: Option<Rc<RefCell<dyn Material>>>,
+    pub object_id: u32,
+    /// Radiance arriving through each `Lobe` at the first hit.
+    pub lobes: [Color3; 3],
+}
+
+/// Auxiliary passes rendered alongside the beauty image.
+///
+/// The light-path passes (`diffuse`, `specular`, `transmission`) use the
+/// camera's reconstruction filter and add up to the beauty pass wherever a
+/// surface is visible. Geometric passes are box-averaged per pixel; depth
+/// and position only over samples that hit something, leaving `INFINITY`
+/// depth on background pixels. IDs come from each pixel's first sample
+/// that hits something and are 0 for the background. Material IDs number
+/// the materials in the order the scene was built.
+pub struct Aovs {
+    pub depth: Image,
+    pub normal: Image,
+    pub albedo: Image,
+    pub position: Image,
+    pub material_id: Image,
+    pub object_id: Image,
+    pub diffuse: Image,
+    pub specular: Image,
+    pub transmission: Image,
+}
+
+impl Aovs {
+    /// Every pass with its layer name and EXR channel names.
+    pub fn layers(&self) -> [(&'static str, &'static [&'static str], &Image); 9] {
+        const XYZ: &[&str] = &["X", "Y", "Z"];
+        const RGB: &[&str] = &["R", "G", "B"];
+        [
+            ("depth", &["Z"], &self.depth),
+            ("normal", XYZ, &self.normal),
+            ("albedo", RGB, &self.albedo),
+            ("position", XYZ, &self.position),
+            ("material_id", &["id"], &self.material_id),
+            ("object_id", &["id"], &self.object_id),
+            ("diffuse", RGB, &self.diffuse),
+            ("specular", RGB, &self.specular),
+            ("transmission", RGB, &self.transmission),
+        ]
+    }
+
+    /// Writes `beauty` as the default `R`, `G`, `B` layer and every pass as
+    /// its own layer of one multi-channel EXR file. A denoised version of
+    /// the beauty goes in a `denoised` layer, leaving the raw one intact.
+    pub fn write_exr(
+        &self,
+        beauty: &Image,
+        denoised: Option<&Image>,
+        path: &str,
+    ) -> io::Result<()> {
+        let mut channels = image_channels(beauty, "", &["R", "G", "B"]);
+        if let Some(denoised) = denoised {
+            channels.extend(image_channels(denoised, "denoised", &["R", "G", "B"]));
+        }
+        for (name, suffixes, image) in self.layers() {
+            channels.extend(image_channels(image, name, suffixes));
+        }
+        write_exr(path, beauty.w, beauty.h, &channels)
+    }
+
+    /// Writes every pass as `<dir>/<layer>.pfm`.
+    pub fn write_pfm(&self, dir: &str) -> io::Result<()> {
+        for (name, _, image) in self.layers() {
+            let path = Path::new(dir).join(format!("{name}.pfm"));
+            image.write_pfm(&path.to_string_lossy())?;
+        }
+        Ok(())
+    }
+}
+
+fn image_channels(image: &Image, layer: &str, suffixes: &[&str]) -> Vec<ExrChannel> {
+    suffixes
+        .iter()
+        .enumerate()
+        .map(|(k, suffix)| {
+            let name = if layer.is_empty() {
+                suffix.to_string()
+            } else {
+                format!("{layer}.{suffix}")
+            };
+            let data = image.pixels.iter().map(|p| p[k] as f32).collect();
+            ExrChannel::new(&name, data)
+        })
+        .collect()
+}
+
+/// Accumulates `AovSample`s into per-pixel passes.
+pub struct AovBuffer {
+    w: u64,
+    h: u64,
+    samples: Vec<u32>,
+    hits: Vec<u32>,
+    depth: Vec<f64>,
+    normal: Vec<Vec3>,
+    albedo: Vec<Color3>,
+    position: Vec<Point3>,
+    material_id: Vec<u32>,
+    object_id: Vec<u32>,
+    lobes: [SampleBuffer; 3],
+}
+
+impl AovBuffer {
+    pub fn new(w: u64, h: u64, filter: Filter) -> AovBuffer {
+        let n = (w * h) as usize;
+        AovBuffer {
+            w,
+            h,
+            samples: vec![0; n],
+            hits: vec![0; n],
+            depth: vec![0.0; n],
+            normal: vec![Vec3::new(); n],
+            albedo: vec![Color3::new(); n],
+            position: vec![Point3::new(); n],
+            material_id: vec![0; n],
+            object_id: vec![0; n],
+            lobes: [
+                SampleBuffer::new(w, h, filter),
+                SampleBuffer::new(w, h, filter),
+                SampleBuffer::new(w, h, filter),
+            ],
+        }
+    }
+
+    /// Adds a sample of pixel `(j, i)` taken at raster position `(x, y)`,
+    /// whose material, if it hit one, has number `material_id`.
+    pub fn add(&mut self, j: u64, i: u64, x: f64, y: f64, sample: &AovSample, material_id: u32) {
+        for (buffer, clr) in self.lobes.iter_mut().zip(sample.lobes.iter()) {
+            buffer.splat(x, y, *clr);
+        }
+        let idx = (i * self.w + j) as usize;
+        self.samples[idx] += 1;
+        if !sample.hit {
+            return;
+        }
+        let first = self.hits[idx] == 0;
+        self.hits[idx] += 1;
+        self.depth[idx] += sample.depth;
+        self.normal[idx] += sample.normal;
+        self.albedo[idx] += sample.albedo;
+        self.position[idx] += sample.position;
+        if first {
+            self.material_id[idx] = material_id;
+            self.object_id[idx] = sample.object_id;
+        }
+    }
+
+    pub fn resolve(&self) -> Aovs {
+        let image = |f: &dyn Fn(usize) -> Color3| Image {
+            w: self.w,
+            h: self.h,
+            pixels: (0..(self.w * self.h) as usize).map(f).collect(),
+        };
+        let gray = |v: f64| Color3::from(v, v, v);
+        let per_sample = |idx: usize| self.samples[idx].max(1) as f64;
+        let per_hit = |idx: usize| self.hits[idx].max(1) as f64;
+        Aovs {
+            depth: image(&|idx| {
+                if self.hits[idx] == 0 {
+                    gray(f64::INFINITY)
+                } else {
+                    gray(self.depth[idx] / per_hit(idx))
+                }
+            }),
+            normal: image(&|idx| self.normal[idx] / per_sample(idx)),
+            albedo: image(&|idx| self.albedo[idx] / per_sample(idx)),
+            position: image(&|idx| self.position[idx] / per_hit(idx)),
+            material_id: image(&|idx| gray(self.material_id[idx] as f64)),
+            object_id: image(&|idx| gray(self.object_id[idx] as f64)),
+            diffuse: self.lobes[Lobe::Diffuse as usize].resolve(),
+            specular: self.lobes[Lobe::Specular as usize].resolve(),
+            transmission: self.lobes[Lobe::Transmission as usize].resolve(),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::rc::Rc;
+
+use crate::{luminance, sample_disk, Image, Vec3, PI};
+
+/// Shape of the lens opening. Samples land inside the unit disk in the xy
+/// plane and are scaled by the lens radius, so the shape is also the shape
+/// of out-of-focus highlights.
+#[derive(Clone)]
+pub enum Aperture {
+    Circle,
+    /// Regular polygon with `blades` sides, at least three, inscribed in
+    /// the unit circle and rotated by `rotation` degrees.
+    Polygon {
+        blades: u32,
+        rotation: f64,
+    },
+    /// Arbitrary shape given by the brightness of an image.
+    Mask(Rc<BokehMask>),
+}
+
+impl Aperture {
+    pub fn sample(&self, u: (f64, f64)) -> Vec3 {
+        match self {
+            Aperture::Circle => sample_disk(u),
+            Aperture::Polygon { blades, rotation } => sample_polygon(*blades, *rotation, u),
+            Aperture::Mask(mask) => mask.sample(u),
+        }
+    }
+}
+
+fn sample_polygon(blades: u32, rotation: f64, u: (f64, f64)) -> Vec3 {
+    let n = blades as f64;
+    // Pick a blade's triangle, then reuse the remainder of u.0 inside it.
+    let k = (u.0 * n).floor().min(n - 1.0);
+    let u0 = u.0 * n - k;
+    let step = 2.0 * PI / n;
+    let theta0 = rotation.to_radians() + k * step;
+    let a = Vec3::from(theta0.cos(), theta0.sin(), 0.0);
+    let b = Vec3::from((theta0 + step).cos(), (theta0 + step).sin(), 0.0);
+    let s = u0.sqrt();
+    (s * (1.0 - u.1)) * a + (s * u.1) * b
+}
+
+/// Aperture mask built from an image, sampled proportionally to brightness.
+pub struct BokehMask {
+    w: u64,
+    h: u64,
+    cdf: Vec<f64>,
+}
+
+impl BokehMask {
+    pub fn from(image: &Image) -> Option<BokehMask> {
+        let mut cdf = Vec::with_capacity(image.pixels.len() + 1);
+        let mut total = 0.0;
+        cdf.push(0.0);
+        for clr in &image.pixels {
+            total += luminance(*clr).max(0.0);
+            cdf.push(total);
+        }
+        if total <= 0.0 {
+            return None;
+        }
+        for c in cdf.iter_mut() {
+            *c /= total;
+        }
+        Some(BokehMask {
+            w: image.w,
+            h: image.h,
+            cdf,
+        })
+    }
+
+    fn sample(&self, u: (f64, f64)) -> Vec3 {
+        let idx = self
+            .cdf
+            .partition_point(|c| *c <= u.0)
+            .clamp(1, self.cdf.len() - 1)
+            - 1;
+        let width = self.cdf[idx + 1] - self.cdf[idx];
+        let jitter = if width > 0.0 {
+            (u.0 - self.cdf[idx]) / width
+        } else {
+            0.5
+        };
+        let x = (idx as u64 % self.w) as f64 + jitter;
+        let y = (idx as u64 / self.w) as f64 + u.1;
+        // Map the image's longer side onto [-1, 1] so that a disk drawn to
+        // the image edges matches `Aperture::Circle`.
+        let scale = 2.0 / self.w.max(self.h) as f64;
+        Vec3::from(
+            (x - self.w as f64 / 2.0) * scale,
+            (self.h as f64 / 2.0 - y) * scale,
+            0.0,
+        )
+    }
+}
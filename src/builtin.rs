@@ -0,0 +1,627 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    create_3_scene, create_bump_scene, create_cutout_scene, create_fov_scene, create_lights_scene,
+    create_quadrics_scene, create_random_spheres_scene, create_sdf_scene, create_subdivision_scene,
+    create_terrain_scene, BumpMap, BvhNode, Camera, CameraBuilder, Color3, Cone, Csg, CsgOp,
+    Cylinder, Diaelectric, DirectionalLight, Hittable, HittableList, Image, ImageTexture, Instance,
+    Lambertian, List, Material, Mesh, Metal, NoiseTexture, Perlin, Point3, PointLight, Scene,
+    SceneSetup, Sphere, Subdivision, TriangleMesh, Vec3, PI,
+};
+
+/// Named scene that comes with the renderer, so that benchmarks, tests and
+/// bug reports can refer to the same canonical content.
+pub struct BuiltinScene {
+    pub name: &'static str,
+    pub description: &'static str,
+    build: fn(u64) -> SceneSetup,
+}
+
+impl BuiltinScene {
+    /// Builds the scene and a camera framing it. `seed` picks the layout
+    /// of randomized scenes; the others ignore it.
+    pub fn build(&self, seed: u64) -> SceneSetup {
+        (self.build)(seed)
+    }
+}
+
+/// Every built-in scene, in the order `--list-scenes` prints them.
+pub const BUILTIN_SCENES: &[BuiltinScene] = &[
+    BuiltinScene {
+        name: "random-spheres",
+        description: "the Ray Tracing in One Weekend cover, laid out by the seed",
+        build: random_spheres,
+    },
+    BuiltinScene {
+        name: "cornell-box",
+        description: "Cornell box lit by a point light under the ceiling",
+        build: cornell_box,
+    },
+    BuiltinScene {
+        name: "material-balls",
+        description: "rows of diffuse, metal and glass test balls",
+        build: material_balls,
+    },
+    BuiltinScene {
+        name: "sponza",
+        description: "two-story colonnaded atrium with drapes, ~350k triangles",
+        build: sponza,
+    },
+    BuiltinScene {
+        name: "instancing",
+        description: "a field of ~14k instanced trees and rocks, laid out by the seed",
+        build: instancing,
+    },
+    BuiltinScene {
+        name: "three-balls",
+        description: "diffuse, hollow glass and metal balls",
+        build: three_balls,
+    },
+    BuiltinScene {
+        name: "lights",
+        description: "three-balls lit by point, spot and directional lights",
+        build: lights,
+    },
+    BuiltinScene {
+        name: "fov",
+        description: "two touching balls filling a 90 degree view",
+        build: fov,
+    },
+    BuiltinScene {
+        name: "quadrics",
+        description: "one of each analytic primitive",
+        build: quadrics,
+    },
+    BuiltinScene {
+        name: "sdf",
+        description: "distance field shapes",
+        build: sdf,
+    },
+    BuiltinScene {
+        name: "terrain",
+        description: "noise terrain under metaballs",
+        build: terrain,
+    },
+    BuiltinScene {
+        name: "subdivision",
+        description: "meshes before and after subdivision",
+        build: subdivision,
+    },
+    BuiltinScene {
+        name: "bump",
+        description: "bump- and normal-mapped surfaces",
+        build: bump,
+    },
+    BuiltinScene {
+        name: "cutout",
+        description: "opacity-masked cards",
+        build: cutout,
+    },
+];
+
+/// The built-in scene called `name`.
+pub fn builtin_scene(name: &str) -> Option<&'static BuiltinScene> {
+    BUILTIN_SCENES.iter().find(|s| s.name == name)
+}
+
+fn setup(fill: impl FnOnce(&mut Scene), camera: CameraBuilder) -> SceneSetup {
+    let mut scene = Scene::new();
+    fill(&mut scene);
+    SceneSetup { scene, camera }
+}
+
+/// Camera used by the small test scenes, which all sit around the origin.
+fn front_view() -> CameraBuilder {
+    Camera::builder()
+        .fov(40.0)
+        .lookfrom(Point3::from(0.0, 2.0, 6.0))
+        .lookat(Point3::from(0.0, 0.5, 0.0))
+}
+
+fn random_spheres(seed: u64) -> SceneSetup {
+    setup(
+        |s| create_random_spheres_scene(&mut s.world, seed),
+        Camera::builder()
+            .fov(20.0)
+            .lookfrom(Point3::from(13.0, 2.0, 3.0))
+            .lookat(Point3::from(0.0, 0.0, 0.0))
+            .defocus_angle(0.01)
+            .focus_dist(10.0),
+    )
+}
+
+fn three_balls(_seed: u64) -> SceneSetup {
+    setup(|s| create_3_scene(&mut s.world), Camera::builder())
+}
+
+fn lights(_seed: u64) -> SceneSetup {
+    setup(
+        |s| create_lights_scene(&mut s.world, &mut s.lights),
+        Camera::builder(),
+    )
+}
+
+fn fov(_seed: u64) -> SceneSetup {
+    setup(|s| create_fov_scene(&mut s.world), Camera::builder())
+}
+
+fn quadrics(_seed: u64) -> SceneSetup {
+    setup(|s| create_quadrics_scene(&mut s.world), front_view())
+}
+
+fn sdf(_seed: u64) -> SceneSetup {
+    setup(|s| create_sdf_scene(&mut s.world), front_view())
+}
+
+fn terrain(_seed: u64) -> SceneSetup {
+    setup(
+        |s| create_terrain_scene(&mut s.world),
+        Camera::builder()
+            .fov(50.0)
+            .lookfrom(Point3::from(0.0, 3.0, 7.0))
+            .lookat(Point3::from(0.0, 0.8, 0.0)),
+    )
+}
+
+fn subdivision(_seed: u64) -> SceneSetup {
+    setup(|s| create_subdivision_scene(&mut s.world), front_view())
+}
+
+fn bump(_seed: u64) -> SceneSetup {
+    setup(|s| create_bump_scene(&mut s.world), front_view())
+}
+
+fn cutout(_seed: u64) -> SceneSetup {
+    setup(|s| create_cutout_scene(&mut s.world), front_view())
+}
+
+fn lambertian(r: f64, g: f64, b: f64) -> Rc<RefCell<dyn Material>> {
+    Rc::new(RefCell::new(Lambertian::from(Color3::from(r, g, b))))
+}
+
+fn add_quad(mesh: &mut Mesh, corners: [Point3; 4]) {
+    let start = mesh.positions.len();
+    mesh.positions.extend(corners);
+    mesh.faces.push((start..start + 4).collect());
+}
+
+/// Axis-aligned box between `min` and `max`, as six quads.
+fn add_cuboid(mesh: &mut Mesh, min: Point3, max: Point3) {
+    let start = mesh.positions.len();
+    for k in 0..8 {
+        mesh.positions.push(Point3::from(
+            if k & 1 == 0 { min.x() } else { max.x() },
+            if k & 2 == 0 { min.y() } else { max.y() },
+            if k & 4 == 0 { min.z() } else { max.z() },
+        ));
+    }
+    for face in [
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+    ] {
+        mesh.faces.push(face.iter().map(|v| start + v).collect());
+    }
+}
+
+/// Grid of `nu` by `nv` quads over `f(u, v)` for `u, v` in `[0, 1]`, closed
+/// in `u` when `wrap_u`.
+fn add_grid(mesh: &mut Mesh, nu: usize, nv: usize, wrap_u: bool, f: impl Fn(f64, f64) -> Point3) {
+    let start = mesh.positions.len();
+    let columns = if wrap_u { nu } else { nu + 1 };
+    for j in 0..=nv {
+        for i in 0..columns {
+            mesh.positions
+                .push(f(i as f64 / nu as f64, j as f64 / nv as f64));
+        }
+    }
+    let vertex = |i: usize, j: usize| start + j * columns + i % columns;
+    for j in 0..nv {
+        for i in 0..nu {
+            mesh.faces.push(vec![
+                vertex(i, j),
+                vertex(i + 1, j),
+                vertex(i + 1, j + 1),
+                vertex(i, j + 1),
+            ]);
+        }
+    }
+}
+
+fn smooth_mesh(mut mesh: Mesh, mat: Rc<RefCell<dyn Material>>) -> Rc<RefCell<dyn Hittable>> {
+    mesh.compute_normals();
+    Rc::new(RefCell::new(TriangleMesh::new(&mesh, mat)))
+}
+
+fn flat_mesh(mesh: &Mesh, mat: Rc<RefCell<dyn Material>>) -> Rc<RefCell<dyn Hittable>> {
+    Rc::new(RefCell::new(TriangleMesh::new(mesh, mat)))
+}
+
+/// The classic 555 unit box with a tall and a short block. It is open
+/// towards the camera.
+fn cornell_box(_seed: u64) -> SceneSetup {
+    let white = lambertian(0.73, 0.73, 0.73);
+    let p = Point3::from;
+    let mut walls = Mesh::new();
+    let s = 555.0;
+    add_quad(
+        &mut walls,
+        [p(0., 0., 0.), p(s, 0., 0.), p(s, 0., s), p(0., 0., s)],
+    );
+    add_quad(
+        &mut walls,
+        [p(0., s, 0.), p(0., s, s), p(s, s, s), p(s, s, 0.)],
+    );
+    add_quad(
+        &mut walls,
+        [p(0., 0., s), p(s, 0., s), p(s, s, s), p(0., s, s)],
+    );
+    let mut left = Mesh::new();
+    add_quad(
+        &mut left,
+        [p(s, 0., 0.), p(s, s, 0.), p(s, s, s), p(s, 0., s)],
+    );
+    let mut right = Mesh::new();
+    add_quad(
+        &mut right,
+        [p(0., 0., 0.), p(0., 0., s), p(0., s, s), p(0., s, 0.)],
+    );
+
+    // Blocks are modeled at the origin and turned into place.
+    let block = |height: f64, angle: f64, offset: Vec3| -> Rc<RefCell<dyn Hittable>> {
+        let mut mesh = Mesh::new();
+        add_cuboid(&mut mesh, p(0.0, 0.0, 0.0), p(165.0, height, 165.0));
+        Rc::new(RefCell::new(
+            Instance::new(flat_mesh(&mesh, white.clone()))
+                .with_rotation(Vec3::from(0.0, 1.0, 0.0), angle)
+                .with_offset(offset),
+        ))
+    };
+
+    setup(
+        |scene| {
+            scene.world.add(flat_mesh(&walls, white.clone()));
+            scene
+                .world
+                .add(flat_mesh(&left, lambertian(0.12, 0.45, 0.15)));
+            scene
+                .world
+                .add(flat_mesh(&right, lambertian(0.65, 0.05, 0.05)));
+            scene
+                .world
+                .add(block(330.0, 15.0, Vec3::from(265.0, 0.0, 295.0)));
+            scene
+                .world
+                .add(block(165.0, -18.0, Vec3::from(130.0, 0.0, 65.0)));
+            scene.lights.add(Rc::new(PointLight::new(
+                Point3::from(278.0, 480.0, 279.5),
+                Color3::from(1.0, 0.85, 0.6) * 1.0e5,
+            )));
+        },
+        Camera::builder()
+            .aspect_ratio(1.0)
+            .fov(40.0)
+            .lookfrom(Point3::from(278.0, 278.0, -800.0))
+            .lookat(Point3::from(278.0, 278.0, 0.0)),
+    )
+}
+
+/// Three rows of four balls: diffuse ones in the back, metals of growing
+/// roughness in the middle and dielectrics in front.
+fn material_balls(_seed: u64) -> SceneSetup {
+    let checker = Image {
+        w: 16,
+        h: 8,
+        pixels: (0..128)
+            .map(|k| {
+                if (k % 16 + k / 16) % 2 == 0 {
+                    Color3::from(0.9, 0.9, 0.9)
+                } else {
+                    Color3::from(0.1, 0.1, 0.1)
+                }
+            })
+            .collect(),
+    };
+    let steel: Rc<RefCell<dyn Material>> = Rc::new(RefCell::new(Metal::from(
+        Color3::from(0.8, 0.8, 0.85),
+        0.05,
+    )));
+    let glass: Rc<RefCell<dyn Material>> = Rc::new(RefCell::new(Diaelectric::from(1.5)));
+    let rows: [[Rc<RefCell<dyn Material>>; 4]; 3] = [
+        [
+            lambertian(0.7, 0.2, 0.2),
+            lambertian(0.2, 0.6, 0.3),
+            lambertian(0.8, 0.8, 0.8),
+            Rc::new(RefCell::new(Lambertian::from_texture(
+                Color3::from(0.8, 0.7, 0.5),
+                Rc::new(ImageTexture::new(checker)),
+            ))),
+        ],
+        [
+            Rc::new(RefCell::new(Metal::from(Color3::from(0.9, 0.9, 0.9), 0.0))),
+            Rc::new(RefCell::new(Metal::from(Color3::from(0.9, 0.7, 0.3), 0.1))),
+            Rc::new(RefCell::new(Metal::from(Color3::from(0.9, 0.5, 0.4), 0.3))),
+            Rc::new(RefCell::new(BumpMap::from(
+                steel,
+                Rc::new(NoiseTexture::new(3, 8.0, 3)),
+                0.02,
+            ))),
+        ],
+        [
+            Rc::new(RefCell::new(Diaelectric::from(1.33))),
+            glass.clone(),
+            Rc::new(RefCell::new(Diaelectric::from(2.42))),
+            glass,
+        ],
+    ];
+
+    setup(
+        |scene| {
+            scene.world.add(Rc::new(RefCell::new(Sphere::new(
+                Point3::from(0.0, -1000.0, 0.0),
+                1000.0,
+                lambertian(0.5, 0.5, 0.5),
+            ))));
+            for (row, mats) in rows.into_iter().enumerate() {
+                for (col, mat) in mats.into_iter().enumerate() {
+                    let center =
+                        Point3::from(-1.8 + 1.2 * col as f64, 0.45, -1.2 + 1.2 * row as f64);
+                    let ball = Sphere::new(center, 0.45, mat.clone());
+                    if row == 2 && col == 3 {
+                        // Hollow glass: a thin shell carved with CSG.
+                        scene.world.add(Rc::new(RefCell::new(Csg::new(
+                            CsgOp::Difference,
+                            Rc::new(RefCell::new(ball)),
+                            Rc::new(RefCell::new(Sphere::new(center, 0.4, mat))),
+                        ))));
+                    } else {
+                        scene.world.add(Rc::new(RefCell::new(ball)));
+                    }
+                }
+            }
+        },
+        Camera::builder()
+            .fov(30.0)
+            .lookfrom(Point3::from(0.0, 5.0, 8.0))
+            .lookat(Point3::from(0.0, 0.2, 0.0)),
+    )
+}
+
+/// Fluted column with a slight taper between `y0` and `y1`.
+fn add_column(mesh: &mut Mesh, x: f64, z: f64, y0: f64, y1: f64, radius: f64) {
+    add_grid(mesh, 64, 48, true, |u, v| {
+        let a = 2.0 * PI * u;
+        let r = radius * (1.0 - 0.1 * v) * (1.0 + 0.03 * (20.0 * a).cos());
+        Point3::from(x + r * a.cos(), y0 + v * (y1 - y0), z - r * a.sin())
+    });
+}
+
+/// Semicircular arch in the xy plane, `depth` thick along z, springing at
+/// height `y` from `x - outer` to `x + outer`.
+fn add_arch(mesh: &mut Mesh, x: f64, y: f64, z: f64, inner: f64, outer: f64, depth: f64) {
+    let at = |r: f64, a: f64, dz: f64| Point3::from(x + r * a.cos(), y + r * a.sin(), z + dz);
+    let h = 0.5 * depth;
+    add_grid(mesh, 32, 1, false, |u, v| at(inner, PI * u, h - v * depth));
+    add_grid(mesh, 32, 1, false, |u, v| at(outer, PI * u, v * depth - h));
+    add_grid(mesh, 32, 1, false, |u, v| {
+        at(inner + v * (outer - inner), PI * u, -h)
+    });
+    add_grid(mesh, 32, 1, false, |u, v| {
+        at(outer - v * (outer - inner), PI * u, h)
+    });
+}
+
+/// Drape hanging from `top` across `width` along x, folded along z.
+fn add_drape(mesh: &mut Mesh, top: Point3, width: f64, height: f64, phase: f64) {
+    add_grid(mesh, 96, 64, false, |u, v| {
+        let folds = 0.08 * (0.3 + 0.7 * v) * (2.0 * PI * 6.0 * u + phase).sin();
+        let sway = 0.15 * v * v * (PI * u + phase).sin();
+        Point3::from(
+            top.x() + width * u,
+            top.y() - height * v,
+            top.z() + folds + sway,
+        )
+    });
+}
+
+/// Courtyard in the manner of the Sponza atrium: two stories of arcades
+/// around an open nave, with drapes between the lower columns. The
+/// geometry is a few large meshes, to stress the mesh hierarchy.
+fn sponza(_seed: u64) -> SceneSetup {
+    let mut stone = Mesh::new();
+    let mut columns = Mesh::new();
+    let mut drapes = [Mesh::new(), Mesh::new(), Mesh::new()];
+    let p = Point3::from;
+
+    // Floor, outer walls, gallery floors and roofs over the aisles.
+    add_cuboid(&mut stone, p(-15.5, -0.2, -6.5), p(15.5, 0.0, 6.5));
+    for side in [-1.0, 1.0] {
+        let (z0, z1) = if side < 0.0 { (-6.5, -6.2) } else { (6.2, 6.5) };
+        add_cuboid(&mut stone, p(-15.5, 0.0, z0), p(15.5, 11.1, z1));
+        let (a0, a1) = if side < 0.0 { (-6.2, -3.6) } else { (3.6, 6.2) };
+        add_cuboid(&mut stone, p(-15.2, 5.8, a0), p(15.2, 6.1, a1));
+        add_cuboid(&mut stone, p(-15.2, 10.8, a0), p(15.2, 11.1, a1));
+        // Balustrade along the gallery.
+        add_cuboid(
+            &mut stone,
+            p(-15.2, 6.1, side * 3.7 - 0.1),
+            p(15.2, 7.0, side * 3.7 + 0.1),
+        );
+    }
+    for x0 in [-15.5, 15.2] {
+        add_cuboid(&mut stone, p(x0, 0.0, -6.2), p(x0 + 0.3, 11.1, 6.2));
+    }
+
+    for side in [-1.0, 1.0] {
+        let z = 4.0 * side;
+        for k in 0..10 {
+            let x = -13.5 + 3.0 * k as f64;
+            // Lower story: plinth, shaft and capital.
+            add_cuboid(
+                &mut stone,
+                p(x - 0.4, 0.0, z - 0.4),
+                p(x + 0.4, 0.4, z + 0.4),
+            );
+            add_column(&mut columns, x, z, 0.4, 4.0, 0.3);
+            add_cuboid(
+                &mut stone,
+                p(x - 0.4, 4.0, z - 0.4),
+                p(x + 0.4, 4.3, z + 0.4),
+            );
+            // Upper story, standing on the gallery floor.
+            add_column(&mut columns, x, z, 6.1, 9.0, 0.22);
+            add_cuboid(
+                &mut stone,
+                p(x - 0.3, 9.0, z - 0.3),
+                p(x + 0.3, 9.3, z + 0.3),
+            );
+            if k < 9 {
+                add_arch(&mut stone, x + 1.5, 4.3, z, 1.2, 1.5, 0.6);
+                add_arch(&mut stone, x + 1.5, 9.3, z, 1.28, 1.5, 0.5);
+                if k % 2 == 1 {
+                    let top = p(x + 0.3, 5.6, z + 0.35 * side);
+                    add_drape(&mut drapes[k / 2 % 3], top, 2.4, 4.2, k as f64 + side);
+                }
+            }
+        }
+    }
+
+    let fabrics = [
+        lambertian(0.6, 0.1, 0.1),
+        lambertian(0.1, 0.3, 0.6),
+        lambertian(0.2, 0.5, 0.2),
+    ];
+
+    setup(
+        |scene| {
+            scene
+                .world
+                .add(flat_mesh(&stone, lambertian(0.75, 0.7, 0.6)));
+            scene
+                .world
+                .add(smooth_mesh(columns, lambertian(0.8, 0.75, 0.65)));
+            for (drape, fabric) in drapes.into_iter().zip(fabrics) {
+                scene.world.add(smooth_mesh(drape, fabric));
+            }
+            scene.lights.add(Rc::new(DirectionalLight::new(
+                Vec3::from(0.35, -1.0, 0.15),
+                Color3::from(3.0, 2.8, 2.4),
+            )));
+        },
+        Camera::builder()
+            .fov(60.0)
+            .lookfrom(Point3::from(-14.0, 1.7, 0.0))
+            .lookat(Point3::from(0.0, 4.0, 0.0)),
+    )
+}
+
+/// A rock: a subdivided octahedron pushed in and out by noise.
+fn rock(seed: u64) -> Mesh {
+    let mut mesh = Mesh::new();
+    mesh.positions = vec![
+        Point3::from(1.0, 0.0, 0.0),
+        Point3::from(-1.0, 0.0, 0.0),
+        Point3::from(0.0, 1.0, 0.0),
+        Point3::from(0.0, -1.0, 0.0),
+        Point3::from(0.0, 0.0, 1.0),
+        Point3::from(0.0, 0.0, -1.0),
+    ];
+    mesh.faces = vec![
+        vec![0, 2, 4],
+        vec![4, 2, 1],
+        vec![1, 2, 5],
+        vec![5, 2, 0],
+        vec![4, 3, 0],
+        vec![1, 3, 4],
+        vec![5, 3, 1],
+        vec![0, 3, 5],
+    ];
+    let mut mesh = mesh.subdivide(Subdivision::Loop, 4);
+    let noise = Perlin::new(seed);
+    for p in mesh.positions.iter_mut() {
+        let d = *p / p.length();
+        let r = 0.35 * (1.0 + 0.35 * noise.fbm(2.0 * d, 4));
+        *p = Point3::from(r * d.x(), 0.6 * r * d.y() + 0.1, r * d.z());
+    }
+    mesh
+}
+
+/// Field of trees and rocks, every one an instance of one of two shared
+/// prototypes with its own rotation, size and position.
+fn instancing(seed: u64) -> SceneSetup {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let rock: Rc<RefCell<dyn Hittable>> = smooth_mesh(rock(seed), lambertian(0.45, 0.42, 0.4));
+    let mut tree = HittableList::new();
+    tree.add(Rc::new(RefCell::new(
+        Cylinder::new(
+            Point3::from(0.0, 0.0, 0.0),
+            Point3::from(0.0, 0.5, 0.0),
+            0.06,
+            true,
+            lambertian(0.35, 0.22, 0.1),
+        )
+        .expect("the trunk has a height"),
+    )));
+    let leaves = lambertian(0.15, 0.4, 0.15);
+    for (k, (y, r)) in [(0.35, 0.45), (0.75, 0.35), (1.1, 0.25)]
+        .into_iter()
+        .enumerate()
+    {
+        tree.add(Rc::new(RefCell::new(
+            Cone::new(
+                Point3::from(0.0, y, 0.0),
+                Point3::from(0.0, y + 0.7 - 0.1 * k as f64, 0.0),
+                r,
+                0.0,
+                true,
+                leaves.clone(),
+            )
+            .expect("every layer of leaves has a height"),
+        )));
+    }
+    let tree: Rc<RefCell<dyn Hittable>> = Rc::new(RefCell::new(tree));
+
+    let n = 120;
+    let spacing = 0.8;
+    let mut instances: Vec<Rc<RefCell<dyn Hittable>>> = Vec::with_capacity(n * n);
+    for i in 0..n {
+        for j in 0..n {
+            let x = (i as f64 - 0.5 * n as f64 + rng.gen::<f64>()) * spacing;
+            let z = (j as f64 - 0.5 * n as f64 + rng.gen::<f64>()) * spacing;
+            let prototype = if rng.gen::<f64>() < 0.7 { &tree } else { &rock };
+            instances.push(Rc::new(RefCell::new(
+                Instance::new(prototype.clone())
+                    .with_rotation(Vec3::from(0.0, 1.0, 0.0), rng.gen_range(0.0..360.0))
+                    .with_scale(rng.gen_range(0.6..1.4))
+                    .with_offset(Vec3::from(x, 0.0, z)),
+            )));
+        }
+    }
+
+    setup(
+        |scene| {
+            scene.world.add(Rc::new(RefCell::new(Sphere::new(
+                Point3::from(0.0, -1000.0, 0.0),
+                1000.0,
+                lambertian(0.45, 0.5, 0.3),
+            ))));
+            scene
+                .world
+                .add(Rc::new(RefCell::new(BvhNode::new(instances))));
+            scene.lights.add(Rc::new(DirectionalLight::new(
+                Vec3::from(-0.5, -1.0, -0.3),
+                Color3::from(1.5, 1.4, 1.2),
+            )));
+        },
+        Camera::builder()
+            .fov(35.0)
+            .lookfrom(Point3::from(0.0, 6.0, 52.0))
+            .lookat(Point3::from(0.0, 0.0, 20.0)),
+    )
+}
@@ -0,0 +1,91 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    count, time_bvh_build, Aabb, Counter, HitRecord, Hittable, Interval, Json, MaterialVisitor,
+    Ray, SceneWriter,
+};
+
+/// Bounding volume hierarchy node. Children are split at the median
+/// centroid along the longest axis of their common bounds.
+pub struct BvhNode {
+    left: Rc<RefCell<dyn Hittable>>,
+    right: Rc<RefCell<dyn Hittable>>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    /// Builds a hierarchy over `objects`, which must not be empty.
+    pub fn new(objects: Vec<Rc<RefCell<dyn Hittable>>>) -> BvhNode {
+        time_bvh_build(|| BvhNode::build(objects))
+    }
+
+    fn build(mut objects: Vec<Rc<RefCell<dyn Hittable>>>) -> BvhNode {
+        assert!(!objects.is_empty(), "BvhNode needs at least one object");
+        let bbox = objects.iter().fold(Aabb::new(), |acc, o| {
+            Aabb::from_boxes(&acc, &o.borrow().bounding_box())
+        });
+        if objects.len() == 1 {
+            let only = objects.pop().unwrap();
+            return BvhNode {
+                left: only.clone(),
+                right: only,
+                bbox,
+            };
+        }
+        if objects.len() == 2 {
+            let right = objects.pop().unwrap();
+            let left = objects.pop().unwrap();
+            return BvhNode { left, right, bbox };
+        }
+
+        let axis = bbox.longest_axis();
+        let key = |o: &Rc<RefCell<dyn Hittable>>| o.borrow().bounding_box().centroid()[axis];
+        objects.sort_by(|a, b| key(a).total_cmp(&key(b)));
+        let upper = objects.split_off(objects.len() / 2);
+        BvhNode {
+            left: Rc::new(RefCell::new(BvhNode::build(objects))),
+            right: Rc::new(RefCell::new(BvhNode::build(upper))),
+            bbox,
+        }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        count(Counter::BvhNodesVisited);
+        if !self.bbox.hit(r, ray_root.clone()) {
+            return false;
+        }
+        let hit_left = self.left.borrow().hit(r, ray_root.clone(), rec);
+        let closest = if hit_left { rec.t } else { ray_root.max };
+        let hit_right = !Rc::ptr_eq(&self.left, &self.right)
+            && self
+                .right
+                .borrow()
+                .hit(r, Interval::from(ray_root.min, closest), rec);
+        hit_left || hit_right
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    /// Saved as its two children, which `BvhNode::new` pairs up again in
+    /// the same order.
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        let mut objects = vec![out.object(&self.left)?];
+        if !Rc::ptr_eq(&self.left, &self.right) {
+            objects.push(out.object(&self.right)?);
+        }
+        Some(Json::object([
+            ("type", "bvh".into()),
+            ("objects", objects.into()),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        self.left.borrow().for_each_material(f);
+        self.right.borrow().for_each_material(f);
+    }
+}
@@ -1,148 +1,785 @@
+use std::cmp;
+use std::fmt;
+use std::ops::Range;
+use std::time::Instant;
+
 use crate::{
-    cmp, cross, deg2rad, rand_circle, rand_norm, rand_outside, rand_unit_vector, unit_vector,
-    write_clr, Color3, HitRecord, Hittable, Interval, Point3, Ray, Vec3, INFINTY,
+    count, cross, deg2rad, dot, unit_vector, AovBuffer, AovSample, Aovs, Aperture, Checkpoint,
+    Color3, Counter, Film, Filter, Frame, HitRecord, Hittable, Image, Interval, Json, LightList,
+    Lobe, Point3, Projection, Ray, RenderCounters, RenderError, RenderProgress, RenderSettings,
+    Sampler, SamplerKind, Scene, SceneWriter, ToneMap, Vec3, INFINTY,
 };
 
 pub struct Camera {
-    aspect_ratio: f64,
     w: u64,
     h: u64,
     center: Point3,
     pixel00: Point3,
-    pixelDeltau: Vec3,
-    pixelDeltav: Vec3,
-    samplesPerPixel: u64,
-    pixelSamplesScale: f64,
-    maxDepth: u32,
+    pixel_delta_u: Vec3,
+    pixel_delta_v: Vec3,
+    samples_per_pixel: u64,
+    max_depth: u32,
     fov: f64,
-    w_a: Vec3,
     u_a: Vec3,
     v_a: Vec3,
-    defocus_angle: f64,
+    w_a: Vec3,
     focus_dist: f64,
-    defocusRadiusU: Vec3,
-    defocusRadiusV: Vec3,
+    lens_radius: f64,
+    aperture: Aperture,
+    projection: Projection,
+    film: Film,
+    filter: Filter,
+    sampler: SamplerKind,
+    seed: u64,
 }
 
 impl Camera {
-    pub fn render(&self, world: &mut (impl Hittable)) {
-        print!("P3\n{} {}\n255\n", self.w, self.h);
+    /// Renders into a linear float image; see `render` for the public entry.
+    pub fn render(&self, scene: &Scene, settings: &RenderSettings) -> Result<Image, RenderError> {
+        self.render_passes(scene, settings, None)
+    }
+
+    /// Renders the beauty image together with its AOVs.
+    pub fn render_aovs(
+        &self,
+        scene: &Scene,
+        settings: &RenderSettings,
+    ) -> Result<(Image, Aovs), RenderError> {
+        let mut aovs = AovBuffer::new(self.w, self.h, self.filter);
+        let image = self.render_passes(scene, settings, Some(&mut aovs))?;
+        Ok((image, aovs.resolve()))
+    }
+
+    fn render_passes(
+        &self,
+        scene: &Scene,
+        settings: &RenderSettings,
+        mut aovs: Option<&mut AovBuffer>,
+    ) -> Result<Image, RenderError> {
+        let mut state = match settings.resume() {
+            // AOVs are not part of checkpoints, so their renders cannot
+            // pick one up.
+            Some(_) if aovs.is_some() => return Err(RenderError::ResumeAovs),
+            Some(checkpoint) => {
+                self.check_checkpoint(checkpoint)
+                    .map_err(RenderError::Checkpoint)?;
+                checkpoint.clone()
+            }
+            None => self.checkpoint(),
+        };
+        let mut samplers = state.samplers(self.samples_per_pixel);
+        // Drop whatever was traced before, e.g. by autofocus.
+        RenderCounters::take();
+        let start = Instant::now();
+        let mut saved = start;
+        let samples_total = state
+            .row_samples
+            .iter()
+            .map(|n| self.samples_per_pixel.saturating_sub(*n) * self.w)
+            .sum();
+        let mut samples_done = 0;
         for i in 0..self.h {
-            eprintln!("REMAINING LINES === {}", self.h - i);
-            for j in 0..self.w {
-                // eprintln!("   REMAINING PIX === {}", self.w - j);
-                let mut clr = Color3::new();
-                for s in 0..self.samplesPerPixel {
-                    let r = self.get_ray(i, j);
-                    let pixelClr: Color3 = self.ray_color(r, self.maxDepth, world);
-                    clr += pixelClr;
+            if settings.is_cancelled() {
+                break;
+            }
+            let first = state.row_samples[i as usize];
+            if first >= self.samples_per_pixel {
+                continue;
+            }
+            self.render_row(
+                scene,
+                i,
+                first..self.samples_per_pixel,
+                &mut samplers,
+                &mut state,
+                aovs.as_deref_mut(),
+            );
+            state.row_samples[i as usize] = self.samples_per_pixel;
+            samples_done += (self.samples_per_pixel - first) * self.w;
+            if samples_done < samples_total {
+                settings.report(RenderProgress {
+                    samples_done,
+                    samples_total,
+                    elapsed: start.elapsed(),
+                    finished: false,
+                });
+                if aovs.is_none() {
+                    saved = settings.save_checkpoint(&state, saved, false);
+                }
+            }
+        }
+        settings.report(RenderProgress {
+            samples_done,
+            samples_total,
+            elapsed: start.elapsed(),
+            finished: true,
+        });
+        if aovs.is_none() {
+            settings.save_checkpoint(&state, saved, true);
+        }
+        settings.record(RenderCounters::take(), start.elapsed());
+        Ok(state.image())
+    }
+
+    /// Traces samples `samples` of every pixel in row `i` into `state`,
+    /// taking sample `s` from `samplers[s / state.period]`, as returned by
+    /// `Checkpoint::samplers`. The row count in `state` is left to the
+    /// caller.
+    pub fn render_row(
+        &self,
+        scene: &Scene,
+        i: u64,
+        samples: Range<u64>,
+        samplers: &mut [Box<dyn Sampler>],
+        state: &mut Checkpoint,
+        mut aovs: Option<&mut AovBuffer>,
+    ) {
+        let world = &scene.world;
+        let lights = &scene.lights;
+        for j in 0..self.w {
+            for s in samples.clone() {
+                let sampler = &mut samplers[(s / state.period) as usize];
+                sampler.start_pixel_sample((j, i), s);
+                let offset = self.sample_square(sampler.as_mut());
+                let mut aov = AovSample::default();
+                let aov_out = if aovs.is_some() { Some(&mut aov) } else { None };
+                let pixel_clr = match self.get_ray(i, j, offset, sampler.as_mut()) {
+                    Some(r) => {
+                        count(Counter::PrimaryRays);
+                        self.trace(r, self.max_depth, world, lights, sampler.as_mut(), aov_out)
+                    }
+                    None => Color3::new(),
+                };
+                let (x, y) = (j as f64 + 0.5 + offset.x(), i as f64 + 0.5 + offset.y());
+                state.buffer.splat(x, y, pixel_clr);
+                if let Some(aovs) = aovs.as_deref_mut() {
+                    let material_id = aov.material.as_ref().map_or(0, |m| world.material_id(m));
+                    aovs.add(j, i, x, y, &aov, material_id);
                 }
-                write_clr(clr * self.pixelSamplesScale, false);
             }
         }
     }
 
-    pub fn get_ray(&self, i: u64, j: u64) -> Ray {
-        let offset = self.sample_square();
-        let pixelCenter = self.pixel00
-            + ((j as f64 + offset.x()) * self.pixelDeltau)
-            + ((i as f64 + offset.y()) * self.pixelDeltav);
+    pub fn samples_per_pixel(&self) -> u64 {
+        self.samples_per_pixel
+    }
+
+    /// Render state before the first sample, with this camera's settings.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(
+            self.w,
+            self.h,
+            self.filter,
+            self.sampler,
+            self.seed,
+            self.samples_per_pixel,
+        )
+    }
+
+    /// Whether `checkpoint` was rendered with this image size, filter and
+    /// sampler, so that `RenderSettings::with_resume` can continue it.
+    pub fn check_checkpoint(&self, checkpoint: &Checkpoint) -> Result<(), String> {
+        if (checkpoint.w, checkpoint.h) != (self.w, self.h) {
+            return Err(format!(
+                "checkpoint is {}x{}, the camera renders {}x{}",
+                checkpoint.w, checkpoint.h, self.w, self.h
+            ));
+        }
+        if checkpoint.filter.to_json() != self.filter.to_json() {
+            return Err("checkpoint was rendered with a different filter".to_string());
+        }
+        if checkpoint.sampler.name() != self.sampler.name() || checkpoint.seed != self.seed {
+            return Err(format!(
+                "checkpoint was rendered with the {} sampler and seed {}",
+                checkpoint.sampler.name(),
+                checkpoint.seed
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn film(&self) -> &Film {
+        &self.film
+    }
+
+    /// Moves the plane of focus onto the first surface seen through the
+    /// image center, keeping the lens size. Returns the new focus distance,
+    /// or `None` (leaving the camera untouched) if that ray escapes.
+    pub fn autofocus(&mut self, world: &impl Hittable) -> Option<f64> {
+        let r = Ray::from(self.center, -self.w_a);
+        let mut rec = HitRecord::new();
+        if !world.hit(r, Interval::from(0.001, INFINTY), &mut rec) {
+            return None;
+        }
+        self.focus_dist = rec.t;
+        self.update_viewport();
+        Some(rec.t)
+    }
+
+    /// Ray through pixel `(i, j)` displaced by `offset` pixels from its center,
+    /// or `None` where the projection does not cover the image.
+    pub fn get_ray(&self, i: u64, j: u64, offset: Vec3, sampler: &mut dyn Sampler) -> Option<Ray> {
+        let sx = (j as f64 + 0.5 + offset.x()) / self.w as f64;
+        let sy = (i as f64 + 0.5 + offset.y()) / self.h as f64;
+        let aspect = self.w as f64 / self.h as f64;
+        match self.projection {
+            Projection::Perspective => {}
+            Projection::Orthographic { height } => {
+                let org = self.center
+                    + ((sx - 0.5) * height * aspect) * self.u_a
+                    + ((0.5 - sy) * height) * self.v_a;
+                return Some(Ray::from(org, -self.w_a));
+            }
+            _ => {
+                let frame = Frame {
+                    forward: -self.w_a,
+                    right: self.u_a,
+                    up: self.v_a,
+                };
+                let dir = self.projection.direction(&frame, sx, sy, aspect)?;
+                return Some(Ray::from(self.center, dir));
+            }
+        }
+
+        let pixel_center = self.pixel00
+            + ((j as f64 + offset.x()) * self.pixel_delta_u)
+            + ((i as f64 + offset.y()) * self.pixel_delta_v);
 
         let mut org = self.center;
-        if self.defocus_angle > 0.0 {
-            org = self.defocus_lens_sample();
-        }
-        let rayDir = pixelCenter - org;
-        return Ray::from(self.center, rayDir);
-    }
-
-    fn defocus_lens_sample(&self) -> Vec3 {
-        let p = rand_circle();
-        // eprintln!("{:?} {:?}", self.defocusRadiusU, self.defocusRadiusV);
-        self.center + (p.x() * self.defocusRadiusU) + (p.y() * self.defocusRadiusV)
-    }
-
-    pub fn new(
-        aspect_ratio: f64,
-        w: u64,
-        samplesPerPixel: u64,
-        maxDepth: u32,
-        fov: f64,
-        lookfrom: Point3,
-        lookat: Point3,
-        vup: Vec3,
-        defocus_angle: f64,
-        focus_dist: f64,
-    ) -> Camera {
-        let h = cmp::max((w as f64 / aspect_ratio) as u64, 1);
-
-        let camCenter: Point3 = lookfrom;
-        // let focalLength = (lookfrom - lookat).length();
-        let w_a = unit_vector(lookfrom - lookat);
-        let u_a = unit_vector(cross(vup, w_a));
-        let v_a = cross(w_a, u_a);
-        let fov_angle = f64::tan(deg2rad(fov) / 2.0);
-        let vpHeight = 2.0 * focus_dist * fov_angle;
-        let vpWidth = vpHeight * (w as f64 / h as f64);
-        let vpu = vpWidth * u_a;
-        let vpv = vpHeight * (-v_a);
-
-        let pixelDeltau = vpu / (w as f64);
-        let pixelDeltav = vpv / (h as f64);
-        let vpUpperLeft = camCenter - (focus_dist * w_a) - (vpu / 2.0) - (vpv / 2.0);
-        let pixel00 = vpUpperLeft + 0.5 * (pixelDeltau + pixelDeltav);
-
-        let defocus_radius = focus_dist * f64::tan(deg2rad(defocus_angle / 2.0));
-        let defocusRadiusU = u_a * defocus_radius;
-        let defocusRadiusV = v_a * defocus_radius;
-
-        eprintln!("{camCenter:?} {defocusRadiusU:?} {defocusRadiusV:?} {focus_dist:?}");
-
-        Camera {
-            aspect_ratio,
-            w,
-            h,
-            center: camCenter,
-            pixel00,
-            pixelDeltau,
-            pixelDeltav,
-            samplesPerPixel,
-            pixelSamplesScale: 1.0 / (samplesPerPixel as f64),
-            maxDepth,
-            fov,
-            w_a,
-            u_a,
-            v_a,
-            defocus_angle,
-            focus_dist,
-            defocusRadiusU,
-            defocusRadiusV,
+        if self.lens_radius > 0.0 {
+            org = self.defocus_lens_sample(sampler);
         }
+        let ray_dir = pixel_center - org;
+        Some(Ray::from(org, ray_dir))
+    }
+
+    fn defocus_lens_sample(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let p = self.aperture.sample(sampler.get_2d()) * self.lens_radius;
+        self.center + (p.x() * self.u_a) + (p.y() * self.v_a)
+    }
+
+    pub fn builder() -> CameraBuilder {
+        CameraBuilder::new()
+    }
+
+    /// Places the viewport on the plane of focus.
+    fn update_viewport(&mut self) {
+        let fov_angle = f64::tan(deg2rad(self.fov) / 2.0);
+        let vp_height = 2.0 * self.focus_dist * fov_angle;
+        let vp_width = vp_height * (self.w as f64 / self.h as f64);
+        let vpu = vp_width * self.u_a;
+        let vpv = vp_height * (-self.v_a);
+
+        self.pixel_delta_u = vpu / (self.w as f64);
+        self.pixel_delta_v = vpv / (self.h as f64);
+        let vp_upper_left = self.center - (self.focus_dist * self.w_a) - (vpu / 2.0) - (vpv / 2.0);
+        self.pixel00 = vp_upper_left + 0.5 * (self.pixel_delta_u + self.pixel_delta_v);
+    }
+
+    pub fn ray_color(
+        &self,
+        r: Ray,
+        depth: u32,
+        world: &impl Hittable,
+        lights: &LightList,
+        sampler: &mut dyn Sampler,
+    ) -> Color3 {
+        self.trace(r, depth, world, lights, sampler, None)
     }
 
-    pub fn ray_color(&self, r: Ray, depth: u32, world: &mut impl Hittable) -> Color3 {
-        if depth <= 0 {
+    /// `ray_color` that also fills `aov` from the first hit, if given.
+    fn trace(
+        &self,
+        r: Ray,
+        depth: u32,
+        world: &impl Hittable,
+        lights: &LightList,
+        sampler: &mut dyn Sampler,
+        aov: Option<&mut AovSample>,
+    ) -> Color3 {
+        if depth == 0 {
             return Color3::new();
         }
         let mut rec = HitRecord::new();
         if world.hit(r, Interval::from(0.001, INFINTY), &mut rec) {
+            let mat = rec.mat.clone();
+            mat.borrow().perturb_normal(&mut rec);
+            let direct = self.direct_light(r, &rec, world, lights);
             let mut attenuation = Color3::new();
             let mut scattered = Ray::from(Point3::new(), Vec3::new());
-            if rec
-                .mat
-                .borrow_mut()
-                .scatter(r, &mut rec.clone(), &mut attenuation, &mut scattered)
+            let mut indirect = Color3::new();
+            if rec.mat.borrow().scatter(
+                r,
+                &mut rec.clone(),
+                &mut attenuation,
+                &mut scattered,
+                sampler,
+            ) && same_side(scattered.direction(), &rec)
             {
-                return attenuation * self.ray_color(scattered, depth - 1, world);
+                if depth > 1 {
+                    count(Counter::SecondaryRays);
+                }
+                indirect =
+                    attenuation * self.trace(scattered, depth - 1, world, lights, sampler, None);
             }
-            return Color3::new();
+            if let Some(aov) = aov {
+                let mat = rec.mat.borrow();
+                aov.hit = true;
+                aov.depth = self.view_depth(rec.p);
+                aov.normal = rec.normal;
+                aov.albedo = mat.albedo(&rec);
+                aov.position = rec.p;
+                aov.material = Some(rec.mat.clone());
+                aov.object_id = rec.object_id;
+                let mut lobe = mat.lobe();
+                if lobe == Lobe::Specular && dot(scattered.direction(), rec.normal) < 0.0 {
+                    lobe = Lobe::Transmission;
+                }
+                // Delta lights are only picked up by diffuse surfaces.
+                aov.lobes[Lobe::Diffuse as usize] += direct;
+                aov.lobes[lobe as usize] += indirect;
+            }
+            return direct + indirect;
         }
-        let uDir: Vec3 = unit_vector(r.direction());
-        let a = 0.5 * (uDir.y() + 1.0);
-        return (1.0 - a) * Color3::from(1.0, 1.0, 1.0) + a * Color3::from(0.5, 0.7, 1.0);
+        let u_dir: Vec3 = unit_vector(r.direction());
+        let a = 0.5 * (u_dir.y() + 1.0);
+        (1.0 - a) * Color3::from(1.0, 1.0, 1.0) + a * Color3::from(0.5, 0.7, 1.0)
     }
 
-    pub fn sample_square(&self) -> Vec3 {
-        Vec3::from(rand_norm() - 0.5, rand_norm() - 0.5, 0.0)
+    /// Depth of `p` along the view axis, or its distance from the camera for
+    /// panoramic projections that have no single view axis.
+    fn view_depth(&self, p: Point3) -> f64 {
+        match self.projection {
+            Projection::Perspective | Projection::Orthographic { .. } => {
+                dot(p - self.center, -self.w_a)
+            }
+            _ => (p - self.center).length(),
+        }
+    }
+
+    /// Sums the unoccluded contribution of every delta light at `rec.p`.
+    fn direct_light(
+        &self,
+        r: Ray,
+        rec: &HitRecord,
+        world: &impl Hittable,
+        lights: &LightList,
+    ) -> Color3 {
+        let mut clr = Color3::new();
+        for light in lights.iter() {
+            let Some(ls) = light.sample_li(rec.p) else {
+                continue;
+            };
+            // Lights behind the true surface must not leak through a
+            // shading normal that leans towards them.
+            if dot(ls.wi, rec.geometric_normal) <= 0.0 {
+                continue;
+            }
+            let f = rec.mat.borrow().eval(r, rec, ls.wi);
+            if f.near_zero() {
+                continue;
+            }
+            count(Counter::ShadowRays);
+            let shadow = Ray::from(rec.p, ls.wi);
+            let mut shadow_rec = HitRecord::new();
+            if world.hit(
+                shadow,
+                Interval::from(0.001, ls.dist - 0.001),
+                &mut shadow_rec,
+            ) {
+                continue;
+            }
+            clr += f * ls.radiance;
+        }
+        clr
+    }
+
+    /// Offset from the pixel center within `[-0.5, 0.5)` in both directions.
+    pub fn sample_square(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let (x, y) = sampler.get_2d();
+        Vec3::from(x - 0.5, y - 0.5, 0.0)
+    }
+}
+
+/// Whether `dir` leaves on the same side of the geometric and the shading
+/// normal. Directions caught between the two would pass through the surface.
+fn same_side(dir: Vec3, rec: &HitRecord) -> bool {
+    dot(dir, rec.geometric_normal) * dot(dir, rec.normal) > 0.0
+}
+
+/// Reasons `CameraBuilder::build` refuses a configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CameraError {
+    /// Width or height of zero pixels.
+    EmptyImage,
+    ZeroSamples,
+    /// `lookfrom` and `lookat` coincide, so there is no view direction.
+    NoViewDirection,
+    /// `vup` is zero or parallel to the view direction.
+    DegenerateVup,
+    InvalidFov(f64),
+    InvalidFocusDistance(f64),
+    InvalidDefocusAngle(f64),
+    InvalidLens {
+        focal_length: f64,
+        f_number: f64,
+    },
+    InvalidProjection(Projection),
+    InvalidFilterRadius(f64),
+    /// A polygonal aperture with fewer than three blades.
+    InvalidBlades(u32),
+}
+
+impl fmt::Display for CameraError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CameraError::EmptyImage => write!(f, "image width and height must be non-zero"),
+            CameraError::ZeroSamples => write!(f, "samples per pixel must be non-zero"),
+            CameraError::NoViewDirection => write!(f, "lookfrom and lookat are the same point"),
+            CameraError::DegenerateVup => {
+                write!(f, "vup is zero or parallel to the view direction")
+            }
+            CameraError::InvalidFov(fov) => {
+                write!(f, "vertical fov must be in (0, 180) degrees, got {fov}")
+            }
+            CameraError::InvalidFocusDistance(d) => {
+                write!(f, "focus distance must be positive, got {d}")
+            }
+            CameraError::InvalidDefocusAngle(a) => {
+                write!(f, "defocus angle must be in [0, 180) degrees, got {a}")
+            }
+            CameraError::InvalidLens {
+                focal_length,
+                f_number,
+            } => write!(
+                f,
+                "focal length and f-number must be positive, got {focal_length} and {f_number}"
+            ),
+            CameraError::InvalidProjection(p) => write!(f, "invalid projection parameters {p:?}"),
+            CameraError::InvalidFilterRadius(r) => {
+                write!(f, "filter radius must be positive, got {r}")
+            }
+            CameraError::InvalidBlades(n) => {
+                write!(f, "an aperture needs at least 3 blades, got {n}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CameraError {}
+
+/// Named, validated construction of a `Camera`. Unset options keep the
+/// defaults from `CameraBuilder::new`.
+#[derive(Clone)]
+pub struct CameraBuilder {
+    width: u64,
+    height: Option<u64>,
+    aspect_ratio: f64,
+    samples_per_pixel: u64,
+    max_depth: u32,
+    fov: f64,
+    lookfrom: Point3,
+    lookat: Point3,
+    vup: Vec3,
+    defocus_angle: f64,
+    focus_dist: f64,
+    lens: Option<(f64, f64)>,
+    aperture: Aperture,
+    projection: Projection,
+    film: Film,
+    filter: Filter,
+    sampler: SamplerKind,
+    seed: u64,
+}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CameraBuilder {
+    /// A 400 pixel wide 16:9 pinhole camera at the origin looking down -z.
+    pub fn new() -> CameraBuilder {
+        CameraBuilder {
+            width: 400,
+            height: None,
+            aspect_ratio: 16.0 / 9.0,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            fov: 90.0,
+            lookfrom: Point3::from(0.0, 0.0, 0.0),
+            lookat: Point3::from(0.0, 0.0, -1.0),
+            vup: Vec3::from(0.0, 1.0, 0.0),
+            defocus_angle: 0.0,
+            focus_dist: 10.0,
+            lens: None,
+            aperture: Aperture::Circle,
+            projection: Projection::Perspective,
+            film: Film::new(),
+            filter: Filter::default(),
+            sampler: SamplerKind::Stratified,
+            seed: 0,
+        }
+    }
+
+    pub fn width(mut self, width: u64) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Explicit image height. Without it the height follows `aspect_ratio`.
+    pub fn height(mut self, height: u64) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Width over height, used only when no explicit height is given.
+    pub fn aspect_ratio(mut self, aspect_ratio: f64) -> Self {
+        self.aspect_ratio = aspect_ratio;
+        self
+    }
+
+    pub fn samples_per_pixel(mut self, samples_per_pixel: u64) -> Self {
+        self.samples_per_pixel = samples_per_pixel;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Vertical field of view in degrees.
+    pub fn fov(mut self, fov: f64) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    pub fn lookfrom(mut self, lookfrom: Point3) -> Self {
+        self.lookfrom = lookfrom;
+        self
+    }
+
+    pub fn lookat(mut self, lookat: Point3) -> Self {
+        self.lookat = lookat;
+        self
+    }
+
+    pub fn vup(mut self, vup: Vec3) -> Self {
+        self.vup = vup;
+        self
+    }
+
+    /// Cone angle in degrees subtended by the lens at the focus plane.
+    pub fn defocus_angle(mut self, defocus_angle: f64) -> Self {
+        self.defocus_angle = defocus_angle;
+        self
+    }
+
+    pub fn focus_dist(mut self, focus_dist: f64) -> Self {
+        self.focus_dist = focus_dist;
+        self
+    }
+
+    /// Sizes the lens from a focal length (in scene units) and an f-number
+    /// instead of `defocus_angle`.
+    pub fn lens(mut self, focal_length: f64, f_number: f64) -> Self {
+        self.lens = Some((focal_length, f_number));
+        self
+    }
+
+    pub fn aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    pub fn projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    pub fn film(mut self, film: Film) -> Self {
+        self.film = film;
+        self
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn sampler(mut self, sampler: SamplerKind, seed: u64) -> Self {
+        self.sampler = sampler;
+        self.seed = seed;
+        self
+    }
+
+    pub fn build(self) -> Result<Camera, CameraError> {
+        let h = match self.height {
+            Some(h) => h,
+            None if self.aspect_ratio > 0.0 => {
+                cmp::max((self.width as f64 / self.aspect_ratio) as u64, 1)
+            }
+            None => 0,
+        };
+        if self.width == 0 || h == 0 {
+            return Err(CameraError::EmptyImage);
+        }
+        if self.samples_per_pixel == 0 {
+            return Err(CameraError::ZeroSamples);
+        }
+        let view = self.lookfrom - self.lookat;
+        if view.length().is_nan() || view.length() <= 1e-12 {
+            return Err(CameraError::NoViewDirection);
+        }
+        let w_a = unit_vector(view);
+        let vup_len = self.vup.length();
+        if vup_len.is_nan() || vup_len <= 1e-12 || cross(self.vup / vup_len, w_a).length() < 1e-9 {
+            return Err(CameraError::DegenerateVup);
+        }
+        if !(self.fov > 0.0 && self.fov < 180.0) {
+            return Err(CameraError::InvalidFov(self.fov));
+        }
+        if !(self.focus_dist > 0.0 && self.focus_dist.is_finite()) {
+            return Err(CameraError::InvalidFocusDistance(self.focus_dist));
+        }
+        if !(self.defocus_angle >= 0.0 && self.defocus_angle < 180.0) {
+            return Err(CameraError::InvalidDefocusAngle(self.defocus_angle));
+        }
+        let valid_projection = match self.projection {
+            Projection::Orthographic { height } => height > 0.0 && height.is_finite(),
+            Projection::Fisheye { fov } => fov > 0.0 && fov <= 360.0,
+            _ => true,
+        };
+        if !valid_projection {
+            return Err(CameraError::InvalidProjection(self.projection));
+        }
+        if let Aperture::Polygon { blades, .. } = self.aperture {
+            if blades < 3 {
+                return Err(CameraError::InvalidBlades(blades));
+            }
+        }
+        let filter_radius = self.filter.radius();
+        if !(filter_radius > 0.0 && filter_radius.is_finite()) {
+            return Err(CameraError::InvalidFilterRadius(filter_radius));
+        }
+        let lens_radius = match self.lens {
+            Some((focal_length, f_number)) => {
+                if !(focal_length > 0.0 && f_number > 0.0) {
+                    return Err(CameraError::InvalidLens {
+                        focal_length,
+                        f_number,
+                    });
+                }
+                focal_length / (2.0 * f_number)
+            }
+            None => self.focus_dist * f64::tan(deg2rad(self.defocus_angle / 2.0)),
+        };
+
+        let u_a = unit_vector(cross(self.vup, w_a));
+        let v_a = cross(w_a, u_a);
+
+        let mut camera = Camera {
+            w: self.width,
+            h,
+            center: self.lookfrom,
+            pixel00: Point3::new(),
+            pixel_delta_u: Vec3::new(),
+            pixel_delta_v: Vec3::new(),
+            samples_per_pixel: self.samples_per_pixel,
+            max_depth: self.max_depth,
+            fov: self.fov,
+            u_a,
+            v_a,
+            w_a,
+            focus_dist: self.focus_dist,
+            lens_radius,
+            aperture: self.aperture,
+            projection: self.projection,
+            film: self.film,
+            filter: self.filter,
+            sampler: self.sampler,
+            seed: self.seed,
+        };
+        camera.update_viewport();
+        Ok(camera)
+    }
+
+    /// Scene file description of every setting, see `SceneWriter`. Image
+    /// apertures are not saved; they fall back to a circle with a warning.
+    pub fn to_json(&self, out: &mut SceneWriter) -> Json {
+        let mut members = vec![
+            ("width", self.width.into()),
+            ("aspect_ratio", self.aspect_ratio.into()),
+            ("samples_per_pixel", self.samples_per_pixel.into()),
+            ("max_depth", self.max_depth.into()),
+            ("fov", self.fov.into()),
+            ("lookfrom", self.lookfrom.into()),
+            ("lookat", self.lookat.into()),
+            ("vup", self.vup.into()),
+            ("defocus_angle", self.defocus_angle.into()),
+            ("focus_dist", self.focus_dist.into()),
+        ];
+        if let Some(height) = self.height {
+            members.push(("height", height.into()));
+        }
+        if let Some((focal_length, f_number)) = self.lens {
+            members.push((
+                "lens",
+                Json::object([
+                    ("focal_length", focal_length.into()),
+                    ("f_number", f_number.into()),
+                ]),
+            ));
+        }
+        let aperture = match self.aperture {
+            Aperture::Circle => Json::object([("type", "circle".into())]),
+            Aperture::Polygon { blades, rotation } => Json::object([
+                ("type", "polygon".into()),
+                ("blades", blades.into()),
+                ("rotation", rotation.into()),
+            ]),
+            Aperture::Mask(_) => {
+                out.warn("image apertures cannot be saved, using a circle");
+                Json::object([("type", "circle".into())])
+            }
+        };
+        let projection = match self.projection {
+            Projection::Perspective => Json::object([("type", "perspective".into())]),
+            Projection::Orthographic { height } => {
+                Json::object([("type", "orthographic".into()), ("height", height.into())])
+            }
+            Projection::Fisheye { fov } => {
+                Json::object([("type", "fisheye".into()), ("fov", fov.into())])
+            }
+            Projection::Equirectangular => Json::object([("type", "equirect".into())]),
+            Projection::CubeMap => Json::object([("type", "cubemap".into())]),
+        };
+        let tone_map = match self.film.tone_map {
+            ToneMap::Clamp => Json::object([("type", "clamp".into())]),
+            ToneMap::Reinhard => Json::object([("type", "reinhard".into())]),
+            ToneMap::ExtendedReinhard { white } => Json::object([
+                ("type", "extended-reinhard".into()),
+                ("white", white.into()),
+            ]),
+            ToneMap::AcesFilmic => Json::object([("type", "aces".into())]),
+            ToneMap::AgX => Json::object([("type", "agx".into())]),
+        };
+        members.extend([
+            ("aperture", aperture),
+            ("projection", projection),
+            (
+                "film",
+                Json::object([
+                    ("exposure", self.film.exposure.into()),
+                    ("tone_map", tone_map),
+                ]),
+            ),
+            ("filter", self.filter.to_json()),
+            ("sampler", self.sampler.name().into()),
+            ("seed", self.seed.into()),
+        ]);
+        Json::object(members)
     }
 }
@@ -0,0 +1,111 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    around_axis, count, dot, solve_quadratic, unit_vector, Aabb, Counter, HitRecord, Hittable,
+    Interval, Json, Material, MaterialVisitor, Onb, Point3, Ray, SceneWriter, Vec3, PI,
+};
+
+/// Cylinder between `a` and `b` closed by hemispheres, i.e. every point
+/// within `radius` of the segment. `u` goes around the axis and `v` along
+/// it, including the hemispheres.
+pub struct Capsule {
+    a: Point3,
+    frame: Onb,
+    length: f64,
+    radius: f64,
+    mat: Rc<RefCell<dyn Material>>,
+}
+
+impl Capsule {
+    pub fn new(a: Point3, b: Point3, radius: f64, mat: Rc<RefCell<dyn Material>>) -> Capsule {
+        Capsule {
+            a,
+            frame: Onb::from_w(b - a),
+            length: (b - a).length(),
+            radius,
+            mat,
+        }
+    }
+}
+
+impl Hittable for Capsule {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        count(Counter::IntersectionTests);
+        let o = self.frame.to_local(r.origin() - self.a);
+        let d = self.frame.to_local(r.direction());
+        let (h, rad) = (self.length, self.radius);
+        let mut best: Option<(f64, Vec3)> = None;
+        let mut consider = |t: f64, n: Vec3| {
+            if ray_root.surrounds(t) && best.as_ref().is_none_or(|b| t < b.0) {
+                best = Some((t, n));
+            }
+        };
+
+        let a = d.x() * d.x() + d.y() * d.y();
+        let b = 2.0 * (o.x() * d.x() + o.y() * d.y());
+        let c = o.x() * o.x() + o.y() * o.y() - rad * rad;
+        for t in solve_quadratic(a, b, c) {
+            let p = o + t * d;
+            if (0.0..=h).contains(&p.z()) {
+                consider(t, Vec3::from(p.x(), p.y(), 0.0));
+            }
+        }
+        for (z, below) in [(0.0, true), (h, false)] {
+            let oc = o - Vec3::from(0.0, 0.0, z);
+            let roots = solve_quadratic(
+                d.length_squared(),
+                2.0 * dot(oc, d),
+                oc.length_squared() - rad * rad,
+            );
+            for t in roots {
+                let p = oc + t * d;
+                if (p.z() <= 0.0) == below {
+                    consider(t, p);
+                }
+            }
+        }
+
+        let Some((t, n)) = best else {
+            return false;
+        };
+        let p = o + t * d;
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_face_normal(r, unit_vector(self.frame.to_world(n)));
+        rec.u = around_axis(p);
+        rec.v = ((p.z() + rad) / (h + 2.0 * rad)).clamp(0.0, 1.0);
+        // Along the axis the radius stays `rad` on the side and follows the
+        // circle `rho² + (z - z_cap)² = rad²` on the hemispheres.
+        let rho = (p.x() * p.x() + p.y() * p.y()).sqrt();
+        let slope = -(p.z() - p.z().clamp(0.0, h)) / rho;
+        let dpdu = 2.0 * PI * Vec3::from(-p.y(), p.x(), 0.0);
+        let dpdv = (h + 2.0 * rad) * Vec3::from(slope * p.x() / rho, slope * p.y() / rho, 1.0);
+        rec.set_tangents(self.frame.to_world(dpdu), self.frame.to_world(dpdv));
+        rec.mat = self.mat.clone();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let b = self.a + self.length * self.frame.w;
+        let r = Vec3::from(self.radius, self.radius, self.radius);
+        Aabb::from_boxes(
+            &Aabb::from(self.a - r, self.a + r),
+            &Aabb::from(b - r, b + r),
+        )
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "capsule".into()),
+            ("a", self.a.into()),
+            ("b", (self.a + self.length * self.frame.w).into()),
+            ("radius", self.radius.into()),
+            ("material", out.material(&self.mat)),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        f(&self.mat);
+    }
+}
@@ -0,0 +1,161 @@
+use std::fs;
+use std::io;
+
+use crate::{hash, Filter, Image, Json, SampleBuffer, Sampler, SamplerKind};
+
+const FORMAT: &str = "raytracer-checkpoint";
+const VERSION: usize = 1;
+
+/// Saved state of a render in progress: the float accumulation buffer and
+/// how many samples per pixel each row holds. Samplers derive every random
+/// number from the pixel, the sample index and the seed, so together with
+/// the sampler settings this is all it takes to continue the render, or to
+/// add samples to a finished one, exactly where it stopped.
+///
+/// The scene is not part of the checkpoint; resuming with a different
+/// scene or view silently mixes the two.
+#[derive(Clone)]
+pub struct Checkpoint {
+    pub w: u64,
+    pub h: u64,
+    pub filter: Filter,
+    pub sampler: SamplerKind,
+    pub seed: u64,
+    /// Samples per pixel each sampler stratifies over: the target of the
+    /// first render. Further batches of this size reuse the sampler with
+    /// fresh seeds.
+    pub period: u64,
+    /// Samples per pixel finished in each row.
+    pub row_samples: Vec<u64>,
+    pub buffer: SampleBuffer,
+}
+
+impl Checkpoint {
+    /// State of a render that has not started.
+    pub fn new(
+        w: u64,
+        h: u64,
+        filter: Filter,
+        sampler: SamplerKind,
+        seed: u64,
+        period: u64,
+    ) -> Self {
+        Checkpoint {
+            w,
+            h,
+            filter,
+            sampler,
+            seed,
+            period: period.max(1),
+            row_samples: vec![0; h as usize],
+            buffer: SampleBuffer::new(w, h, filter),
+        }
+    }
+
+    /// One sampler per batch of `period` samples, enough to reach
+    /// `samples_per_pixel`. The first batch uses the seed itself, so a
+    /// render that was never resumed matches one without checkpoints.
+    pub fn samplers(&self, samples_per_pixel: u64) -> Vec<Box<dyn Sampler>> {
+        (0..samples_per_pixel.div_ceil(self.period).max(1))
+            .map(|batch| {
+                let seed = if batch == 0 {
+                    self.seed
+                } else {
+                    hash(&[self.seed, batch])
+                };
+                self.sampler.build(self.period, seed)
+            })
+            .collect()
+    }
+
+    /// The image accumulated so far, as linear radiance.
+    pub fn image(&self) -> Image {
+        self.buffer.resolve()
+    }
+
+    /// Writes a JSON header line followed by the raw buffer. The file is
+    /// replaced atomically, so a crash while saving keeps the previous one.
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let header = Json::object([
+            ("format", FORMAT.into()),
+            ("version", VERSION.into()),
+            ("width", self.w.into()),
+            ("height", self.h.into()),
+            ("filter", self.filter.to_json()),
+            ("sampler", self.sampler.name().into()),
+            ("seed", self.seed.into()),
+            ("period", self.period.into()),
+            (
+                "row_samples",
+                self.row_samples
+                    .iter()
+                    .map(|n| Json::from(*n))
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+        ]);
+        let mut out = format!("{header}\n").into_bytes();
+        out.extend_from_slice(&self.buffer.to_bytes());
+        let partial = format!("{path}.partial");
+        fs::write(&partial, out)?;
+        fs::rename(&partial, path)
+    }
+
+    /// Reads a file written by `write`.
+    pub fn load(path: &str) -> io::Result<Checkpoint> {
+        let bytes = fs::read(path)?;
+        let invalid = |what: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{path} is not a valid checkpoint: {what}"),
+            )
+        };
+        let split = bytes
+            .iter()
+            .position(|b| *b == b'\n')
+            .ok_or_else(|| invalid("no header"))?;
+        let header = std::str::from_utf8(&bytes[..split]).map_err(|_| invalid("bad header"))?;
+        let header = Json::parse(header).map_err(|e| invalid(&e))?;
+        if header.get("format").and_then(Json::as_str) != Some(FORMAT) {
+            return Err(invalid("wrong format"));
+        }
+        match header.get("version").and_then(Json::as_usize) {
+            Some(v) if v <= VERSION => {}
+            _ => return Err(invalid("unsupported version")),
+        }
+        let get_u64 = |key: &str| {
+            header
+                .get(key)
+                .and_then(Json::as_u64)
+                .ok_or_else(|| invalid(&format!("bad or missing `{key}`")))
+        };
+        let (w, h) = (get_u64("width")?, get_u64("height")?);
+        let filter = header
+            .get("filter")
+            .and_then(Filter::from_json)
+            .ok_or_else(|| invalid("bad or missing `filter`"))?;
+        let sampler = header
+            .get("sampler")
+            .and_then(Json::as_str)
+            .and_then(SamplerKind::from)
+            .ok_or_else(|| invalid("bad or missing `sampler`"))?;
+        let row_samples: Vec<u64> = header
+            .get("row_samples")
+            .and_then(Json::as_array)
+            .and_then(|rows| rows.iter().map(Json::as_u64).collect::<Option<_>>())
+            .filter(|rows: &Vec<u64>| rows.len() as u64 == h)
+            .ok_or_else(|| invalid("bad or missing `row_samples`"))?;
+        let buffer = SampleBuffer::from_bytes(w, h, filter, &bytes[split + 1..])
+            .ok_or_else(|| invalid("truncated buffer"))?;
+        Ok(Checkpoint {
+            w,
+            h,
+            filter,
+            sampler,
+            seed: get_u64("seed")?,
+            period: get_u64("period")?.max(1),
+            row_samples,
+            buffer,
+        })
+    }
+}
@@ -1,26 +1,35 @@
 use crate::intervals::Interval;
 pub use crate::vec3::Vec3 as Color3;
 
+/// The sRGB opto-electronic transfer function (linear to encoded).
 #[inline(always)]
-pub fn lin2gamma(p: f64) -> f64 {
-    if p > 0.0 {
-        return p.sqrt();
+pub fn linear_to_srgb(p: f64) -> f64 {
+    if p <= 0.0 {
+        0.0
+    } else if p <= 0.0031308 {
+        12.92 * p
     } else {
-        return 0.0;
+        1.055 * p.powf(1.0 / 2.4) - 0.055
     }
 }
 
+/// Inverse of `linear_to_srgb`, for decoding color textures.
+pub fn srgb_to_linear(p: f64) -> f64 {
+    if p <= 0.0 {
+        0.0
+    } else if p <= 0.04045 {
+        p / 12.92
+    } else {
+        ((p + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Quantizes an already display-encoded pixel (see `Film::develop`) to 8 bits.
 pub fn write_clr(pixel: Color3, stderr: bool) {
     let intensity: Interval = Interval::from(0.0, 0.999);
-    let mut r = pixel.x();
-    let mut g = pixel.y();
-    let mut b = pixel.z();
-    r = lin2gamma(r);
-    g = lin2gamma(g);
-    b = lin2gamma(b);
-    r = (intensity.clamp(r) * (255.0 as f64));
-    g = (intensity.clamp(g) * (255.0 as f64));
-    b = (intensity.clamp(b) * (255.0 as f64));
+    let r = (intensity.clamp(pixel.x()) * 256.0) as u8;
+    let g = (intensity.clamp(pixel.y()) * 256.0) as u8;
+    let b = (intensity.clamp(pixel.z()) * 256.0) as u8;
 
     if stderr {
         eprintln!("{r} {g} {b}");
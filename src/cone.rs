@@ -0,0 +1,208 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    count, solve_quadratic, unit_vector, Aabb, Counter, HitRecord, Hittable, Interval, Json,
+    Material, MaterialVisitor, Onb, Point3, Ray, SceneWriter, Vec3, PI,
+};
+
+/// Truncated cone between two disks on a common axis. A zero radius at
+/// either end gives a pointed cone, equal radii a cylinder.
+///
+/// UVs: `u` goes around the axis, `v` from `base` to `top` on the side and
+/// across the disk on the caps.
+pub struct Cone {
+    base: Point3,
+    frame: Onb,
+    height: f64,
+    base_radius: f64,
+    top_radius: f64,
+    capped: bool,
+    mat: Rc<RefCell<dyn Material>>,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point3,
+        top: Point3,
+        base_radius: f64,
+        top_radius: f64,
+        capped: bool,
+        mat: Rc<RefCell<dyn Material>>,
+    ) -> Result<Cone, String> {
+        let height = (top - base).length();
+        if !(height > 0.0 && height.is_finite()) {
+            return Err("a cone needs distinct, finite base and top points".to_string());
+        }
+        Ok(Cone {
+            base,
+            frame: Onb::from_w(top - base),
+            height,
+            base_radius,
+            top_radius,
+            capped,
+            mat,
+        })
+    }
+
+    /// Closest hit in the local frame.
+    fn local_hit(&self, o: Vec3, d: Vec3, ray_root: &Interval) -> Option<LocalHit> {
+        let (h, r0) = (self.height, self.base_radius);
+        let k = (self.top_radius - r0) / h;
+        let mut best: Option<LocalHit> = None;
+
+        // Side: x² + y² = (r0 + k z)².
+        let rz = r0 + k * o.z();
+        let a = d.x() * d.x() + d.y() * d.y() - k * k * d.z() * d.z();
+        let b = 2.0 * (o.x() * d.x() + o.y() * d.y() - k * rz * d.z());
+        let c = o.x() * o.x() + o.y() * o.y() - rz * rz;
+        for t in solve_quadratic(a, b, c) {
+            let p = o + t * d;
+            if !ray_root.surrounds(t) || p.z() < 0.0 || p.z() > h {
+                continue;
+            }
+            let radius = r0 + k * p.z();
+            best = Some(LocalHit {
+                t,
+                n: Vec3::from(p.x(), p.y(), -k * radius),
+                u: around_axis(p),
+                v: p.z() / h,
+                dpdu: 2.0 * PI * Vec3::from(-p.y(), p.x(), 0.0),
+                dpdv: h * Vec3::from(k * p.x() / radius, k * p.y() / radius, 1.0),
+            });
+            break;
+        }
+
+        if self.capped {
+            for (z, radius, nz) in [(0.0, r0, -1.0), (h, self.top_radius, 1.0)] {
+                if radius <= 0.0 || d.z() == 0.0 {
+                    continue;
+                }
+                let t = (z - o.z()) / d.z();
+                let p = o + t * d;
+                if !ray_root.surrounds(t)
+                    || best.as_ref().is_some_and(|b| b.t <= t)
+                    || p.x() * p.x() + p.y() * p.y() > radius * radius
+                {
+                    continue;
+                }
+                let (u, v) = disk_uv(p, radius);
+                best = Some(LocalHit {
+                    t,
+                    n: Vec3::from(0.0, 0.0, nz),
+                    u,
+                    v,
+                    dpdu: Vec3::from(2.0 * radius, 0.0, 0.0),
+                    dpdv: Vec3::from(0.0, 2.0 * radius, 0.0),
+                });
+            }
+        }
+        best
+    }
+}
+
+/// Intersection in the cone's local frame.
+struct LocalHit {
+    t: f64,
+    n: Vec3,
+    u: f64,
+    v: f64,
+    dpdu: Vec3,
+    dpdv: Vec3,
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        count(Counter::IntersectionTests);
+        let o = self.frame.to_local(r.origin() - self.base);
+        let d = self.frame.to_local(r.direction());
+        let Some(hit) = self.local_hit(o, d, &ray_root) else {
+            return false;
+        };
+        rec.t = hit.t;
+        rec.p = r.at(hit.t);
+        rec.set_face_normal(r, unit_vector(self.frame.to_world(hit.n)));
+        (rec.u, rec.v) = (hit.u, hit.v);
+        rec.set_tangents(self.frame.to_world(hit.dpdu), self.frame.to_world(hit.dpdv));
+        rec.mat = self.mat.clone();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let top = self.base + self.height * self.frame.w;
+        Aabb::from_boxes(
+            &disk_box(self.base, self.frame.w, self.base_radius),
+            &disk_box(top, self.frame.w, self.top_radius),
+        )
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "cone".into()),
+            ("base", self.base.into()),
+            ("top", (self.base + self.height * self.frame.w).into()),
+            ("base_radius", self.base_radius.into()),
+            ("top_radius", self.top_radius.into()),
+            ("capped", self.capped.into()),
+            ("material", out.material(&self.mat)),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        f(&self.mat);
+    }
+}
+
+/// Finite cylinder with optional end caps.
+pub struct Cylinder {
+    cone: Cone,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Point3,
+        top: Point3,
+        radius: f64,
+        capped: bool,
+        mat: Rc<RefCell<dyn Material>>,
+    ) -> Result<Cylinder, String> {
+        Ok(Cylinder {
+            cone: Cone::new(base, top, radius, radius, capped, mat)?,
+        })
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        self.cone.hit(r, ray_root, rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.cone.bounding_box()
+    }
+
+    /// Saved as the equivalent cone.
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        self.cone.to_json(out)
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        self.cone.for_each_material(f);
+    }
+}
+
+/// Angle of local point `p` around the z axis, mapped to `[0, 1]`.
+pub fn around_axis(p: Vec3) -> f64 {
+    p.y().atan2(p.x()) / (2.0 * PI) + 0.5
+}
+
+fn disk_uv(p: Vec3, radius: f64) -> (f64, f64) {
+    (0.5 + p.x() / (2.0 * radius), 0.5 + p.y() / (2.0 * radius))
+}
+
+/// Bounds of a disk of `radius` around `center`, perpendicular to unit `axis`.
+pub fn disk_box(center: Point3, axis: Vec3, radius: f64) -> Aabb {
+    let extent = |a: f64| radius * (1.0 - a * a).max(0.0).sqrt();
+    let e = Vec3::from(extent(axis.x()), extent(axis.y()), extent(axis.z()));
+    Aabb::from(center - e, center + e)
+}
@@ -0,0 +1,119 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    Aabb, HitRecord, Hittable, Interval, Json, MaterialVisitor, Ray, SceneWriter, INFINTY,
+    NEG_INFINTY,
+};
+
+/// Boolean operation combining the solids of a `Csg` node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    /// Points inside `a` but not inside `b`.
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(&self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            CsgOp::Union => in_a || in_b,
+            CsgOp::Intersection => in_a && in_b,
+            CsgOp::Difference => in_a && !in_b,
+        }
+    }
+}
+
+/// Constructive solid geometry node over two closed `Hittable`s.
+///
+/// Each boundary of the result keeps the material of the operand it came
+/// from. `front_face` is derived from the combined solid, so the walls that
+/// `b` carves out of `a` in a difference face outwards for refraction.
+pub struct Csg {
+    op: CsgOp,
+    a: Rc<RefCell<dyn Hittable>>,
+    b: Rc<RefCell<dyn Hittable>>,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, a: Rc<RefCell<dyn Hittable>>, b: Rc<RefCell<dyn Hittable>>) -> Csg {
+        Csg { op, a, b }
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        let mut hits = Vec::new();
+        if !self.hit_all(r, ray_root, &mut hits) {
+            return false;
+        }
+        rec.copy(&hits[0]);
+        true
+    }
+
+    fn hit_all(&self, r: Ray, ray_root: Interval, hits: &mut Vec<HitRecord>) -> bool {
+        // Classify along the whole line: both operands start outside at
+        // -infinity, and every crossing toggles inside/outside.
+        let line = Interval::from(NEG_INFINTY, INFINTY);
+        let mut events: Vec<(HitRecord, bool)> = Vec::new();
+        let mut a_hits = Vec::new();
+        let mut b_hits = Vec::new();
+        self.a.borrow().hit_all(r, line.clone(), &mut a_hits);
+        self.b.borrow().hit_all(r, line, &mut b_hits);
+        events.extend(a_hits.into_iter().map(|rec| (rec, false)));
+        events.extend(b_hits.into_iter().map(|rec| (rec, true)));
+        events.sort_by(|x, y| x.0.t.total_cmp(&y.0.t));
+
+        let start = hits.len();
+        let (mut in_a, mut in_b) = (false, false);
+        for (mut rec, from_b) in events {
+            let was_inside = self.op.inside(in_a, in_b);
+            if from_b {
+                in_b = !in_b;
+            } else {
+                in_a = !in_a;
+            }
+            let is_inside = self.op.inside(in_a, in_b);
+            if was_inside == is_inside || !ray_root.surrounds(rec.t) {
+                continue;
+            }
+            // `normal` already faces the ray; only which side is the outside
+            // changes, e.g. for the inner walls of a difference.
+            rec.front_face = is_inside;
+            hits.push(rec);
+        }
+        hits.len() > start
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let (a, b) = (
+            self.a.borrow().bounding_box(),
+            self.b.borrow().bounding_box(),
+        );
+        match self.op {
+            CsgOp::Union => Aabb::from_boxes(&a, &b),
+            CsgOp::Intersection => a.intersect(&b),
+            CsgOp::Difference => a,
+        }
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        let op = match self.op {
+            CsgOp::Union => "union",
+            CsgOp::Intersection => "intersection",
+            CsgOp::Difference => "difference",
+        };
+        Some(Json::object([
+            ("type", "csg".into()),
+            ("op", op.into()),
+            ("a", out.object(&self.a)?),
+            ("b", out.object(&self.b)?),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        self.a.borrow().for_each_material(f);
+        self.b.borrow().for_each_material(f);
+    }
+}
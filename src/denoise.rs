@@ -0,0 +1,241 @@
+use crate::{luminance, Aovs, Color3, Image};
+
+/// Standard deviation of the normal difference treated as the same surface.
+const NORMAL_SIGMA: f64 = 0.25;
+/// Standard deviation of the albedo difference treated as the same material.
+const ALBEDO_SIGMA: f64 = 0.1;
+
+/// B3-spline taps of the à-trous kernel.
+const ATROUS_KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Edge-aware filters for noisy low sample count renders. They run on the
+/// linear framebuffer, before exposure and tone mapping, and use the normal
+/// and albedo AOVs to keep geometric and material edges sharp.
+#[derive(Debug, Clone, Copy)]
+pub enum Denoiser {
+    /// Cross bilateral filter over a `(2 radius + 1)²` window, weighted by
+    /// pixel distance, color difference and the AOV guides.
+    JointBilateral {
+        radius: u32,
+        sigma_spatial: f64,
+        sigma_color: f64,
+    },
+    /// Non-local means: neighbors within `radius` are weighted by how much
+    /// their `(2 patch + 1)²` neighborhood resembles the pixel's own.
+    NonLocalMeans { radius: u32, patch: u32, h: f64 },
+    /// Edge-avoiding à-trous wavelet filter as in SVGF's spatial pass: a 5x5
+    /// kernel applied `iterations` times with doubling stride, its luminance
+    /// weight scaled by the locally estimated variance.
+    ATrous {
+        iterations: u32,
+        sigma_luminance: f64,
+    },
+}
+
+impl Denoiser {
+    /// Builds a denoiser with default parameters.
+    pub fn from(name: &str) -> Option<Denoiser> {
+        match name {
+            "bilateral" => Some(Denoiser::JointBilateral {
+                radius: 5,
+                sigma_spatial: 3.0,
+                sigma_color: 0.5,
+            }),
+            "nlm" => Some(Denoiser::NonLocalMeans {
+                radius: 7,
+                patch: 2,
+                h: 0.3,
+            }),
+            "atrous" => Some(Denoiser::ATrous {
+                iterations: 5,
+                sigma_luminance: 4.0,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Filters `beauty`, which must have been rendered along with `aovs`.
+    pub fn apply(&self, beauty: &Image, aovs: &Aovs) -> Image {
+        let guide = Guide { aovs };
+        let pixels = match *self {
+            Denoiser::JointBilateral {
+                radius,
+                sigma_spatial,
+                sigma_color,
+            } => joint_bilateral(beauty, &guide, radius, sigma_spatial, sigma_color),
+            Denoiser::NonLocalMeans { radius, patch, h } => {
+                non_local_means(beauty, &guide, radius, patch, h)
+            }
+            Denoiser::ATrous {
+                iterations,
+                sigma_luminance,
+            } => a_trous(beauty, &guide, iterations, sigma_luminance),
+        };
+        Image {
+            w: beauty.w,
+            h: beauty.h,
+            pixels,
+        }
+    }
+}
+
+struct Guide<'a> {
+    aovs: &'a Aovs,
+}
+
+impl Guide<'_> {
+    /// Likelihood that pixels `p` and `q` show the same surface.
+    fn weight(&self, p: usize, q: usize) -> f64 {
+        let dn = (self.aovs.normal.pixels[p] - self.aovs.normal.pixels[q]).length_squared();
+        let da = (self.aovs.albedo.pixels[p] - self.aovs.albedo.pixels[q]).length_squared();
+        (-dn / (2.0 * NORMAL_SIGMA * NORMAL_SIGMA) - da / (2.0 * ALBEDO_SIGMA * ALBEDO_SIGMA)).exp()
+    }
+}
+
+/// Calls `f(x, y, q)` for every pixel `q = (x, y)` of the window of `radius`
+/// around `(px, py)` that lies inside the image.
+fn for_window(image: &Image, px: i64, py: i64, radius: i64, mut f: impl FnMut(i64, i64, usize)) {
+    for y in (py - radius).max(0)..=(py + radius).min(image.h as i64 - 1) {
+        for x in (px - radius).max(0)..=(px + radius).min(image.w as i64 - 1) {
+            f(x, y, (y * image.w as i64 + x) as usize);
+        }
+    }
+}
+
+fn joint_bilateral(
+    beauty: &Image,
+    guide: &Guide,
+    radius: u32,
+    sigma_spatial: f64,
+    sigma_color: f64,
+) -> Vec<Color3> {
+    let (w, h) = (beauty.w as i64, beauty.h as i64);
+    let mut out = Vec::with_capacity(beauty.pixels.len());
+    for py in 0..h {
+        for px in 0..w {
+            let p = (py * w + px) as usize;
+            let cp = beauty.pixels[p];
+            let mut sum = Color3::new();
+            let mut total = 0.0;
+            for_window(beauty, px, py, radius as i64, |x, y, q| {
+                let d2 = ((x - px) * (x - px) + (y - py) * (y - py)) as f64;
+                let dc = (beauty.pixels[q] - cp).length_squared();
+                let wt = (-d2 / (2.0 * sigma_spatial * sigma_spatial)
+                    - dc / (2.0 * sigma_color * sigma_color))
+                    .exp()
+                    * guide.weight(p, q);
+                sum += beauty.pixels[q] * wt;
+                total += wt;
+            });
+            out.push(sum / total);
+        }
+    }
+    out
+}
+
+fn non_local_means(beauty: &Image, guide: &Guide, radius: u32, patch: u32, h: f64) -> Vec<Color3> {
+    let (w, ht) = (beauty.w as i64, beauty.h as i64);
+    let patch = patch as i64;
+    let at = |x: i64, y: i64| {
+        let (x, y) = (x.clamp(0, w - 1), y.clamp(0, ht - 1));
+        beauty.pixels[(y * w + x) as usize]
+    };
+    let patch_size = ((2 * patch + 1) * (2 * patch + 1)) as f64;
+    let mut out = Vec::with_capacity(beauty.pixels.len());
+    for py in 0..ht {
+        for px in 0..w {
+            let p = (py * w + px) as usize;
+            let mut sum = Color3::new();
+            let mut total = 0.0;
+            for_window(beauty, px, py, radius as i64, |x, y, q| {
+                let mut d2 = 0.0;
+                for oy in -patch..=patch {
+                    for ox in -patch..=patch {
+                        d2 += (at(px + ox, py + oy) - at(x + ox, y + oy)).length_squared();
+                    }
+                }
+                let wt = (-d2 / (patch_size * h * h)).exp() * guide.weight(p, q);
+                sum += beauty.pixels[q] * wt;
+                total += wt;
+            });
+            out.push(sum / total);
+        }
+    }
+    out
+}
+
+fn a_trous(beauty: &Image, guide: &Guide, iterations: u32, sigma_luminance: f64) -> Vec<Color3> {
+    let (w, h) = (beauty.w as i64, beauty.h as i64);
+    let mut color = beauty.pixels.clone();
+
+    // Without temporal history, start from the luminance variance of each
+    // 3x3 neighborhood.
+    let mut variance = Vec::with_capacity(color.len());
+    for py in 0..h {
+        for px in 0..w {
+            let (mut m1, mut m2, mut n) = (0.0, 0.0, 0.0);
+            for_window(beauty, px, py, 1, |_, _, q| {
+                let l = luminance(color[q]);
+                m1 += l;
+                m2 += l * l;
+                n += 1.0;
+            });
+            variance.push((m2 / n - (m1 / n) * (m1 / n)).max(0.0));
+        }
+    }
+
+    for it in 0..iterations {
+        let step = 1i64 << it;
+        // The luminance weight uses a slightly blurred variance, which is
+        // itself noisy.
+        let mut blurred = Vec::with_capacity(variance.len());
+        for py in 0..h {
+            for px in 0..w {
+                let (mut sum, mut total) = (0.0, 0.0);
+                for_window(beauty, px, py, 1, |x, y, q| {
+                    let k =
+                        ATROUS_KERNEL[(x - px + 2) as usize] * ATROUS_KERNEL[(y - py + 2) as usize];
+                    sum += variance[q] * k;
+                    total += k;
+                });
+                blurred.push(sum / total);
+            }
+        }
+
+        let mut next_color = Vec::with_capacity(color.len());
+        let mut next_variance = Vec::with_capacity(variance.len());
+        for py in 0..h {
+            for px in 0..w {
+                let p = (py * w + px) as usize;
+                let lp = luminance(color[p]);
+                let scale = sigma_luminance * blurred[p].sqrt() + 1e-6;
+                let mut sum = Color3::new();
+                let mut sum_var = 0.0;
+                let mut total = 0.0;
+                for (ky, ty) in ATROUS_KERNEL.iter().enumerate() {
+                    let y = py + (ky as i64 - 2) * step;
+                    if y < 0 || y >= h {
+                        continue;
+                    }
+                    for (kx, tx) in ATROUS_KERNEL.iter().enumerate() {
+                        let x = px + (kx as i64 - 2) * step;
+                        if x < 0 || x >= w {
+                            continue;
+                        }
+                        let q = (y * w + x) as usize;
+                        let wl = (-(luminance(color[q]) - lp).abs() / scale).exp();
+                        let wt = tx * ty * wl * guide.weight(p, q);
+                        sum += color[q] * wt;
+                        sum_var += wt * wt * variance[q];
+                        total += wt;
+                    }
+                }
+                next_color.push(sum / total);
+                next_variance.push(sum_var / (total * total));
+            }
+        }
+        color = next_color;
+        variance = next_variance;
+    }
+    color
+}
@@ -0,0 +1,678 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{
+    scene_from_json, Camera, Image, Json, RenderCounters, RenderProgress, RenderSettings, Scene,
+    SceneSetup,
+};
+
+/// Version of the coordinator/worker protocol; both sides must agree.
+const PROTOCOL: usize = 1;
+
+/// Largest scene document a worker accepts, unless
+/// `Worker::with_max_scene_bytes` says otherwise.
+const DEFAULT_MAX_SCENE_BYTES: usize = 256 << 20;
+
+/// How long a peer may go without sending or accepting data before it
+/// counts as failed, unless `Coordinator::with_timeout` says otherwise.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Reasons a distributed render fails.
+#[derive(Debug)]
+pub enum DistributedError {
+    Io(io::Error),
+    /// The other side sent something unexpected, or reported an error.
+    Protocol(String),
+    /// Every worker failed before the image was done; holds the last
+    /// failure.
+    NoWorkers(String),
+}
+
+impl fmt::Display for DistributedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DistributedError::Io(e) => write!(f, "{e}"),
+            DistributedError::Protocol(what) => write!(f, "protocol error: {what}"),
+            DistributedError::NoWorkers(last) => write!(f, "no worker left, last error: {last}"),
+        }
+    }
+}
+
+impl std::error::Error for DistributedError {}
+
+impl From<io::Error> for DistributedError {
+    fn from(e: io::Error) -> DistributedError {
+        DistributedError::Io(e)
+    }
+}
+
+fn protocol(what: impl Into<String>) -> DistributedError {
+    DistributedError::Protocol(what.into())
+}
+
+/// Read errors, with the expiry of a read timeout spelled out.
+fn read_error(e: io::Error) -> DistributedError {
+    match e.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => protocol("timed out"),
+        _ => DistributedError::Io(e),
+    }
+}
+
+/// Messages are a compact JSON header on one line, followed by `length`
+/// bytes of payload when the header has that member. `receive` refuses
+/// payloads longer than the `max_length` the caller expects.
+fn send(stream: &mut TcpStream, header: Json, payload: &[u8]) -> io::Result<()> {
+    let mut out = format!("{header}\n").into_bytes();
+    out.extend_from_slice(payload);
+    stream.write_all(&out)?;
+    stream.flush()
+}
+
+fn receive(
+    reader: &mut impl BufRead,
+    max_length: usize,
+) -> Result<(Json, Vec<u8>), DistributedError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).map_err(read_error)? == 0 {
+        return Err(protocol("connection closed"));
+    }
+    let header = Json::parse(line.trim_end()).map_err(protocol)?;
+    let mut payload = Vec::new();
+    if let Some(length) = header.get("length") {
+        let length = length.as_usize().ok_or_else(|| protocol("bad length"))?;
+        if length > max_length {
+            return Err(protocol(format!(
+                "payload of {length} bytes, expected at most {max_length}"
+            )));
+        }
+        // Grows with the data instead of trusting `length` up front.
+        reader
+            .take(length as u64)
+            .read_to_end(&mut payload)
+            .map_err(read_error)?;
+        if payload.len() != length {
+            return Err(protocol("connection closed"));
+        }
+    }
+    if header.get("type").and_then(Json::as_str) == Some("error") {
+        let message = header.get("message").and_then(Json::as_str).unwrap_or("");
+        return Err(protocol(format!("remote error: {message}")));
+    }
+    Ok((header, payload))
+}
+
+fn kind(header: &Json) -> &str {
+    header.get("type").and_then(Json::as_str).unwrap_or("")
+}
+
+fn range(header: &Json, key: &str) -> Result<Range<u64>, DistributedError> {
+    let bad = || protocol(format!("bad or missing `{key}`"));
+    let value = header.get(key).ok_or_else(bad)?;
+    let start = value.at(0).and_then(Json::as_u64).ok_or_else(bad)?;
+    let end = value.at(1).and_then(Json::as_u64).ok_or_else(bad)?;
+    if start > end {
+        return Err(bad());
+    }
+    Ok(start..end)
+}
+
+fn range_json(r: &Range<u64>) -> Json {
+    vec![Json::from(r.start), Json::from(r.end)].into()
+}
+
+fn counters_json(c: &RenderCounters) -> Json {
+    Json::object([
+        ("primary_rays", c.primary_rays.into()),
+        ("secondary_rays", c.secondary_rays.into()),
+        ("shadow_rays", c.shadow_rays.into()),
+        ("intersection_tests", c.intersection_tests.into()),
+        ("bvh_nodes_visited", c.bvh_nodes_visited.into()),
+    ])
+}
+
+fn counters_from_json(value: Option<&Json>) -> RenderCounters {
+    let get = |key: &str| {
+        value
+            .and_then(|v| v.get(key))
+            .and_then(Json::as_u64)
+            .unwrap_or(0)
+    };
+    RenderCounters {
+        primary_rays: get("primary_rays"),
+        secondary_rays: get("secondary_rays"),
+        shadow_rays: get("shadow_rays"),
+        intersection_tests: get("intersection_tests"),
+        bvh_nodes_visited: get("bvh_nodes_visited"),
+    }
+}
+
+/// Tells the other side why its request is refused.
+fn send_error(stream: &mut TcpStream, e: &DistributedError) {
+    let message = e.to_string();
+    let _ = send(
+        stream,
+        Json::object([
+            ("type", "error".into()),
+            ("message", message.as_str().into()),
+        ]),
+        &[],
+    );
+}
+
+/// Side of the protocol that renders: a process that coordinators connect
+/// to with the scene and then hand jobs.
+pub struct Worker {
+    max_connections: usize,
+    max_scene_bytes: usize,
+}
+
+impl Default for Worker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Worker {
+    /// Takes as many connections at once as there are cores.
+    pub fn new() -> Worker {
+        Worker {
+            max_connections: thread::available_parallelism().map_or(1, |n| n.get()),
+            max_scene_bytes: DEFAULT_MAX_SCENE_BYTES,
+        }
+    }
+
+    /// Connections served at once; further ones are refused with an
+    /// error, so their coordinators give the jobs to other workers.
+    pub fn with_max_connections(mut self, connections: usize) -> Self {
+        self.max_connections = connections.max(1);
+        self
+    }
+
+    /// Largest scene document accepted; 256 MiB by default.
+    pub fn with_max_scene_bytes(mut self, bytes: usize) -> Self {
+        self.max_scene_bytes = bytes;
+        self
+    }
+
+    /// Accepts coordinator connections on `listener` forever, serving each
+    /// on its own thread with its own copy of the scene, so one worker
+    /// process can take several connections to use several cores.
+    /// Connections that fail or are refused are passed to `failed`.
+    pub fn serve(
+        &self,
+        listener: TcpListener,
+        failed: impl Fn(SocketAddr, DistributedError) + Send + Sync + 'static,
+    ) -> io::Result<()> {
+        let failed = Arc::new(failed);
+        let active = Arc::new(AtomicUsize::new(0));
+        loop {
+            let (mut stream, peer) = listener.accept()?;
+            if active.load(Ordering::SeqCst) >= self.max_connections {
+                let e = protocol(format!(
+                    "worker is busy, it takes {} connections at once",
+                    self.max_connections
+                ));
+                send_error(&mut stream, &e);
+                failed(peer, e);
+                continue;
+            }
+            active.fetch_add(1, Ordering::SeqCst);
+            let (failed, active) = (failed.clone(), active.clone());
+            let max_scene_bytes = self.max_scene_bytes;
+            thread::spawn(move || {
+                if let Err(e) = serve_connection(stream, max_scene_bytes) {
+                    failed(peer, e);
+                }
+                active.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    }
+}
+
+/// Serves one coordinator: receives a scene of at most `max_scene_bytes`,
+/// then renders jobs until told it is done.
+pub fn serve_connection(
+    mut stream: TcpStream,
+    max_scene_bytes: usize,
+) -> Result<(), DistributedError> {
+    // No read timeout: the coordinator may leave a connection idle while
+    // others finish the last jobs.
+    stream.set_write_timeout(Some(DEFAULT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let loaded = receive(&mut reader, max_scene_bytes)
+        .and_then(|(header, payload)| Ok((load_scene_message(&header, &payload)?, header)));
+    let ((scene, mut camera), header) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            send_error(&mut stream, &e);
+            return Err(e);
+        }
+    };
+    if header.get("autofocus").and_then(Json::as_bool) == Some(true) {
+        camera.autofocus(&scene.world);
+    }
+    send(&mut stream, Json::object([("type", "ready".into())]), &[])?;
+
+    let mut state = camera.checkpoint();
+    let mut samplers = state.samplers(camera.samples_per_pixel());
+    loop {
+        let (header, _) = receive(&mut reader, 0)?;
+        match kind(&header) {
+            "job" => {}
+            "done" => return Ok(()),
+            other => return Err(protocol(format!("unexpected message {other}"))),
+        }
+        let rows = range(&header, "rows")?;
+        let samples = range(&header, "samples")?;
+        if rows.end > state.h || samples.end > camera.samples_per_pixel() {
+            return Err(protocol("job outside the image"));
+        }
+        RenderCounters::take();
+        for i in rows.clone() {
+            camera.render_row(&scene, i, samples.clone(), &mut samplers, &mut state, None);
+        }
+        let band = state.buffer.splat_rows(rows);
+        let bytes = state.buffer.rows_to_bytes(band.clone());
+        state.buffer.clear_rows(band.clone());
+        send(
+            &mut stream,
+            Json::object([
+                ("type", "result".into()),
+                ("id", header.get("id").cloned().unwrap_or(Json::Null)),
+                ("first_row", band.start.into()),
+                ("counters", counters_json(&RenderCounters::take())),
+                ("length", bytes.len().into()),
+            ]),
+            &bytes,
+        )?;
+    }
+}
+
+fn load_scene_message(header: &Json, payload: &[u8]) -> Result<(Scene, Camera), DistributedError> {
+    if kind(header) != "scene" {
+        return Err(protocol("expected a scene"));
+    }
+    if header.get("protocol").and_then(Json::as_usize) != Some(PROTOCOL) {
+        return Err(protocol(format!("expected protocol version {PROTOCOL}")));
+    }
+    let text = std::str::from_utf8(payload).map_err(|_| protocol("scene is not UTF-8"))?;
+    let json = Json::parse(text).map_err(protocol)?;
+    let SceneSetup { scene, camera } =
+        scene_from_json(&json).map_err(|e| protocol(e.to_string()))?;
+    match camera.build() {
+        Ok(camera) => Ok((scene, camera)),
+        Err(e) => Err(protocol(format!("invalid camera: {e}"))),
+    }
+}
+
+/// Piece of the image handed to one worker at a time.
+#[derive(Debug, Clone)]
+struct Job {
+    id: usize,
+    rows: Range<u64>,
+    samples: Range<u64>,
+    /// Where the rows the samples can reach start, and their size in
+    /// bytes: what the result must hold.
+    first_row: u64,
+    length: usize,
+}
+
+/// Jobs shared by the connection threads. A job counts as outstanding
+/// from the moment it is handed out until its result arrives; jobs of a
+/// failed worker go back into `pending`.
+struct Queue {
+    pending: VecDeque<Job>,
+    outstanding: usize,
+}
+
+/// Drops the jobs not handed out yet, so that every connection ends once
+/// its current job is in.
+fn drain(queue: &(Mutex<Queue>, Condvar)) {
+    let (lock, wake) = queue;
+    lock.lock().unwrap().pending.clear();
+    wake.notify_all();
+}
+
+enum Event {
+    Result {
+        job: Job,
+        first_row: u64,
+        counters: RenderCounters,
+        bytes: Vec<u8>,
+    },
+    Failed(String, DistributedError),
+}
+
+/// Renders on worker processes, possibly on other machines, reached over
+/// TCP. The image is cut into bands of rows, optionally further split into
+/// ranges of samples; each worker renders the jobs it is handed into its
+/// own float buffer and sends back the rows they touched, which are
+/// summed here. Jobs of a worker that fails are given to the others.
+pub struct Coordinator {
+    workers: Vec<String>,
+    connections: usize,
+    tile_rows: u64,
+    tile_samples: Option<u64>,
+    autofocus: bool,
+    timeout: Duration,
+}
+
+impl Coordinator {
+    /// Workers are `host:port` addresses of processes running `serve`.
+    pub fn new(workers: Vec<String>) -> Coordinator {
+        Coordinator {
+            workers,
+            connections: 1,
+            tile_rows: 16,
+            tile_samples: None,
+            autofocus: false,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Connections opened to every worker; each renders on its own core.
+    pub fn with_connections(mut self, connections: usize) -> Self {
+        self.connections = connections.max(1);
+        self
+    }
+
+    /// Height of a job in rows.
+    pub fn with_tile_rows(mut self, rows: u64) -> Self {
+        self.tile_rows = rows.max(1);
+        self
+    }
+
+    /// Samples per pixel of a job; by default a job takes them all.
+    pub fn with_tile_samples(mut self, samples: u64) -> Self {
+        self.tile_samples = Some(samples.max(1));
+        self
+    }
+
+    /// Has the workers focus their cameras like `Camera::autofocus`.
+    pub fn with_autofocus(mut self, autofocus: bool) -> Self {
+        self.autofocus = autofocus;
+        self
+    }
+
+    /// How long a worker may take to accept a message or to answer one,
+    /// a job included, before it counts as failed and its jobs go to the
+    /// others. Ten minutes by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Renders `scene`, a scene file document as written by
+    /// `scene_to_json`. `camera` must be built from the same document; it
+    /// gives the size and sampling of the image.
+    pub fn render(
+        &self,
+        scene: &Json,
+        camera: &Camera,
+        settings: &RenderSettings,
+    ) -> Result<Image, DistributedError> {
+        let mut state = camera.checkpoint();
+        let spp = camera.samples_per_pixel();
+        let tile_samples = self.tile_samples.unwrap_or(spp);
+        let mut pending = VecDeque::new();
+        for rows in (0..state.h).step_by(self.tile_rows as usize) {
+            let rows = rows..(rows + self.tile_rows).min(state.h);
+            let band = state.buffer.splat_rows(rows.clone());
+            for samples in (0..spp).step_by(tile_samples as usize) {
+                pending.push_back(Job {
+                    id: pending.len(),
+                    rows: rows.clone(),
+                    samples: samples..(samples + tile_samples).min(spp),
+                    first_row: band.start,
+                    length: ((band.end - band.start) * state.w * 32) as usize,
+                });
+            }
+        }
+        let jobs = pending.len();
+        let queue = Arc::new((
+            Mutex::new(Queue {
+                pending,
+                outstanding: 0,
+            }),
+            Condvar::new(),
+        ));
+        let text = Arc::new(scene.to_string().into_bytes());
+        let (events, results) = mpsc::channel();
+        let mut sessions = Vec::new();
+        for address in &self.workers {
+            for _ in 0..self.connections {
+                let address = address.clone();
+                let text = text.clone();
+                let queue = queue.clone();
+                let events = events.clone();
+                let (autofocus, timeout) = (self.autofocus, self.timeout);
+                sessions.push(thread::spawn(move || {
+                    if let Err(e) = session(&address, &text, autofocus, timeout, &queue, &events) {
+                        let _ = events.send(Event::Failed(address, e));
+                    }
+                }));
+            }
+        }
+        drop(events);
+
+        let start = Instant::now();
+        let samples_total = state.w * state.h * spp;
+        let mut samples_done = 0;
+        let mut merged = 0;
+        let mut last_failure = None;
+        let mut error = None;
+        while merged < jobs {
+            if settings.is_cancelled() {
+                drain(&queue);
+            }
+            let event = match results.recv_timeout(Duration::from_millis(200)) {
+                Ok(event) => event,
+                Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                // Every connection has ended.
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+            match event {
+                Event::Result {
+                    job,
+                    first_row,
+                    counters,
+                    bytes,
+                } => {
+                    if !state.buffer.add_bytes(first_row, &bytes) {
+                        error = Some(protocol("result does not fit the image"));
+                        drain(&queue);
+                        break;
+                    }
+                    merged += 1;
+                    samples_done += (job.rows.end - job.rows.start)
+                        * (job.samples.end - job.samples.start)
+                        * state.w;
+                    settings.record(counters, Duration::ZERO);
+                    if merged < jobs {
+                        settings.report(RenderProgress {
+                            samples_done,
+                            samples_total,
+                            elapsed: start.elapsed(),
+                            finished: false,
+                        });
+                    }
+                }
+                Event::Failed(address, e) => {
+                    let message = format!("worker {address} failed: {e}");
+                    settings.warn(&message);
+                    last_failure = Some(message);
+                }
+            }
+        }
+        // Lets every connection tell its worker it is done.
+        for session in sessions {
+            let _ = session.join();
+        }
+        if let Some(e) = error {
+            return Err(e);
+        }
+        settings.report(RenderProgress {
+            samples_done,
+            samples_total,
+            elapsed: start.elapsed(),
+            finished: true,
+        });
+        settings.record(RenderCounters::default(), start.elapsed());
+        if merged < jobs && !settings.is_cancelled() {
+            return Err(DistributedError::NoWorkers(
+                last_failure.unwrap_or("no workers given".to_string()),
+            ));
+        }
+        Ok(state.image())
+    }
+}
+
+/// Drives one connection: sends the scene, then hands out jobs until none
+/// are pending or outstanding.
+fn session(
+    address: &str,
+    scene: &[u8],
+    autofocus: bool,
+    timeout: Duration,
+    queue: &(Mutex<Queue>, Condvar),
+    events: &mpsc::Sender<Event>,
+) -> Result<(), DistributedError> {
+    let target = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| protocol(format!("cannot resolve {address}")))?;
+    let mut stream = TcpStream::connect_timeout(&target, Duration::from_secs(10))?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    send(
+        &mut stream,
+        Json::object([
+            ("type", "scene".into()),
+            ("protocol", PROTOCOL.into()),
+            ("autofocus", autofocus.into()),
+            ("length", scene.len().into()),
+        ]),
+        scene,
+    )?;
+    let (header, _) = receive(&mut reader, 0)?;
+    if kind(&header) != "ready" {
+        return Err(protocol("worker did not accept the scene"));
+    }
+
+    let (lock, wake) = queue;
+    loop {
+        let job = {
+            let mut queue = lock.lock().unwrap();
+            while queue.pending.is_empty() && queue.outstanding > 0 {
+                queue = wake.wait(queue).unwrap();
+            }
+            match queue.pending.pop_front() {
+                Some(job) => {
+                    queue.outstanding += 1;
+                    job
+                }
+                None => break,
+            }
+        };
+        let result = run_job(&mut stream, &mut reader, &job);
+        let mut queue = lock.lock().unwrap();
+        queue.outstanding -= 1;
+        match result {
+            Ok((first_row, counters, bytes)) => {
+                let _ = events.send(Event::Result {
+                    job,
+                    first_row,
+                    counters,
+                    bytes,
+                });
+                wake.notify_all();
+            }
+            Err(e) => {
+                queue.pending.push_front(job);
+                wake.notify_all();
+                return Err(e);
+            }
+        }
+    }
+    send(&mut stream, Json::object([("type", "done".into())]), &[])?;
+    Ok(())
+}
+
+fn run_job(
+    stream: &mut TcpStream,
+    reader: &mut impl BufRead,
+    job: &Job,
+) -> Result<(u64, RenderCounters, Vec<u8>), DistributedError> {
+    send(
+        stream,
+        Json::object([
+            ("type", "job".into()),
+            ("id", job.id.into()),
+            ("rows", range_json(&job.rows)),
+            ("samples", range_json(&job.samples)),
+        ]),
+        &[],
+    )?;
+    let (header, bytes) = receive(reader, job.length)?;
+    if kind(&header) != "result" || header.get("id").and_then(Json::as_usize) != Some(job.id) {
+        return Err(protocol("expected the result of the job"));
+    }
+    if header.get("first_row").and_then(Json::as_u64) != Some(job.first_row)
+        || bytes.len() != job.length
+    {
+        return Err(protocol("result does not cover the rows of the job"));
+    }
+    Ok((
+        job.first_row,
+        counters_from_json(header.get("counters")),
+        bytes,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_scene, render, scene_to_json};
+
+    #[test]
+    fn workers_on_localhost_match_a_local_render() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || Worker::new().serve(listener, |_, _| {}));
+
+        let SceneSetup { scene, camera } = builtin_scene("random-spheres").unwrap().build(0);
+        let builder = camera.width(48).samples_per_pixel(4).max_depth(8);
+        let (json, warnings) = scene_to_json(&scene, &builder);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let camera = builder.build().unwrap();
+
+        let local = render(&scene, &camera, &RenderSettings::new()).unwrap();
+        let remote = Coordinator::new(vec![address])
+            .with_connections(2)
+            .with_tile_rows(5)
+            .with_tile_samples(3)
+            .render(&json, &camera, &RenderSettings::new())
+            .unwrap();
+        let values = |image: &Image| -> Vec<[f64; 3]> {
+            image.pixels.iter().map(|p| [p.x(), p.y(), p.z()]).collect()
+        };
+        assert_eq!(values(&local), values(&remote));
+    }
+
+    #[test]
+    fn oversized_payloads_are_refused() {
+        let header = "{\"type\":\"scene\",\"protocol\":1,\"length\":100000000000000}\n";
+        let result = receive(&mut header.as_bytes(), DEFAULT_MAX_SCENE_BYTES);
+        assert!(matches!(result, Err(DistributedError::Protocol(_))));
+    }
+}
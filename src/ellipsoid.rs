@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    count, dot, solve_quadratic, sphere_tangents, sphere_uv, unit_vector, Aabb, Counter, HitRecord,
+    Hittable, Interval, Json, Material, MaterialVisitor, Point3, Ray, SceneWriter, Vec3,
+};
+
+/// Axis-aligned ellipsoid with semi-axes `radii`. UVs are those of the unit
+/// sphere it is stretched from.
+pub struct Ellipsoid {
+    center: Point3,
+    radii: Vec3,
+    mat: Rc<RefCell<dyn Material>>,
+}
+
+impl Ellipsoid {
+    pub fn new(center: Point3, radii: Vec3, mat: Rc<RefCell<dyn Material>>) -> Ellipsoid {
+        Ellipsoid { center, radii, mat }
+    }
+
+    /// Maps world space onto the unit sphere.
+    fn scale(&self, v: Vec3) -> Vec3 {
+        Vec3::from(
+            v.x() / self.radii.x(),
+            v.y() / self.radii.y(),
+            v.z() / self.radii.z(),
+        )
+    }
+}
+
+impl Hittable for Ellipsoid {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        count(Counter::IntersectionTests);
+        // Scaling keeps `t`, so intersect the unit sphere instead.
+        let o = self.scale(r.origin() - self.center);
+        let d = self.scale(r.direction());
+        let roots = solve_quadratic(
+            d.length_squared(),
+            2.0 * dot(o, d),
+            o.length_squared() - 1.0,
+        );
+        let Some(t) = roots.into_iter().find(|t| ray_root.surrounds(*t)) else {
+            return false;
+        };
+        let unit = o + t * d;
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_face_normal(r, unit_vector(self.scale(unit)));
+        (rec.u, rec.v) = sphere_uv(unit);
+        let (dpdu, dpdv) = sphere_tangents(unit);
+        rec.set_tangents(self.radii * dpdu, self.radii * dpdv);
+        rec.mat = self.mat.clone();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from(self.center - self.radii, self.center + self.radii)
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "ellipsoid".into()),
+            ("center", self.center.into()),
+            ("radii", self.radii.into()),
+            ("material", out.material(&self.mat)),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        f(&self.mat);
+    }
+}
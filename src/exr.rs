@@ -0,0 +1,89 @@
+use std::fs;
+use std::io;
+
+/// One 32-bit float channel of an EXR file, row-major with `w * h` values.
+pub struct ExrChannel {
+    pub name: String,
+    pub data: Vec<f32>,
+}
+
+impl ExrChannel {
+    pub fn new(name: &str, data: Vec<f32>) -> ExrChannel {
+        ExrChannel {
+            name: name.to_string(),
+            data,
+        }
+    }
+}
+
+/// Writes a single-part, uncompressed scanline OpenEXR file. Layers follow
+/// the usual `layer.channel` naming, e.g. `depth.Z` or `diffuse.R`.
+pub fn write_exr(path: &str, w: u64, h: u64, channels: &[ExrChannel]) -> io::Result<()> {
+    let n = (w * h) as usize;
+    if channels.iter().any(|c| c.data.len() != n) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "EXR channel size does not match the image",
+        ));
+    }
+    // Readers expect channels sorted by name, both in the header and in
+    // every scanline block.
+    let mut sorted: Vec<&ExrChannel> = channels.iter().collect();
+    sorted.sort_by(|a, b| a.name.as_bytes().cmp(b.name.as_bytes()));
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&0x762f3101u32.to_le_bytes());
+    let long_names = sorted.iter().any(|c| c.name.len() > 31);
+    let version: u32 = if long_names { 2 | 0x400 } else { 2 };
+    out.extend_from_slice(&version.to_le_bytes());
+
+    let mut chlist = Vec::new();
+    for c in &sorted {
+        chlist.extend_from_slice(c.name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&2i32.to_le_bytes()); // FLOAT
+        chlist.extend_from_slice(&[0, 0, 0, 0]); // pLinear + reserved
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+    }
+    chlist.push(0);
+    attribute(&mut out, "channels", "chlist", &chlist);
+    attribute(&mut out, "compression", "compression", &[0]);
+    let mut window = Vec::new();
+    for v in [0, 0, w as i32 - 1, h as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+    attribute(&mut out, "dataWindow", "box2i", &window);
+    attribute(&mut out, "displayWindow", "box2i", &window);
+    attribute(&mut out, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut out, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    attribute(&mut out, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut out, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    out.push(0);
+
+    let block_size = 8 + 4 * w as usize * sorted.len();
+    let table_end = out.len() + 8 * h as usize;
+    for y in 0..h as usize {
+        out.extend_from_slice(&((table_end + y * block_size) as u64).to_le_bytes());
+    }
+    for y in 0..h as usize {
+        out.extend_from_slice(&(y as i32).to_le_bytes());
+        out.extend_from_slice(&((block_size - 8) as i32).to_le_bytes());
+        for c in &sorted {
+            let row = &c.data[y * w as usize..(y + 1) * w as usize];
+            for v in row {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+    }
+    fs::write(path, out)
+}
+
+fn attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    out.extend_from_slice(kind.as_bytes());
+    out.push(0);
+    out.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    out.extend_from_slice(value);
+}
@@ -0,0 +1,272 @@
+use std::ops::Range;
+
+use crate::{linear_to_srgb, write_clr, Color3, Filter, Image};
+
+/// Operator mapping scene-referred radiance onto the displayable `[0, 1]` range.
+#[derive(Debug, Clone, Copy)]
+pub enum ToneMap {
+    /// Hard clip at 1.0, the renderer's historical behaviour.
+    Clamp,
+    /// `L / (1 + L)` on luminance, keeping hue.
+    Reinhard,
+    /// Reinhard with a white point: luminance `white` maps to 1.0.
+    ExtendedReinhard { white: f64 },
+    /// Narkowicz's fit of the ACES filmic RRT/ODT curve.
+    AcesFilmic,
+    /// Polynomial approximation of Troy Sobotka's AgX base transform.
+    AgX,
+}
+
+/// Post-processing applied to the float framebuffer before quantization:
+/// exposure, tone mapping, then the sRGB transfer function.
+#[derive(Debug, Clone, Copy)]
+pub struct Film {
+    /// Exposure compensation in stops; every stop doubles the radiance.
+    pub exposure: f64,
+    pub tone_map: ToneMap,
+}
+
+impl Default for Film {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Film {
+    pub fn new() -> Film {
+        Film {
+            exposure: 0.0,
+            tone_map: ToneMap::Clamp,
+        }
+    }
+
+    pub fn from(exposure: f64, tone_map: ToneMap) -> Film {
+        Film { exposure, tone_map }
+    }
+
+    /// Turns a linear radiance value into a display-encoded sRGB color.
+    pub fn develop(&self, clr: Color3) -> Color3 {
+        let exposed = clr * 2f64.powf(self.exposure);
+        let mapped = self.tone_map.apply(exposed);
+        Color3::from(
+            linear_to_srgb(mapped.x()),
+            linear_to_srgb(mapped.y()),
+            linear_to_srgb(mapped.z()),
+        )
+    }
+
+    /// Writes an image of linear radiance to stdout as a PPM.
+    pub fn write_ppm(&self, image: &Image) {
+        print!("P3\n{} {}\n255\n", image.w, image.h);
+        for clr in &image.pixels {
+            write_clr(self.develop(*clr), false);
+        }
+    }
+}
+
+/// Float accumulation buffer. Samples are splatted into every pixel covered by
+/// the reconstruction filter and normalized by the summed filter weights.
+#[derive(Clone)]
+pub struct SampleBuffer {
+    w: u64,
+    h: u64,
+    filter: Filter,
+    sum: Vec<Color3>,
+    weight: Vec<f64>,
+}
+
+impl SampleBuffer {
+    pub fn new(w: u64, h: u64, filter: Filter) -> SampleBuffer {
+        let n = (w * h) as usize;
+        SampleBuffer {
+            w,
+            h,
+            filter,
+            sum: vec![Color3::new(); n],
+            weight: vec![0.0; n],
+        }
+    }
+
+    /// Adds a sample taken at raster position `(x, y)`, where pixel `(j, i)`
+    /// covers `[j, j + 1) x [i, i + 1)`.
+    pub fn splat(&mut self, x: f64, y: f64, clr: Color3) {
+        let r = self.filter.radius();
+        let j0 = (x - 0.5 - r).ceil().max(0.0) as u64;
+        let j1 = ((x - 0.5 + r).floor() as i64).min(self.w as i64 - 1);
+        let i0 = (y - 0.5 - r).ceil().max(0.0) as u64;
+        let i1 = ((y - 0.5 + r).floor() as i64).min(self.h as i64 - 1);
+        if j1 < 0 || i1 < 0 {
+            return;
+        }
+        for i in i0..=i1 as u64 {
+            for j in j0..=j1 as u64 {
+                let wt = self.filter.eval(x - (j as f64 + 0.5), y - (i as f64 + 0.5));
+                if wt == 0.0 {
+                    continue;
+                }
+                let idx = (i * self.w + j) as usize;
+                self.sum[idx] += clr * wt;
+                self.weight[idx] += wt;
+            }
+        }
+    }
+
+    /// Raw sums and weights, four little-endian `f64` per pixel, for
+    /// `Checkpoint` files.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.rows_to_bytes(0..self.h)
+    }
+
+    /// Inverse of `to_bytes`; `None` unless `bytes` holds exactly `w * h`
+    /// pixels.
+    pub fn from_bytes(w: u64, h: u64, filter: Filter, bytes: &[u8]) -> Option<SampleBuffer> {
+        if bytes.len() as u64 != w.checked_mul(h)?.checked_mul(32)? {
+            return None;
+        }
+        let mut buffer = SampleBuffer::new(w, h, filter);
+        buffer.add_bytes(0, bytes);
+        Some(buffer)
+    }
+
+    /// `to_bytes` restricted to the rows `rows`.
+    pub fn rows_to_bytes(&self, rows: Range<u64>) -> Vec<u8> {
+        let pixels = (rows.start * self.w) as usize..(rows.end * self.w) as usize;
+        let mut out = Vec::with_capacity(pixels.len() * 32);
+        for k in pixels {
+            let clr = self.sum[k];
+            for v in [clr.x(), clr.y(), clr.z(), self.weight[k]] {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    /// Adds whole rows in the `to_bytes` layout, starting at `first_row`,
+    /// to the sums and weights; `false`, leaving the buffer unchanged, if
+    /// they do not fit.
+    pub fn add_bytes(&mut self, first_row: u64, bytes: &[u8]) -> bool {
+        let row_bytes = (self.w * 32) as usize;
+        let rows = bytes.len().checked_div(row_bytes).unwrap_or(0);
+        if rows * row_bytes != bytes.len() || first_row + rows as u64 > self.h {
+            return false;
+        }
+        let first = (first_row * self.w) as usize;
+        for (k, pixel) in bytes.chunks_exact(32).enumerate() {
+            let v: Vec<f64> = pixel
+                .chunks_exact(8)
+                .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+                .collect();
+            self.sum[first + k] += Color3::from(v[0], v[1], v[2]);
+            self.weight[first + k] += v[3];
+        }
+        true
+    }
+
+    /// Rows that samples taken in `rows` can reach through the filter.
+    pub fn splat_rows(&self, rows: Range<u64>) -> Range<u64> {
+        let r = self.filter.radius().ceil() as u64 + 1;
+        rows.start.saturating_sub(r)..(rows.end + r).min(self.h)
+    }
+
+    /// Zeroes the rows `rows`.
+    pub fn clear_rows(&mut self, rows: Range<u64>) {
+        let pixels = (rows.start * self.w) as usize..(rows.end * self.w) as usize;
+        self.sum[pixels.clone()].fill(Color3::new());
+        self.weight[pixels].fill(0.0);
+    }
+
+    /// The filtered image as linear radiance.
+    pub fn resolve(&self) -> Image {
+        let pixels = self
+            .sum
+            .iter()
+            .zip(self.weight.iter())
+            .map(|(clr, wt)| if *wt > 0.0 { *clr / *wt } else { Color3::new() })
+            .collect();
+        Image {
+            w: self.w,
+            h: self.h,
+            pixels,
+        }
+    }
+}
+
+impl ToneMap {
+    /// Maps linear scene radiance to linear display values in `[0, 1]`.
+    pub fn apply(&self, clr: Color3) -> Color3 {
+        let clr = Color3::from(clr.x().max(0.0), clr.y().max(0.0), clr.z().max(0.0));
+        match *self {
+            ToneMap::Clamp => Color3::from(clr.x().min(1.0), clr.y().min(1.0), clr.z().min(1.0)),
+            ToneMap::Reinhard => scale_luminance(clr, |l| l / (1.0 + l)),
+            ToneMap::ExtendedReinhard { white } => {
+                let w2 = white * white;
+                scale_luminance(clr, |l| l * (1.0 + l / w2) / (1.0 + l))
+            }
+            ToneMap::AcesFilmic => Color3::from(aces(clr.x()), aces(clr.y()), aces(clr.z())),
+            ToneMap::AgX => agx(clr),
+        }
+    }
+}
+
+#[inline(always)]
+pub fn luminance(clr: Color3) -> f64 {
+    0.2126 * clr.x() + 0.7152 * clr.y() + 0.0722 * clr.z()
+}
+
+fn scale_luminance(clr: Color3, curve: impl Fn(f64) -> f64) -> Color3 {
+    let l = luminance(clr);
+    if l <= 0.0 {
+        return Color3::new();
+    }
+    let out = clr * (curve(l) / l);
+    Color3::from(out.x().min(1.0), out.y().min(1.0), out.z().min(1.0))
+}
+
+fn aces(x: f64) -> f64 {
+    let x = x * 0.6;
+    let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+    ((x * (a * x + b)) / (x * (c * x + d) + e)).clamp(0.0, 1.0)
+}
+
+fn mat_mul(m: &[[f64; 3]; 3], v: Color3) -> Color3 {
+    Color3::from(
+        m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+        m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+        m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+    )
+}
+
+const AGX_INSET: [[f64; 3]; 3] = [
+    [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+    [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+    [0.0423756549057051, 0.0784336, 0.879142973793104],
+];
+
+const AGX_OUTSET: [[f64; 3]; 3] = [
+    [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+    [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+    [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+];
+
+fn agx_contrast(x: f64) -> f64 {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+        - 0.00232
+}
+
+fn agx(clr: Color3) -> Color3 {
+    const MIN_EV: f64 = -12.47393;
+    const MAX_EV: f64 = 4.026069;
+    let inset = mat_mul(&AGX_INSET, clr);
+    let encode = |c: f64| {
+        let ev = c.max(1e-10).log2().clamp(MIN_EV, MAX_EV);
+        agx_contrast((ev - MIN_EV) / (MAX_EV - MIN_EV))
+    };
+    let curved = Color3::from(encode(inset.x()), encode(inset.y()), encode(inset.z()));
+    // The AgX curve outputs display-encoded values; undo the implied 2.2
+    // gamma so the sRGB OETF in `Film::develop` is applied exactly once.
+    let out = mat_mul(&AGX_OUTSET, curved);
+    let linear = |c: f64| c.clamp(0.0, 1.0).powf(2.2);
+    Color3::from(linear(out.x()), linear(out.y()), linear(out.z()))
+}
@@ -0,0 +1,148 @@
+use crate::{Json, PI};
+
+/// Pixel reconstruction filter. Every filter is separable and evaluated in
+/// pixel units relative to a pixel center; `radius` is the half-width of its
+/// support, so samples are splatted into every pixel within that distance.
+#[derive(Debug, Clone, Copy)]
+pub enum Filter {
+    Box {
+        radius: f64,
+    },
+    Tent {
+        radius: f64,
+    },
+    Gaussian {
+        radius: f64,
+        alpha: f64,
+    },
+    /// Mitchell–Netravali cubic, `b = c = 1/3` is the recommended setting.
+    Mitchell {
+        radius: f64,
+        b: f64,
+        c: f64,
+    },
+    /// Sinc windowed by a wider sinc with `radius` lobes.
+    Lanczos {
+        radius: f64,
+    },
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::Box { radius: 0.5 }
+    }
+}
+
+impl Filter {
+    /// Builds a filter with the usual default parameters for its kind.
+    pub fn from(name: &str, radius: f64) -> Option<Filter> {
+        match name {
+            "box" => Some(Filter::Box { radius }),
+            "tent" => Some(Filter::Tent { radius }),
+            "gaussian" => Some(Filter::Gaussian { radius, alpha: 2.0 }),
+            "mitchell" => Some(Filter::Mitchell {
+                radius,
+                b: 1.0 / 3.0,
+                c: 1.0 / 3.0,
+            }),
+            "lanczos" => Some(Filter::Lanczos { radius }),
+            _ => None,
+        }
+    }
+
+    /// Kind and parameters, as stored in scene files.
+    pub fn to_json(&self) -> Json {
+        match *self {
+            Filter::Box { radius } => {
+                Json::object([("type", "box".into()), ("radius", radius.into())])
+            }
+            Filter::Tent { radius } => {
+                Json::object([("type", "tent".into()), ("radius", radius.into())])
+            }
+            Filter::Gaussian { radius, alpha } => Json::object([
+                ("type", "gaussian".into()),
+                ("radius", radius.into()),
+                ("alpha", alpha.into()),
+            ]),
+            Filter::Mitchell { radius, b, c } => Json::object([
+                ("type", "mitchell".into()),
+                ("radius", radius.into()),
+                ("b", b.into()),
+                ("c", c.into()),
+            ]),
+            Filter::Lanczos { radius } => {
+                Json::object([("type", "lanczos".into()), ("radius", radius.into())])
+            }
+        }
+    }
+
+    /// Inverse of `Filter::to_json`.
+    pub fn from_json(value: &Json) -> Option<Filter> {
+        let radius = value.get("radius")?.as_f64()?;
+        let param = |key: &str| value.get(key).and_then(Json::as_f64);
+        match value.get("type")?.as_str()? {
+            "gaussian" => Some(Filter::Gaussian {
+                radius,
+                alpha: param("alpha")?,
+            }),
+            "mitchell" => Some(Filter::Mitchell {
+                radius,
+                b: param("b")?,
+                c: param("c")?,
+            }),
+            name => Filter::from(name, radius),
+        }
+    }
+
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius, .. }
+            | Filter::Mitchell { radius, .. }
+            | Filter::Lanczos { radius } => radius,
+        }
+    }
+
+    pub fn eval(&self, x: f64, y: f64) -> f64 {
+        self.eval_1d(x) * self.eval_1d(y)
+    }
+
+    fn eval_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.0;
+        }
+        match *self {
+            Filter::Box { .. } => 1.0,
+            Filter::Tent { radius } => radius - x,
+            Filter::Gaussian { radius, alpha } => {
+                ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0.0)
+            }
+            Filter::Mitchell { radius, b, c } => mitchell(2.0 * x / radius, b, c),
+            Filter::Lanczos { radius } => sinc(x) * sinc(x / radius),
+        }
+    }
+}
+
+fn mitchell(x: f64, b: f64, c: f64) -> f64 {
+    if x > 1.0 {
+        ((-b - 6.0 * c) * x.powi(3)
+            + (6.0 * b + 30.0 * c) * x.powi(2)
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c))
+            / 6.0
+    } else {
+        ((12.0 - 9.0 * b - 6.0 * c) * x.powi(3)
+            + (-18.0 + 12.0 * b + 6.0 * c) * x.powi(2)
+            + (6.0 - 2.0 * b))
+            / 6.0
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        return 1.0;
+    }
+    (PI * x).sin() / (PI * x)
+}
@@ -0,0 +1,864 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::{
+    decode_png, srgb_to_linear, unit_vector, Camera, CameraBuilder, Color3, Diaelectric,
+    DirectionalLight, Image, ImageTexture, Json, Lambertian, List, Material, Mesh, Metal,
+    NormalMap, OpacityMask, Point3, PointLight, Projection, Scene, SpotLight, Subdivision, Texture,
+    TriangleMesh, Vec3, PI,
+};
+
+/// Contents of a glTF 2.0 file, ready to render.
+pub struct GltfScene {
+    pub scene: Scene,
+    /// One builder per camera node, with the view and projection set.
+    pub cameras: Vec<CameraBuilder>,
+    /// Parts of the file that were skipped, e.g. JPEG textures.
+    pub warnings: Vec<String>,
+}
+
+/// Reasons `load_gltf` fails.
+#[derive(Debug)]
+pub enum GltfError {
+    Io(io::Error),
+    /// Malformed JSON, GLB container or reference between objects.
+    Invalid(String),
+    /// Well-formed input needing a feature the loader lacks, such as a
+    /// required extension.
+    Unsupported(String),
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GltfError::Io(e) => write!(f, "{e}"),
+            GltfError::Invalid(what) => write!(f, "invalid glTF: {what}"),
+            GltfError::Unsupported(what) => write!(f, "unsupported glTF feature: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+impl From<io::Error> for GltfError {
+    fn from(e: io::Error) -> GltfError {
+        GltfError::Io(e)
+    }
+}
+
+/// Extensions the loader understands, so files may require them.
+const SUPPORTED_EXTENSIONS: [&str; 4] = [
+    "KHR_lights_punctual",
+    "KHR_materials_ior",
+    "KHR_materials_transmission",
+    "KHR_mesh_quantization",
+];
+
+/// Loads a `.gltf` (JSON with external or embedded buffers) or `.glb`
+/// file.
+///
+/// Node transforms are baked into world-space triangle meshes. Metallic-
+/// roughness materials map onto the closest built-in material: glass for
+/// `KHR_materials_transmission`, `Metal` with roughness as fuzz when
+/// metallic, textured `Lambertian` otherwise; normal textures and alpha
+/// masks are honored. Textures must be PNG. Punctual light intensities are
+/// used directly as this renderer's intensity and radiance. With
+/// `subdivision`, every primitive gets that many levels of the scheme,
+/// and smooth normals in place of its own.
+pub fn load_gltf(
+    path: &str,
+    subdivision: Option<(Subdivision, u32)>,
+) -> Result<GltfScene, GltfError> {
+    let bytes = fs::read(path)?;
+    let (json, bin) = if bytes.starts_with(b"glTF") {
+        split_glb(&bytes)?
+    } else {
+        let text = String::from_utf8(bytes).map_err(|_| invalid("file is not UTF-8"))?;
+        (text, None)
+    };
+    let json = Json::parse(&json).map_err(GltfError::Invalid)?;
+    let version = json
+        .get("asset")
+        .and_then(|a| a.get("version"))
+        .and_then(Json::as_str)
+        .unwrap_or("");
+    if !version.starts_with("2.") {
+        return Err(GltfError::Unsupported(format!("version {version:?}")));
+    }
+    for ext in array(&json, "extensionsRequired") {
+        let name = ext.as_str().unwrap_or("");
+        if !SUPPORTED_EXTENSIONS.contains(&name) {
+            return Err(GltfError::Unsupported(format!("required extension {name}")));
+        }
+    }
+
+    let base = Path::new(path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let mut loader = Loader {
+        json: &json,
+        base,
+        buffers: Vec::new(),
+        images: HashMap::new(),
+        materials: HashMap::new(),
+        subdivision,
+        out: GltfScene {
+            scene: Scene::new(),
+            cameras: Vec::new(),
+            warnings: Vec::new(),
+        },
+    };
+    let mut bin = bin;
+    for buffer in array(&json, "buffers") {
+        let data = match buffer.get("uri").and_then(Json::as_str) {
+            Some(uri) => loader.read_uri(uri)?,
+            None => bin
+                .take()
+                .ok_or(invalid("buffer without uri or GLB chunk"))?,
+        };
+        loader.buffers.push(data);
+    }
+
+    let roots = match json.get("scenes") {
+        Some(scenes) => {
+            let index = json.get("scene").and_then(Json::as_usize).unwrap_or(0);
+            let scene = scenes.at(index).ok_or(invalid("scene index"))?;
+            array(scene, "nodes")
+                .iter()
+                .map(|n| n.as_usize().ok_or(invalid("scene node index")))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+        // Without scenes, every node that is nobody's child is a root.
+        None => {
+            let nodes = array(&json, "nodes");
+            let children: Vec<usize> = nodes
+                .iter()
+                .flat_map(|n| array(n, "children"))
+                .filter_map(Json::as_usize)
+                .collect();
+            (0..nodes.len()).filter(|n| !children.contains(n)).collect()
+        }
+    };
+    for root in roots {
+        loader.node(root, &IDENTITY, 0)?;
+    }
+    Ok(loader.out)
+}
+
+/// Most values an accessor without a buffer view may hold.
+const MAX_ZERO_VALUES: usize = 1 << 24;
+
+fn invalid(what: &str) -> GltfError {
+    GltfError::Invalid(what.to_string())
+}
+
+/// Array member `key` of `value`, empty when missing.
+fn array<'a>(value: &'a Json, key: &str) -> &'a [Json] {
+    value
+        .get(key)
+        .and_then(Json::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+fn number(value: &Json, key: &str, default: f64) -> f64 {
+    value.get(key).and_then(Json::as_f64).unwrap_or(default)
+}
+
+/// JSON and binary chunks of a GLB container.
+fn split_glb(bytes: &[u8]) -> Result<(String, Option<Vec<u8>>), GltfError> {
+    let word = |pos: usize| -> Result<usize, GltfError> {
+        let b = bytes.get(pos..pos + 4).ok_or(invalid("truncated GLB"))?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+    if word(4)? != 2 {
+        return Err(GltfError::Unsupported(format!("GLB version {}", word(4)?)));
+    }
+    let total = word(8)?.min(bytes.len());
+    let (mut json, mut bin) = (None, None);
+    let mut pos = 12;
+    while pos + 8 <= total {
+        let (len, kind) = (word(pos)?, word(pos + 4)?);
+        let chunk = bytes
+            .get(pos + 8..pos + 8 + len)
+            .ok_or(invalid("truncated GLB chunk"))?;
+        match kind {
+            0x4e4f_534a => json = Some(chunk),
+            0x004e_4942 => bin = Some(chunk.to_vec()),
+            _ => {}
+        }
+        pos += 8 + len;
+    }
+    let json = json.ok_or(invalid("GLB without JSON chunk"))?;
+    let json = String::from_utf8(json.to_vec()).map_err(|_| invalid("JSON chunk is not UTF-8"))?;
+    Ok((json, bin))
+}
+
+/// Row-major 4x4 transform.
+type Mat4 = [[f64; 4]; 4];
+
+const IDENTITY: Mat4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+fn mat_mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut m = [[0.0; 4]; 4];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            *v = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    m
+}
+
+fn transform_point(m: &Mat4, p: Point3) -> Point3 {
+    let v = transform_vector(m, p);
+    v + Vec3::from(m[0][3], m[1][3], m[2][3])
+}
+
+fn transform_vector(m: &Mat4, v: Vec3) -> Vec3 {
+    Vec3::from(
+        m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+        m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+        m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+    )
+}
+
+/// Cofactors of the upper 3x3 block, i.e. its inverse transpose times its
+/// determinant, and the determinant.
+fn normal_matrix(m: &Mat4) -> (Mat4, f64) {
+    let mut c = IDENTITY;
+    for (i, row) in c.iter_mut().enumerate().take(3) {
+        for (j, v) in row.iter_mut().enumerate().take(3) {
+            let (i1, i2) = ((i + 1) % 3, (i + 2) % 3);
+            let (j1, j2) = ((j + 1) % 3, (j + 2) % 3);
+            *v = m[i1][j1] * m[i2][j2] - m[i1][j2] * m[i2][j1];
+        }
+    }
+    let det = (0..3).map(|j| m[0][j] * c[0][j]).sum();
+    (c, det)
+}
+
+/// Local transform of a node: its `matrix`, or translation * rotation *
+/// scale.
+fn local_transform(node: &Json) -> Mat4 {
+    if let Some(a) = node.get("matrix").and_then(Json::as_f64s) {
+        if a.len() == 16 {
+            // Stored column by column.
+            let mut m = IDENTITY;
+            for (i, row) in m.iter_mut().enumerate() {
+                for (j, v) in row.iter_mut().enumerate() {
+                    *v = a[j * 4 + i];
+                }
+            }
+            return m;
+        }
+    }
+    let vec = |key: &str, default: &[f64]| {
+        node.get(key)
+            .and_then(Json::as_f64s)
+            .filter(|v| v.len() == default.len())
+            .unwrap_or(default.to_vec())
+    };
+    let t = vec("translation", &[0.0, 0.0, 0.0]);
+    let q = vec("rotation", &[0.0, 0.0, 0.0, 1.0]);
+    let s = vec("scale", &[1.0, 1.0, 1.0]);
+    let (x, y, z, w) = (q[0], q[1], q[2], q[3]);
+    let r = [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+        ],
+        [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+        ],
+        [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ];
+    let mut m = IDENTITY;
+    for i in 0..3 {
+        for j in 0..3 {
+            m[i][j] = r[i][j] * s[j];
+        }
+        m[i][3] = t[i];
+    }
+    m
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut k = 0;
+    while k < bytes.len() {
+        let hex = bytes
+            .get(k + 1..k + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[k], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                k += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                k += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+struct Loader<'a> {
+    json: &'a Json,
+    base: PathBuf,
+    buffers: Vec<Vec<u8>>,
+    /// Decoded images by index; `None` for images that could not be used.
+    images: HashMap<usize, Option<Rc<Decoded>>>,
+    /// Materials by index, with the opacity of their surface when it is not
+    /// simply opaque.
+    materials: HashMap<Option<usize>, MaterialEntry>,
+    subdivision: Option<(Subdivision, u32)>,
+    out: GltfScene,
+}
+
+/// Decoded image with its alpha channel.
+type Decoded = (Image, Vec<f64>);
+
+type MaterialEntry = (Rc<RefCell<dyn Material>>, Option<Rc<dyn Texture>>);
+
+impl<'a> Loader<'a> {
+    fn warn(&mut self, message: String) {
+        if !self.out.warnings.contains(&message) {
+            self.out.warnings.push(message);
+        }
+    }
+
+    fn item(&self, kind: &str, index: usize) -> Result<&'a Json, GltfError> {
+        self.json
+            .get(kind)
+            .and_then(|items| items.at(index))
+            .ok_or(GltfError::Invalid(format!("no {kind}[{index}]")))
+    }
+
+    fn read_uri(&self, uri: &str) -> Result<Vec<u8>, GltfError> {
+        if let Some(data) = uri.strip_prefix("data:") {
+            let (_, payload) = data
+                .split_once(";base64,")
+                .ok_or(GltfError::Unsupported("data URI without base64".into()))?;
+            return base64_decode(payload).ok_or(invalid("bad base64 in data URI"));
+        }
+        Ok(fs::read(self.base.join(percent_decode(uri)))?)
+    }
+
+    fn node(&mut self, index: usize, parent: &Mat4, depth: usize) -> Result<(), GltfError> {
+        if depth > 64 {
+            return Err(invalid("node hierarchy too deep or cyclic"));
+        }
+        let node = self.item("nodes", index)?;
+        let world = mat_mul(parent, &local_transform(node));
+        if let Some(mesh) = node.get("mesh").and_then(Json::as_usize) {
+            self.mesh(mesh, &world)?;
+        }
+        if let Some(camera) = node.get("camera").and_then(Json::as_usize) {
+            self.camera(camera, &world)?;
+        }
+        let light = node
+            .get("extensions")
+            .and_then(|e| e.get("KHR_lights_punctual"))
+            .and_then(|l| l.get("light"))
+            .and_then(Json::as_usize);
+        if let Some(light) = light {
+            self.light(light, &world)?;
+        }
+        let children: Vec<usize> = array(node, "children")
+            .iter()
+            .filter_map(Json::as_usize)
+            .collect();
+        for child in children {
+            self.node(child, &world, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Elements of an accessor as `f64`s, `components` per element, with
+    /// normalized integers mapped to `[0, 1]` or `[-1, 1]`.
+    fn accessor(&self, index: usize) -> Result<(Vec<f64>, usize), GltfError> {
+        let acc = self.item("accessors", index)?;
+        if acc.get("sparse").is_some() {
+            return Err(GltfError::Unsupported("sparse accessors".into()));
+        }
+        let count = acc.get("count").and_then(Json::as_usize).unwrap_or(0);
+        let components = match acc.get("type").and_then(Json::as_str).unwrap_or("") {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            "MAT4" => 16,
+            other => return Err(GltfError::Unsupported(format!("accessor type {other}"))),
+        };
+        let kind = acc.get("componentType").and_then(Json::as_usize);
+        let size = match kind {
+            Some(5120 | 5121) => 1,
+            Some(5122 | 5123) => 2,
+            Some(5125 | 5126) => 4,
+            _ => return Err(invalid("accessor component type")),
+        };
+        let normalized = acc
+            .get("normalized")
+            .and_then(Json::as_bool)
+            .unwrap_or(false);
+        let len = count
+            .checked_mul(components)
+            .ok_or(invalid("accessor count"))?;
+        let Some(view) = acc.get("bufferView").and_then(Json::as_usize) else {
+            // All zeros. No buffer bounds the count, so it is capped.
+            if len > MAX_ZERO_VALUES {
+                return Err(invalid("accessor count"));
+            }
+            return Ok((vec![0.0; len], components));
+        };
+        let view = self.item("bufferViews", view)?;
+        let buffer = view
+            .get("buffer")
+            .and_then(Json::as_usize)
+            .and_then(|b| self.buffers.get(b))
+            .ok_or(invalid("buffer view buffer"))?;
+        let view_start = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let view_length = view
+            .get("byteLength")
+            .and_then(Json::as_usize)
+            .ok_or(invalid("buffer view length"))?;
+        let data = view_start
+            .checked_add(view_length)
+            .and_then(|end| buffer.get(view_start..end))
+            .ok_or(invalid("buffer view out of buffer bounds"))?;
+        let start = acc.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let stride = view
+            .get("byteStride")
+            .and_then(Json::as_usize)
+            .unwrap_or(size * components);
+        // Checked before allocating, since `count` comes from the file.
+        if count > 0 {
+            let end = (count - 1)
+                .checked_mul(stride)
+                .and_then(|last| last.checked_add(start))
+                .and_then(|last| last.checked_add(size * components));
+            if end.is_none_or(|end| end > data.len()) {
+                return Err(invalid("accessor out of buffer view bounds"));
+            }
+        }
+
+        let mut values = Vec::with_capacity(len);
+        for e in 0..count {
+            for c in 0..components {
+                let at = start + e * stride + c * size;
+                let b = &data[at..at + size];
+                let v = match kind {
+                    Some(5120) if normalized => (b[0] as i8 as f64 / 127.0).max(-1.0),
+                    Some(5120) => b[0] as i8 as f64,
+                    Some(5121) if normalized => b[0] as f64 / 255.0,
+                    Some(5121) => b[0] as f64,
+                    Some(5122) if normalized => {
+                        (i16::from_le_bytes([b[0], b[1]]) as f64 / 32767.0).max(-1.0)
+                    }
+                    Some(5122) => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    Some(5123) if normalized => u16::from_le_bytes([b[0], b[1]]) as f64 / 65535.0,
+                    Some(5123) => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    Some(5125) => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                };
+                values.push(v);
+            }
+        }
+        Ok((values, components))
+    }
+
+    fn vec3s(&self, index: usize) -> Result<Vec<Vec3>, GltfError> {
+        let (values, components) = self.accessor(index)?;
+        if components != 3 {
+            return Err(invalid("expected a VEC3 accessor"));
+        }
+        Ok(values
+            .chunks(3)
+            .map(|c| Vec3::from(c[0], c[1], c[2]))
+            .collect())
+    }
+
+    fn mesh(&mut self, index: usize, world: &Mat4) -> Result<(), GltfError> {
+        let (normals_to_world, det) = normal_matrix(world);
+        let primitives = array(self.item("meshes", index)?, "primitives");
+        for primitive in primitives {
+            let mode = primitive.get("mode").and_then(Json::as_usize).unwrap_or(4);
+            if mode != 4 {
+                self.warn(format!("skipped primitives with mode {mode}"));
+                continue;
+            }
+            let attribute = |name: &str| {
+                primitive
+                    .get("attributes")
+                    .and_then(|a| a.get(name))
+                    .and_then(Json::as_usize)
+            };
+            let position = attribute("POSITION").ok_or(invalid("primitive without POSITION"))?;
+            let mut mesh = Mesh::new();
+            mesh.positions = self
+                .vec3s(position)?
+                .into_iter()
+                .map(|p| transform_point(world, p))
+                .collect();
+            if let Some(normal) = attribute("NORMAL") {
+                // Mirroring transforms turn the cofactors inside out.
+                let sign = if det < 0.0 { -1.0 } else { 1.0 };
+                mesh.normals = self
+                    .vec3s(normal)?
+                    .into_iter()
+                    .map(|n| unit_vector(sign * transform_vector(&normals_to_world, n)))
+                    .collect();
+            }
+            if let Some(uv) = attribute("TEXCOORD_0") {
+                // glTF puts v = 0 at the top of the image.
+                let (values, components) = self.accessor(uv)?;
+                if components != 2 {
+                    return Err(invalid("expected a VEC2 accessor"));
+                }
+                mesh.uvs = values.chunks(2).map(|c| (c[0], 1.0 - c[1])).collect();
+            }
+            if let Some(color) = attribute("COLOR_0") {
+                // Linear RGB or RGBA; alpha is not used.
+                let (values, components) = self.accessor(color)?;
+                mesh.colors = values
+                    .chunks(components.max(1))
+                    .filter(|c| c.len() >= 3)
+                    .map(|c| Color3::from(c[0], c[1], c[2]))
+                    .collect();
+            }
+            let n = mesh.positions.len();
+            if [mesh.normals.len(), mesh.uvs.len(), mesh.colors.len()]
+                .iter()
+                .any(|len| *len != 0 && *len != n)
+            {
+                return Err(invalid("attribute counts differ from POSITION"));
+            }
+            let indices: Vec<usize> = match primitive.get("indices").and_then(Json::as_usize) {
+                Some(indices) => self
+                    .accessor(indices)?
+                    .0
+                    .into_iter()
+                    .map(|i| i as usize)
+                    .collect(),
+                None => (0..mesh.positions.len()).collect(),
+            };
+            for tri in indices.chunks_exact(3) {
+                if tri.iter().any(|i| *i >= mesh.positions.len()) {
+                    return Err(invalid("vertex index out of range"));
+                }
+                // Keep counter-clockwise winding under mirroring.
+                if det < 0.0 {
+                    mesh.faces.push(vec![tri[0], tri[2], tri[1]]);
+                } else {
+                    mesh.faces.push(tri.to_vec());
+                }
+            }
+            if mesh.faces.is_empty() {
+                continue;
+            }
+            let material = primitive.get("material").and_then(Json::as_usize);
+            let (mat, opacity) = self.material(material)?;
+            if let Some((scheme, levels)) = self.subdivision {
+                mesh = mesh.subdivide(scheme, levels);
+            }
+            let object = Rc::new(RefCell::new(TriangleMesh::new(&mesh, mat)));
+            match opacity {
+                Some(mask) => self
+                    .out
+                    .scene
+                    .world
+                    .add(Rc::new(RefCell::new(OpacityMask::new(object, mask)))),
+                None => self.out.scene.world.add(object),
+            }
+        }
+        Ok(())
+    }
+
+    fn image(&mut self, index: usize) -> Result<Option<Rc<Decoded>>, GltfError> {
+        if let Some(image) = self.images.get(&index) {
+            return Ok(image.clone());
+        }
+        let info = self.item("images", index)?;
+        let bytes = match (
+            info.get("uri").and_then(Json::as_str),
+            info.get("bufferView").and_then(Json::as_usize),
+        ) {
+            (Some(uri), _) => self.read_uri(uri)?,
+            (None, Some(view)) => {
+                let view = self.item("bufferViews", view)?;
+                let start = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+                let len = view.get("byteLength").and_then(Json::as_usize).unwrap_or(0);
+                view.get("buffer")
+                    .and_then(Json::as_usize)
+                    .and_then(|b| self.buffers.get(b))
+                    .and_then(|b| b.get(start..start + len))
+                    .ok_or(invalid("image buffer view"))?
+                    .to_vec()
+            }
+            _ => return Err(invalid("image without uri or bufferView")),
+        };
+        let image = decode_png(&bytes).map(Rc::new);
+        if image.is_none() {
+            let kind = if bytes.starts_with(&[0xff, 0xd8]) {
+                "a JPEG"
+            } else {
+                "not a readable PNG"
+            };
+            self.warn(format!("image {index} is {kind}; its textures are ignored"));
+        }
+        self.images.insert(index, image.clone());
+        Ok(image)
+    }
+
+    /// Color of texture info `info` (e.g. `baseColorTexture`), decoded from
+    /// sRGB when `srgb` is set.
+    fn texture(
+        &mut self,
+        info: Option<&Json>,
+        srgb: bool,
+    ) -> Result<Option<Rc<dyn Texture>>, GltfError> {
+        let Some(image) = self.texture_image(info)? else {
+            return Ok(None);
+        };
+        let mut image = image.0.clone();
+        if srgb {
+            for p in image.pixels.iter_mut() {
+                *p = Color3::from(
+                    srgb_to_linear(p.x()),
+                    srgb_to_linear(p.y()),
+                    srgb_to_linear(p.z()),
+                );
+            }
+        }
+        Ok(Some(Rc::new(ImageTexture::new(image))))
+    }
+
+    fn texture_image(&mut self, info: Option<&Json>) -> Result<Option<Rc<Decoded>>, GltfError> {
+        let Some(info) = info else {
+            return Ok(None);
+        };
+        if info.get("texCoord").and_then(Json::as_usize).unwrap_or(0) != 0 {
+            self.warn("textures on texCoord sets other than 0 are ignored".into());
+            return Ok(None);
+        }
+        let texture = info
+            .get("index")
+            .and_then(Json::as_usize)
+            .ok_or(invalid("texture info without index"))?;
+        let source = self
+            .item("textures", texture)?
+            .get("source")
+            .and_then(Json::as_usize);
+        match source {
+            Some(source) => self.image(source),
+            None => Ok(None),
+        }
+    }
+
+    fn material(&mut self, index: Option<usize>) -> Result<MaterialEntry, GltfError> {
+        if let Some(entry) = self.materials.get(&index) {
+            return Ok(entry.clone());
+        }
+        let Some(index) = index else {
+            let entry: MaterialEntry = (
+                Rc::new(RefCell::new(Lambertian::from(Color3::from(0.8, 0.8, 0.8)))),
+                None,
+            );
+            self.materials.insert(None, entry.clone());
+            return Ok(entry);
+        };
+        let m = self.item("materials", index)?;
+        let pbr = m.get("pbrMetallicRoughness").unwrap_or(&Json::Null);
+        let factor = pbr
+            .get("baseColorFactor")
+            .and_then(Json::as_f64s)
+            .filter(|f| f.len() == 4)
+            .unwrap_or(vec![1.0; 4]);
+        let base = Color3::from(factor[0], factor[1], factor[2]);
+        let metallic = number(pbr, "metallicFactor", 1.0);
+        let roughness = number(pbr, "roughnessFactor", 1.0);
+        let extension = |name: &str| m.get("extensions").and_then(|e| e.get(name));
+        let transmission = extension("KHR_materials_transmission")
+            .map(|t| number(t, "transmissionFactor", 0.0))
+            .unwrap_or(0.0);
+        let ior = extension("KHR_materials_ior")
+            .map(|t| number(t, "ior", 1.5))
+            .unwrap_or(1.5);
+        let emissive = m.get("emissiveFactor").and_then(Json::as_f64s);
+        let base_info = pbr.get("baseColorTexture");
+        let normal_info = m.get("normalTexture");
+        let normal_scale = normal_info.map(|n| number(n, "scale", 1.0)).unwrap_or(1.0);
+        let alpha_mode = m
+            .get("alphaMode")
+            .and_then(Json::as_str)
+            .unwrap_or("OPAQUE");
+        let cutoff = number(m, "alphaCutoff", 0.5);
+
+        if emissive.is_some_and(|e| e.iter().any(|v| *v > 0.0)) {
+            self.warn("emissive materials are rendered without emission".into());
+        }
+        let mut mat: Rc<RefCell<dyn Material>> = if transmission > 0.5 {
+            Rc::new(RefCell::new(Diaelectric::from(ior)))
+        } else if metallic >= 0.5 {
+            Rc::new(RefCell::new(Metal::from(base, roughness)))
+        } else {
+            match self.texture(base_info, true)? {
+                Some(texture) => Rc::new(RefCell::new(Lambertian::from_texture(base, texture))),
+                None => Rc::new(RefCell::new(Lambertian::from(base))),
+            }
+        };
+        if let Some(map) = self.texture(normal_info, false)? {
+            mat = Rc::new(RefCell::new(NormalMap::from(mat, map, normal_scale)));
+        }
+
+        let opacity: Option<Rc<dyn Texture>> = match alpha_mode {
+            "MASK" | "BLEND" => {
+                let mask = alpha_mode == "MASK";
+                let alpha_of = move |a: f64| {
+                    let a = if mask { (a >= cutoff) as u8 as f64 } else { a };
+                    Color3::from(a, a, a)
+                };
+                match self.texture_image(base_info)? {
+                    Some(image) => {
+                        let mut alpha = image.0.clone();
+                        for (p, a) in alpha.pixels.iter_mut().zip(image.1.iter()) {
+                            *p = Color3::from(*a, *a, *a);
+                        }
+                        let alpha = ImageTexture::new(alpha);
+                        let scale = factor[3];
+                        Some(Rc::new(move |u: f64, v: f64, p: Point3| {
+                            alpha_of(scale * alpha.value(u, v, p).x())
+                        }))
+                    }
+                    None if alpha_of(factor[3]).x() < 1.0 => {
+                        let a = alpha_of(factor[3]);
+                        Some(Rc::new(move |_u: f64, _v: f64, _p: Point3| a))
+                    }
+                    None => None,
+                }
+            }
+            _ => None,
+        };
+        let entry: MaterialEntry = (mat, opacity);
+        self.materials.insert(Some(index), entry.clone());
+        Ok(entry)
+    }
+
+    fn camera(&mut self, index: usize, world: &Mat4) -> Result<(), GltfError> {
+        let camera = self.item("cameras", index)?;
+        let eye = transform_point(world, Point3::new());
+        let forward = unit_vector(transform_vector(world, Vec3::from(0.0, 0.0, -1.0)));
+        let up = transform_vector(world, Vec3::from(0.0, 1.0, 0.0));
+        let mut builder = Camera::builder()
+            .lookfrom(eye)
+            .lookat(eye + forward)
+            .vup(up);
+        match camera.get("type").and_then(Json::as_str) {
+            Some("perspective") => {
+                let p = camera
+                    .get("perspective")
+                    .ok_or(invalid("perspective camera without parameters"))?;
+                builder = builder.fov(number(p, "yfov", PI / 4.0).to_degrees());
+                if let Some(aspect) = p.get("aspectRatio").and_then(Json::as_f64) {
+                    builder = builder.aspect_ratio(aspect);
+                }
+            }
+            Some("orthographic") => {
+                let o = camera
+                    .get("orthographic")
+                    .ok_or(invalid("orthographic camera without parameters"))?;
+                let (xmag, ymag) = (number(o, "xmag", 1.0), number(o, "ymag", 1.0));
+                builder = builder.projection(Projection::Orthographic { height: 2.0 * ymag });
+                if ymag > 0.0 {
+                    builder = builder.aspect_ratio(xmag / ymag);
+                }
+            }
+            other => return Err(GltfError::Unsupported(format!("camera type {other:?}"))),
+        }
+        self.out.cameras.push(builder);
+        Ok(())
+    }
+
+    fn light(&mut self, index: usize, world: &Mat4) -> Result<(), GltfError> {
+        let light = self
+            .json
+            .get("extensions")
+            .and_then(|e| e.get("KHR_lights_punctual"))
+            .and_then(|l| l.get("lights"))
+            .and_then(|l| l.at(index))
+            .ok_or(GltfError::Invalid(format!("no punctual light {index}")))?;
+        let color = light
+            .get("color")
+            .and_then(Json::as_f64s)
+            .filter(|c| c.len() == 3)
+            .unwrap_or(vec![1.0; 3]);
+        let intensity =
+            number(light, "intensity", 1.0) * Color3::from(color[0], color[1], color[2]);
+        let position = transform_point(world, Point3::new());
+        let direction = unit_vector(transform_vector(world, Vec3::from(0.0, 0.0, -1.0)));
+        let lights = &mut self.out.scene.lights;
+        match light.get("type").and_then(Json::as_str) {
+            Some("directional") => lights.add(Rc::new(DirectionalLight::new(direction, intensity))),
+            Some("point") => lights.add(Rc::new(PointLight::new(position, intensity))),
+            Some("spot") => {
+                let spot = light.get("spot").unwrap_or(&Json::Null);
+                let inner = number(spot, "innerConeAngle", 0.0).to_degrees();
+                let outer = number(spot, "outerConeAngle", PI / 4.0).to_degrees();
+                lights.add(Rc::new(SpotLight::new(
+                    position,
+                    position + direction,
+                    intensity,
+                    outer,
+                    inner,
+                )));
+            }
+            other => return Err(GltfError::Unsupported(format!("light type {other:?}"))),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    count, cross, intersect_triangle, luminance, unit_vector, Aabb, Counter, HitRecord, Hittable,
+    Image, Interval, Json, Material, MaterialVisitor, Perlin, Point3, Ray, SceneWriter, Vec3,
+};
+
+/// Terrain from a regular grid of heights. Sample `(i, k)` sits at
+/// `corner + (i / (nx - 1) * size.x, h * size.y, k / (nz - 1) * size.z)`
+/// for height `h`, and each grid cell is split into two triangles.
+///
+/// Rays walk the cells they cross in order (2D DDA), skipping cells whose
+/// height range they pass above or below, so cost grows with the length of
+/// the path over the grid rather than with the number of triangles.
+pub struct HeightField {
+    heights: Vec<f64>,
+    nx: usize,
+    nz: usize,
+    corner: Point3,
+    size: Vec3,
+    /// Lowest and highest height of each cell, in world units.
+    cell_range: Vec<(f64, f64)>,
+    bbox: Aabb,
+    mat: Rc<RefCell<dyn Material>>,
+}
+
+impl HeightField {
+    /// `heights` holds `nx * nz` samples, row by row along x; needs at least
+    /// two samples in each direction and a positive, finite `size` along x
+    /// and z.
+    pub fn new(
+        heights: Vec<f64>,
+        nx: usize,
+        nz: usize,
+        corner: Point3,
+        size: Vec3,
+        mat: Rc<RefCell<dyn Material>>,
+    ) -> Result<HeightField, String> {
+        check_grid(nx, nz, size)?;
+        if nx.checked_mul(nz) != Some(heights.len()) {
+            return Err(format!("{} heights for a {nx}x{nz} grid", heights.len()));
+        }
+        let mut field = HeightField {
+            heights,
+            nx,
+            nz,
+            corner,
+            size,
+            cell_range: Vec::with_capacity((nx - 1) * (nz - 1)),
+            bbox: Aabb::new(),
+            mat,
+        };
+        for k in 0..nz - 1 {
+            for i in 0..nx - 1 {
+                let ys = [
+                    field.vertex(i, k).y(),
+                    field.vertex(i + 1, k).y(),
+                    field.vertex(i, k + 1).y(),
+                    field.vertex(i + 1, k + 1).y(),
+                ];
+                let lo = ys.iter().cloned().fold(f64::INFINITY, f64::min);
+                let hi = ys.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                field.cell_range.push((lo, hi));
+            }
+        }
+        let lo = field
+            .cell_range
+            .iter()
+            .map(|c| c.0)
+            .fold(f64::INFINITY, f64::min);
+        let hi = field
+            .cell_range
+            .iter()
+            .map(|c| c.1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        // Padded so a perfectly flat field still has a box rays can enter.
+        field.bbox = Aabb::from(
+            Point3::from(corner.x(), lo - 1e-4, corner.z()),
+            Point3::from(corner.x() + size.x(), hi + 1e-4, corner.z() + size.z()),
+        );
+        Ok(field)
+    }
+
+    /// Heights from the luminance of a grayscale image, one sample per
+    /// pixel; image rows run along +z.
+    pub fn from_image(
+        image: &Image,
+        corner: Point3,
+        size: Vec3,
+        mat: Rc<RefCell<dyn Material>>,
+    ) -> Result<HeightField, String> {
+        let heights = image.pixels.iter().map(|p| luminance(*p)).collect();
+        HeightField::new(
+            heights,
+            image.w as usize,
+            image.h as usize,
+            corner,
+            size,
+            mat,
+        )
+    }
+
+    /// Fractal Perlin terrain with `octaves` layers and `frequency` base
+    /// features across the field, normalized to heights in `[0, 1]`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_noise(
+        nx: usize,
+        nz: usize,
+        frequency: f64,
+        octaves: u32,
+        seed: u64,
+        corner: Point3,
+        size: Vec3,
+        mat: Rc<RefCell<dyn Material>>,
+    ) -> Result<HeightField, String> {
+        check_grid(nx, nz, size)?;
+        let perlin = Perlin::new(seed);
+        let mut heights = Vec::with_capacity(nx * nz);
+        for k in 0..nz {
+            for i in 0..nx {
+                let p = Point3::from(
+                    frequency * i as f64 / (nx - 1) as f64,
+                    0.5,
+                    frequency * k as f64 / (nz - 1) as f64,
+                );
+                heights.push(perlin.fbm(p, octaves));
+            }
+        }
+        let lo = heights.iter().cloned().fold(f64::INFINITY, f64::min);
+        let hi = heights.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let range = (hi - lo).max(1e-12);
+        for h in heights.iter_mut() {
+            *h = (*h - lo) / range;
+        }
+        HeightField::new(heights, nx, nz, corner, size, mat)
+    }
+
+    fn vertex(&self, i: usize, k: usize) -> Point3 {
+        let h = self.heights[k * self.nx + i];
+        self.corner
+            + Vec3::from(
+                i as f64 / (self.nx - 1) as f64 * self.size.x(),
+                h * self.size.y(),
+                k as f64 / (self.nz - 1) as f64 * self.size.z(),
+            )
+    }
+
+    /// Nearest hit with the two triangles of cell `(i, k)`.
+    fn hit_cell(&self, r: Ray, i: usize, k: usize, ray_root: &Interval) -> Option<(f64, Vec3)> {
+        count(Counter::IntersectionTests);
+        let p00 = self.vertex(i, k);
+        let p10 = self.vertex(i + 1, k);
+        let p01 = self.vertex(i, k + 1);
+        let p11 = self.vertex(i + 1, k + 1);
+        let mut best: Option<(f64, Vec3)> = None;
+        for (a, b, c) in [(p00, p01, p10), (p10, p01, p11)] {
+            if let Some((t, _, _)) = intersect_triangle(r, a, b, c) {
+                if ray_root.surrounds(t) && best.is_none_or(|h| t < h.0) {
+                    best = Some((t, cross(b - a, c - a)));
+                }
+            }
+        }
+        best
+    }
+}
+
+fn check_grid(nx: usize, nz: usize, size: Vec3) -> Result<(), String> {
+    if nx < 2 || nz < 2 {
+        return Err(format!(
+            "a height field needs at least 2x2 samples, got {nx}x{nz}"
+        ));
+    }
+    let extent_ok = |s: f64| s > 0.0 && s.is_finite();
+    if !(extent_ok(size.x()) && extent_ok(size.z()) && size.y().is_finite()) {
+        return Err(format!(
+            "a height field needs a positive, finite size, got {} x {} x {}",
+            size.x(),
+            size.y(),
+            size.z()
+        ));
+    }
+    Ok(())
+}
+
+impl Hittable for HeightField {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        let Some(span) = self.bbox.clip(r, ray_root.clone()) else {
+            return false;
+        };
+        let cells = ((self.nx - 1) as f64, (self.nz - 1) as f64);
+        let cell = (self.size.x() / cells.0, self.size.z() / cells.1);
+        let (o, d) = (r.origin(), r.direction());
+
+        // Cell of the entry point, then the DDA state along x and z.
+        let start = r.at(span.min);
+        let to_cell =
+            |v: f64, c0: f64, w: f64, n: f64| (((v - c0) / w).floor()).clamp(0.0, n - 1.0);
+        let mut i = to_cell(start.x(), self.corner.x(), cell.0, cells.0) as i64;
+        let mut k = to_cell(start.z(), self.corner.z(), cell.1, cells.1) as i64;
+        let axis = |pos: i64, o: f64, d: f64, c0: f64, w: f64| -> (i64, f64, f64) {
+            if d > 0.0 {
+                (1, (c0 + (pos + 1) as f64 * w - o) / d, w / d)
+            } else if d < 0.0 {
+                (-1, (c0 + pos as f64 * w - o) / d, -w / d)
+            } else {
+                (0, f64::INFINITY, f64::INFINITY)
+            }
+        };
+        let (step_x, mut next_x, delta_x) = axis(i, o.x(), d.x(), self.corner.x(), cell.0);
+        let (step_z, mut next_z, delta_z) = axis(k, o.z(), d.z(), self.corner.z(), cell.1);
+
+        let mut t_enter = span.min;
+        while i >= 0 && k >= 0 && i < cells.0 as i64 && k < cells.1 as i64 && t_enter <= span.max {
+            let t_exit = next_x.min(next_z).min(span.max);
+            let (lo, hi) = self.cell_range[k as usize * (self.nx - 1) + i as usize];
+            let (y0, y1) = (r.at(t_enter).y(), r.at(t_exit).y());
+            if y0.min(y1) <= hi && y0.max(y1) >= lo {
+                if let Some((t, n)) = self.hit_cell(r, i as usize, k as usize, &ray_root) {
+                    rec.t = t;
+                    rec.p = r.at(t);
+                    rec.set_face_normal(r, unit_vector(n));
+                    rec.u = (rec.p.x() - self.corner.x()) / self.size.x();
+                    rec.v = (rec.p.z() - self.corner.z()) / self.size.z();
+                    // Steps in u and v follow the triangle's slope.
+                    let (sx, sz) = (self.size.x(), self.size.z());
+                    rec.set_tangents(
+                        Vec3::from(sx, -n.x() / n.y() * sx, 0.0),
+                        Vec3::from(0.0, -n.z() / n.y() * sz, sz),
+                    );
+                    rec.mat = self.mat.clone();
+                    return true;
+                }
+            }
+            if next_x < next_z {
+                i += step_x;
+                t_enter = next_x;
+                next_x += delta_x;
+            } else {
+                k += step_z;
+                t_enter = next_z;
+                next_z += delta_z;
+            }
+        }
+        false
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        let heights: Vec<Json> = self.heights.iter().map(|h| (*h).into()).collect();
+        Some(Json::object([
+            ("type", "heightfield".into()),
+            ("heights", heights.into()),
+            ("nx", self.nx.into()),
+            ("nz", self.nz.into()),
+            ("corner", self.corner.into()),
+            ("size", self.size.into()),
+            ("material", out.material(&self.mat)),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        f(&self.mat);
+    }
+}
@@ -0,0 +1,236 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::{
+    cross, dot, Aabb, Color3, Interval, Json, Lambertian, Material, Onb, Point3, Ray, SceneWriter,
+    Vec3,
+};
+
+#[derive(Clone)]
+pub struct HitRecord {
+    pub p: Point3,
+    /// Shading normal, on the same side of the surface as `geometric_normal`.
+    pub normal: Vec3,
+    /// True surface normal, facing against the ray.
+    pub geometric_normal: Vec3,
+    pub t: f64,
+    /// Surface coordinates of the hit, each in `[0, 1]`.
+    pub u: f64,
+    pub v: f64,
+    /// Derivatives of the hit point along `u` and `v`, the tangent frame
+    /// for normal and bump mapping.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// Interpolated vertex color, white for surfaces without one. Materials
+    /// tint their albedo with it.
+    pub color: Color3,
+    pub front_face: bool,
+    pub mat: Rc<RefCell<dyn Material>>,
+    /// 1-based index of the top-level object hit in the world list.
+    pub object_id: u32,
+}
+
+impl Default for HitRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HitRecord {
+    /// Sets the geometric normal from the outward normal `out_norm`, flipped
+    /// to face the ray. The shading normal starts out equal to it, and the
+    /// tangents to an arbitrary frame around it until `set_tangents`, and
+    /// the vertex color to white.
+    pub fn set_face_normal(&mut self, r: Ray, out_norm: Vec3) {
+        self.front_face = dot(r.direction(), out_norm) < 0.0;
+        if self.front_face {
+            self.geometric_normal = out_norm;
+        } else {
+            self.geometric_normal = -out_norm;
+        }
+        self.normal = self.geometric_normal;
+        let frame = Onb::from_w(out_norm);
+        (self.dpdu, self.dpdv) = (frame.u, frame.v);
+        self.color = Color3::from(1.0, 1.0, 1.0);
+    }
+
+    /// Sets the surface derivatives, keeping the previous frame where they
+    /// are degenerate, e.g. at the poles of a sphere.
+    pub fn set_tangents(&mut self, dpdu: Vec3, dpdv: Vec3) {
+        let n = cross(dpdu, dpdv);
+        if n.length_squared() > 0.0 && n.length_squared().is_finite() {
+            (self.dpdu, self.dpdv) = (dpdu, dpdv);
+        }
+    }
+
+    /// Replaces the shading normal with the outward `out_norm`, e.g. an
+    /// interpolated vertex normal, flipped to the side the geometric normal
+    /// was flipped to by `set_face_normal`.
+    pub fn set_shading_normal(&mut self, out_norm: Vec3) {
+        self.normal = if self.front_face { out_norm } else { -out_norm };
+    }
+
+    pub fn new() -> HitRecord {
+        HitRecord {
+            p: Point3::new(),
+            normal: Vec3::new(),
+            geometric_normal: Vec3::new(),
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            dpdu: Vec3::new(),
+            dpdv: Vec3::new(),
+            color: Color3::from(1.0, 1.0, 1.0),
+            front_face: false,
+            mat: Rc::new(RefCell::new(Lambertian::from(Color3::new()))),
+            object_id: 0,
+        }
+    }
+
+    pub fn copy(&mut self, rec: &HitRecord) {
+        self.p = rec.p;
+        self.front_face = rec.front_face;
+        self.normal = rec.normal;
+        self.geometric_normal = rec.geometric_normal;
+        self.t = rec.t;
+        self.u = rec.u;
+        self.v = rec.v;
+        self.dpdu = rec.dpdu;
+        self.dpdv = rec.dpdv;
+        self.color = rec.color;
+        self.mat = rec.mat.clone();
+        self.object_id = rec.object_id;
+    }
+}
+
+/// Callback of `Hittable::for_each_material`.
+pub type MaterialVisitor<'a> = dyn FnMut(&Rc<RefCell<dyn Material>>) + 'a;
+
+pub trait Hittable {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool;
+
+    fn bounding_box(&self) -> Aabb;
+
+    /// Scene file description of the object, `None` for objects that
+    /// cannot be saved.
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        None
+    }
+
+    /// Calls `f` with every material of the object, in a fixed order, so
+    /// materials can be numbered when the scene is built.
+    fn for_each_material(&self, _f: &mut MaterialVisitor) {}
+
+    /// Appends every crossing of `r` with the surface inside `ray_root` to
+    /// `hits`, ordered by `t`, and returns whether there was any. The default
+    /// walks along the ray with repeated `hit` calls; closed shapes with a
+    /// direct solution override it.
+    fn hit_all(&self, r: Ray, ray_root: Interval, hits: &mut Vec<HitRecord>) -> bool {
+        let start = hits.len();
+        let mut t_min = ray_root.min;
+        let mut rec = HitRecord::new();
+        while self.hit(r, Interval::from(t_min, ray_root.max), &mut rec) {
+            t_min = rec.t + 1e-9 * rec.t.abs().max(1.0);
+            hits.push(rec.clone());
+        }
+        hits.len() > start
+    }
+}
+
+pub struct HittableList {
+    objects: Vec<Rc<RefCell<dyn Hittable>>>,
+    bbox: Aabb,
+    /// 1-based material numbers by address, in the order objects were added.
+    material_ids: HashMap<*const (), u32>,
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        let mut temp_rec = HitRecord::new();
+        let mut hit_any = false;
+        let mut closest = ray_root.max;
+
+        for (idx, i) in self.objects.iter().enumerate() {
+            if i.borrow()
+                .hit(r, Interval::from(ray_root.min, closest), &mut temp_rec)
+            {
+                hit_any = true;
+                closest = temp_rec.t;
+                rec.copy(&temp_rec);
+                rec.object_id = idx as u32 + 1;
+            }
+        }
+
+        hit_any
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        let objects: Vec<Json> = self.objects.iter().filter_map(|o| out.object(o)).collect();
+        Some(Json::object([
+            ("type", "list".into()),
+            ("objects", objects.into()),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        for object in &self.objects {
+            object.borrow().for_each_material(f);
+        }
+    }
+}
+
+pub trait List {
+    fn clear(&mut self);
+    fn add(&mut self, el: Rc<RefCell<dyn Hittable>>);
+}
+
+impl List for HittableList {
+    fn add(&mut self, el: Rc<RefCell<dyn Hittable>>) {
+        self.bbox = Aabb::from_boxes(&self.bbox, &el.borrow().bounding_box());
+        let ids = &mut self.material_ids;
+        el.borrow().for_each_material(&mut |mat| {
+            let next = ids.len() as u32 + 1;
+            ids.entry(Rc::as_ptr(mat) as *const ()).or_insert(next);
+        });
+        self.objects.push(el);
+    }
+
+    fn clear(&mut self) {
+        self.objects.clear();
+        self.bbox = Aabb::new();
+        self.material_ids.clear();
+    }
+}
+
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HittableList {
+    pub fn new() -> HittableList {
+        HittableList {
+            objects: Vec::new(),
+            bbox: Aabb::new(),
+            material_ids: HashMap::new(),
+        }
+    }
+
+    pub fn objects(&self) -> &[Rc<RefCell<dyn Hittable>>] {
+        &self.objects
+    }
+
+    /// Number of `mat` among the materials of the objects in the list,
+    /// counted from 1 in the order they were added; 0 for other materials.
+    /// Unlike addresses, the numbers are the same from run to run.
+    pub fn material_id(&self, mat: &Rc<RefCell<dyn Material>>) -> u32 {
+        let key = Rc::as_ptr(mat) as *const ();
+        self.material_ids.get(&key).copied().unwrap_or(0)
+    }
+}
@@ -0,0 +1,133 @@
+use std::fs;
+use std::io;
+
+use crate::{decode_png, Color3};
+
+/// Row-major RGB float image.
+#[derive(Clone)]
+pub struct Image {
+    pub w: u64,
+    pub h: u64,
+    pub pixels: Vec<Color3>,
+}
+
+impl Image {
+    /// Loads a binary or ASCII PGM/PPM (P2, P3, P5, P6), normalizing samples
+    /// to `[0, 1]`. Values are returned as stored, without decoding sRGB.
+    pub fn load_pnm(path: &str) -> io::Result<Image> {
+        let bytes = fs::read(path)?;
+        parse_pnm(&bytes).ok_or(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{path} is not a supported PNM file"),
+        ))
+    }
+
+    /// Loads a PNG, dropping any alpha channel. Like `load_pnm`, samples
+    /// are returned as stored.
+    pub fn load_png(path: &str) -> io::Result<Image> {
+        let bytes = fs::read(path)?;
+        decode_png(&bytes)
+            .map(|(image, _)| image)
+            .ok_or(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{path} is not a supported PNG file"),
+            ))
+    }
+
+    /// Writes the linear float values as a color PFM (little endian).
+    pub fn write_pfm(&self, path: &str) -> io::Result<()> {
+        let mut out = format!("PF\n{} {}\n-1.0\n", self.w, self.h).into_bytes();
+        // PFM stores scanlines bottom to top.
+        for row in self.pixels.chunks(self.w.max(1) as usize).rev() {
+            for clr in row {
+                for k in 0..3 {
+                    out.extend_from_slice(&(clr[k] as f32).to_le_bytes());
+                }
+            }
+        }
+        fs::write(path, out)
+    }
+}
+
+fn parse_pnm(bytes: &[u8]) -> Option<Image> {
+    let mut pos = 0;
+    let magic = next_token(bytes, &mut pos)?;
+    let (channels, binary) = match magic.as_str() {
+        "P2" => (1, false),
+        "P3" => (3, false),
+        "P5" => (1, true),
+        "P6" => (3, true),
+        _ => return None,
+    };
+    let w: u64 = next_token(bytes, &mut pos)?.parse().ok()?;
+    let h: u64 = next_token(bytes, &mut pos)?.parse().ok()?;
+    let maxval: u32 = next_token(bytes, &mut pos)?.parse().ok()?;
+    if maxval == 0 || maxval > 65535 {
+        return None;
+    }
+    // A single whitespace byte separates the header from binary data.
+    pos += 1;
+
+    let count = (w * h * channels) as usize;
+    let mut samples = Vec::with_capacity(count);
+    if binary {
+        let wide = maxval > 255;
+        for _ in 0..count {
+            let v = if wide {
+                let v = u16::from_be_bytes([*bytes.get(pos)?, *bytes.get(pos + 1)?]);
+                pos += 2;
+                v as u32
+            } else {
+                pos += 1;
+                *bytes.get(pos - 1)? as u32
+            };
+            samples.push(v);
+        }
+    } else {
+        for _ in 0..count {
+            samples.push(next_token(bytes, &mut pos)?.parse().ok()?);
+        }
+    }
+
+    let scale = 1.0 / maxval as f64;
+    let pixels = samples
+        .chunks(channels as usize)
+        .map(|c| {
+            if channels == 1 {
+                let v = c[0] as f64 * scale;
+                Color3::from(v, v, v)
+            } else {
+                Color3::from(
+                    c[0] as f64 * scale,
+                    c[1] as f64 * scale,
+                    c[2] as f64 * scale,
+                )
+            }
+        })
+        .collect();
+    Some(Image { w, h, pixels })
+}
+
+/// Next whitespace-separated header token, skipping `#` comments.
+fn next_token(bytes: &[u8], pos: &mut usize) -> Option<String> {
+    loop {
+        while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if *pos < bytes.len() && bytes[*pos] == b'#' {
+            while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                *pos += 1;
+            }
+            continue;
+        }
+        break;
+    }
+    let start = *pos;
+    while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+        *pos += 1;
+    }
+    if start == *pos {
+        return None;
+    }
+    String::from_utf8(bytes[start..*pos].to_vec()).ok()
+}
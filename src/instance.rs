@@ -0,0 +1,156 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    cross, deg2rad, dot, unit_vector, Aabb, HitRecord, Hittable, Interval, Json, MaterialVisitor,
+    Onb, Point3, Ray, SceneWriter, Vec3,
+};
+
+/// Shared object placed in the world by a similarity transform: scaled by
+/// `scale`, rotated by `angle` degrees around `axis`, then moved by
+/// `offset`. Many instances can reference one object, e.g. a large mesh,
+/// without copying it.
+pub struct Instance {
+    object: Rc<RefCell<dyn Hittable>>,
+    axis: Vec3,
+    angle: f64,
+    scale: f64,
+    offset: Vec3,
+    /// Object axes in world space.
+    rotation: Onb,
+    bbox: Aabb,
+}
+
+impl Instance {
+    /// `object` in place, until moved with the `with_*` setters.
+    pub fn new(object: Rc<RefCell<dyn Hittable>>) -> Instance {
+        let mut instance = Instance {
+            object,
+            axis: Vec3::from(0.0, 1.0, 0.0),
+            angle: 0.0,
+            scale: 1.0,
+            offset: Vec3::new(),
+            rotation: Onb {
+                u: Vec3::from(1.0, 0.0, 0.0),
+                v: Vec3::from(0.0, 1.0, 0.0),
+                w: Vec3::from(0.0, 0.0, 1.0),
+            },
+            bbox: Aabb::new(),
+        };
+        instance.update();
+        instance
+    }
+
+    /// Rotation by `angle` degrees, counter-clockwise looking down `axis`.
+    pub fn with_rotation(mut self, axis: Vec3, angle: f64) -> Self {
+        self.axis = axis;
+        self.angle = angle;
+        self.update();
+        self
+    }
+
+    /// Uniform scale factor, which must be positive.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self.update();
+        self
+    }
+
+    pub fn with_offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self.update();
+        self
+    }
+
+    fn update(&mut self) {
+        let k = unit_vector(self.axis);
+        let (sin, cos) = deg2rad(self.angle).sin_cos();
+        // Rodrigues' formula applied to the coordinate axes.
+        let rotate = |a: Vec3| cos * a + sin * cross(k, a) + (1.0 - cos) * dot(k, a) * k;
+        self.rotation = Onb {
+            u: rotate(Vec3::from(1.0, 0.0, 0.0)),
+            v: rotate(Vec3::from(0.0, 1.0, 0.0)),
+            w: rotate(Vec3::from(0.0, 0.0, 1.0)),
+        };
+
+        let inner = self.object.borrow().bounding_box();
+        let mut bbox = Aabb::new();
+        if inner.x.min <= inner.x.max && inner.y.min <= inner.y.max && inner.z.min <= inner.z.max {
+            let xs = [inner.x.min, inner.x.max];
+            let ys = [inner.y.min, inner.y.max];
+            let zs = [inner.z.min, inner.z.max];
+            for corner in 0..8 {
+                let p = Point3::from(xs[corner & 1], ys[(corner >> 1) & 1], zs[corner >> 2]);
+                let q = self.to_world_point(p);
+                bbox = Aabb::from_boxes(&bbox, &Aabb::from(q, q));
+            }
+        }
+        self.bbox = bbox;
+    }
+
+    fn to_world_point(&self, p: Point3) -> Point3 {
+        self.offset + self.scale * self.rotation.to_world(p)
+    }
+
+    fn to_local_ray(&self, r: Ray) -> Ray {
+        Ray::from(
+            self.rotation
+                .to_local((r.origin() - self.offset) / self.scale),
+            self.rotation.to_local(r.direction() / self.scale),
+        )
+    }
+
+    /// Moves a hit on the object into world space. Ray parameters carry
+    /// over unchanged, and rotations keep normals on the same side.
+    fn to_world_record(&self, rec: &mut HitRecord) {
+        rec.p = self.to_world_point(rec.p);
+        rec.normal = self.rotation.to_world(rec.normal);
+        rec.geometric_normal = self.rotation.to_world(rec.geometric_normal);
+        rec.dpdu = self.scale * self.rotation.to_world(rec.dpdu);
+        rec.dpdv = self.scale * self.rotation.to_world(rec.dpdv);
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        if !self
+            .object
+            .borrow()
+            .hit(self.to_local_ray(r), ray_root, rec)
+        {
+            return false;
+        }
+        self.to_world_record(rec);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "instance".into()),
+            ("object", out.prototype(&self.object)?),
+            ("axis", self.axis.into()),
+            ("angle", self.angle.into()),
+            ("scale", self.scale.into()),
+            ("offset", self.offset.into()),
+        ]))
+    }
+
+    fn hit_all(&self, r: Ray, ray_root: Interval, hits: &mut Vec<HitRecord>) -> bool {
+        let start = hits.len();
+        self.object
+            .borrow()
+            .hit_all(self.to_local_ray(r), ray_root, hits);
+        for rec in &mut hits[start..] {
+            self.to_world_record(rec);
+        }
+        hits.len() > start
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        self.object.borrow().for_each_material(f);
+    }
+}
@@ -7,20 +7,34 @@ pub struct Interval {
     // pub empty: Cow<'static, Interval>,
 }
 
+impl Default for Interval {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Interval {
     pub const fn new() -> Interval {
-        return Interval {
+        Interval {
             min: INFINTY,
             max: NEG_INFINTY,
-        };
+        }
     }
 
     pub const fn from(min: f64, max: f64) -> Interval {
-        return Interval { min, max };
+        Interval { min, max }
+    }
+
+    /// Smallest interval enclosing both `a` and `b`.
+    pub fn from_intervals(a: &Interval, b: &Interval) -> Interval {
+        Interval {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
     }
 
     pub fn size(&self) -> f64 {
-        return self.max - self.min;
+        self.max - self.min
     }
 
     pub fn contains(&self, x: f64) -> bool {
@@ -30,6 +44,10 @@ impl Interval {
         self.min < x && self.max > x
     }
 
+    pub fn expand(&self, delta: f64) -> Interval {
+        Interval::from(self.min - delta / 2.0, self.max + delta / 2.0)
+    }
+
     pub fn clamp(&self, x: f64) -> f64 {
         if self.min > x {
             return self.min;
@@ -37,7 +55,7 @@ impl Interval {
         if self.max < x {
             return self.max;
         }
-        return x;
+        x
     }
 }
 
@@ -0,0 +1,102 @@
+mod aabb;
+pub use aabb::*;
+mod aov;
+pub use aov::*;
+mod aperture;
+pub use aperture::*;
+mod builtin;
+pub use builtin::*;
+mod bvh;
+pub use bvh::*;
+mod camera;
+pub use camera::*;
+mod capsule;
+pub use capsule::*;
+mod checkpoint;
+pub use checkpoint::*;
+mod color;
+pub use color::*;
+mod cone;
+pub use cone::*;
+mod csg;
+pub use csg::*;
+mod denoise;
+pub use denoise::*;
+mod distributed;
+pub use distributed::*;
+mod ellipsoid;
+pub use ellipsoid::*;
+mod exr;
+pub use exr::*;
+mod film;
+pub use film::*;
+mod filter;
+pub use filter::*;
+mod gltf;
+pub use gltf::*;
+mod heightfield;
+pub use heightfield::*;
+mod hittable;
+pub use hittable::*;
+mod image;
+pub use image::*;
+mod instance;
+pub use instance::*;
+mod intervals;
+pub use intervals::*;
+mod json;
+pub use json::*;
+mod light;
+pub use light::*;
+mod material;
+pub use material::*;
+mod mesh;
+pub use mesh::*;
+mod metaball;
+pub use metaball::*;
+mod noise;
+pub use noise::*;
+mod normalmap;
+pub use normalmap::*;
+mod onb;
+pub use onb::*;
+mod opacity;
+pub use opacity::*;
+mod ply;
+pub use ply::*;
+mod png;
+pub use png::*;
+mod progress;
+pub use progress::*;
+mod projection;
+pub use projection::*;
+mod ray;
+pub use ray::*;
+mod render;
+pub use render::*;
+mod roots;
+pub use roots::*;
+mod sampler;
+pub use sampler::*;
+mod scene;
+pub use scene::*;
+mod scenefile;
+pub use scenefile::*;
+mod sdf;
+pub use sdf::*;
+mod sphere;
+pub use sphere::*;
+mod stats;
+pub use stats::*;
+mod stl;
+pub use stl::*;
+mod texture;
+pub use texture::*;
+mod torus;
+pub use torus::*;
+mod triangle;
+pub use triangle::*;
+mod utils;
+pub use utils::*;
+mod vec3;
+pub use vec3::*;
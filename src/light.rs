@@ -0,0 +1,201 @@
+use std::rc::Rc;
+
+use crate::{deg2rad, dot, unit_vector, Color3, Json, Point3, SceneWriter, Vec3, INFINTY};
+
+/// Illumination arriving at a shading point from a single light.
+pub struct LightSample {
+    /// Unit direction from the shading point towards the light.
+    pub wi: Vec3,
+    /// Distance to the light, `INFINTY` for directional lights.
+    pub dist: f64,
+    /// Incident radiance before the cosine term.
+    pub radiance: Color3,
+}
+
+/// Delta light sources. They are not geometry, so rays never hit them; their
+/// contribution is gathered with shadow rays in `Camera::ray_color`.
+pub trait Light {
+    fn sample_li(&self, p: Point3) -> Option<LightSample>;
+
+    /// Scene file description, see `SceneWriter`.
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        None
+    }
+}
+
+/// Isotropic point light with inverse-square falloff.
+pub struct PointLight {
+    position: Point3,
+    intensity: Color3,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color3) -> PointLight {
+        PointLight {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, p: Point3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let dist_sq = to_light.length_squared();
+        if dist_sq == 0.0 {
+            return None;
+        }
+        let dist = dist_sq.sqrt();
+        Some(LightSample {
+            wi: to_light / dist,
+            dist,
+            radiance: self.intensity / dist_sq,
+        })
+    }
+
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "point".into()),
+            ("position", self.position.into()),
+            ("intensity", self.intensity.into()),
+        ]))
+    }
+}
+
+/// Point light restricted to a cone. Full intensity inside `falloff_start`,
+/// smoothly fading to zero at `total_width` (both half-angles in degrees).
+pub struct SpotLight {
+    position: Point3,
+    target: Point3,
+    direction: Vec3,
+    intensity: Color3,
+    total_width: f64,
+    falloff_start: f64,
+    cos_falloff_start: f64,
+    cos_total_width: f64,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Point3,
+        target: Point3,
+        intensity: Color3,
+        total_width: f64,
+        falloff_start: f64,
+    ) -> SpotLight {
+        SpotLight {
+            position,
+            target,
+            direction: unit_vector(target - position),
+            intensity,
+            total_width,
+            falloff_start,
+            cos_falloff_start: deg2rad(falloff_start.min(total_width)).cos(),
+            cos_total_width: deg2rad(total_width).cos(),
+        }
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_total_width {
+            return 0.0;
+        }
+        let t =
+            (cos_theta - self.cos_total_width) / (self.cos_falloff_start - self.cos_total_width);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, p: Point3) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let dist_sq = to_light.length_squared();
+        if dist_sq == 0.0 {
+            return None;
+        }
+        let dist = dist_sq.sqrt();
+        let wi = to_light / dist;
+        let falloff = self.falloff(dot(-wi, self.direction));
+        if falloff == 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            wi,
+            dist,
+            radiance: self.intensity * (falloff / dist_sq),
+        })
+    }
+
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "spot".into()),
+            ("position", self.position.into()),
+            ("target", self.target.into()),
+            ("intensity", self.intensity.into()),
+            ("total_width", self.total_width.into()),
+            ("falloff_start", self.falloff_start.into()),
+        ]))
+    }
+}
+
+/// Light arriving from infinitely far away along `direction`, like the sun.
+pub struct DirectionalLight {
+    direction: Vec3,
+    /// Unit vector towards the light.
+    wi: Vec3,
+    radiance: Color3,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, radiance: Color3) -> DirectionalLight {
+        DirectionalLight {
+            direction,
+            wi: -unit_vector(direction),
+            radiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(&self, _p: Point3) -> Option<LightSample> {
+        Some(LightSample {
+            wi: self.wi,
+            dist: INFINTY,
+            radiance: self.radiance,
+        })
+    }
+
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "directional".into()),
+            ("direction", self.direction.into()),
+            ("radiance", self.radiance.into()),
+        ]))
+    }
+}
+
+pub struct LightList {
+    lights: Vec<Rc<dyn Light>>,
+}
+
+impl Default for LightList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LightList {
+    pub fn new() -> LightList {
+        LightList { lights: Vec::new() }
+    }
+
+    pub fn add(&mut self, light: Rc<dyn Light>) {
+        self.lights.push(light);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rc<dyn Light>> {
+        self.lights.iter()
+    }
+}
@@ -1,414 +1,651 @@
-use core::f64;
 use std::cell::RefCell;
-use std::cmp;
+use std::io;
+use std::net::TcpListener;
 use std::rc::Rc;
-mod camera;
-use camera::*;
-mod color;
-mod intervals;
-use intervals::*;
-mod utils;
-use color::*;
-use intervals::Interval;
-use utils::*;
-mod ray;
-use ray::*;
-mod vec3;
-use vec3::*;
-
-#[derive(Clone)]
-pub struct HitRecord {
-    p: Point3,
-    normal: Vec3,
-    t: f64,
-    front_face: bool,
-    mat: Rc<RefCell<dyn Material>>,
+use std::time::{Duration, Instant};
+
+use raytracer::*;
+
+/// View of a whole scene from the front and slightly above, for imported
+/// scenes without a camera.
+fn overview(bounds: &Aabb) -> CameraBuilder {
+    let center = bounds.centroid();
+    let radius = 0.5 * Vec3::from(bounds.x.size(), bounds.y.size(), bounds.z.size()).length();
+    Camera::builder()
+        .fov(40.0)
+        .lookat(center)
+        .lookfrom(center + 3.0 * radius.max(1e-3) * Vec3::from(0.0, 0.3, 1.0))
 }
 
-impl HitRecord {
-    pub fn set_face_normal(&mut self, r: Ray, out_norm: Vec3) {
-        self.front_face = dot(r.direction(), out_norm) < 0.0;
-        if self.front_face {
-            self.normal = out_norm;
-        } else {
-            self.normal = -out_norm;
-        }
-    }
-
-    pub fn new() -> HitRecord {
-        HitRecord {
-            p: Point3::new(),
-            normal: Vec3::new(),
-            t: 0.0,
-            front_face: false,
-            mat: Rc::new(RefCell::new(Lambertian::from(Color3::new()))),
-        }
-    }
-
-    pub fn copy(&mut self, rec: &HitRecord) {
-        self.p = rec.p;
-        self.front_face = rec.front_face;
-        self.normal = rec.normal;
-        self.t = rec.t;
-        self.mat = rec.mat.clone();
+/// Loads a PLY or STL file, chosen by extension, with a light gray
+/// diffuse material that vertex colors tint, subdivided if asked to.
+fn load_mesh(
+    path: &str,
+    crease_angle: f64,
+    subdivision: Option<(Subdivision, u32)>,
+) -> io::Result<TriangleMesh> {
+    let mat: Rc<RefCell<dyn Material>> =
+        Rc::new(RefCell::new(Lambertian::from(Color3::from(0.8, 0.8, 0.8))));
+    let extension = path.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+    match (extension.as_str(), subdivision) {
+        ("ply", None) => load_ply(path, mat),
+        ("stl", None) => load_stl(path, crease_angle, mat),
+        ("ply", Some((scheme, levels))) => Ok(TriangleMesh::new(
+            &read_ply(path)?.subdivide(scheme, levels),
+            mat,
+        )),
+        ("stl", Some((scheme, levels))) => Ok(TriangleMesh::new(
+            &read_stl(path)?.subdivide(scheme, levels),
+            mat,
+        )),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "expected a .ply or .stl file",
+        )),
     }
 }
 
-pub trait Hittable {
-    fn hit(&mut self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool;
-}
-
-pub trait Material {
-    fn scatter(
-        &mut self,
-        r_in: Ray,
-        rec: &mut HitRecord,
-        attenuation: &mut Color3,
-        scattered: &mut Ray,
-    ) -> bool;
-}
-
-#[derive(Debug)]
-pub struct Lambertian {
-    albedo: Color3,
-}
-
-#[derive(Debug)]
-pub struct Metal {
-    albedo: Color3,
-    fuzz: f64,
-}
-
-#[derive(Debug)]
-pub struct Diaelectric {
-    refractive_index: f64,
-}
-
-impl Material for Diaelectric {
-    fn scatter(
-        &mut self,
-        r_in: Ray,
-        rec: &mut HitRecord,
-        attenuation: &mut Color3,
-        scattered: &mut Ray,
-    ) -> bool {
-        let mut rind = self.refractive_index;
-        if rec.front_face {
-            rind = 1.0 / self.refractive_index;
+fn generate_img(options: &Options) {
+    let stats = Rc::new(RefCell::new(RenderStats::new()));
+    let scene_start = Instant::now();
+    let SceneSetup {
+        scene,
+        camera: view,
+    } = if let Some(path) = &options.scene {
+        match load_scene(path) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                eprintln!("could not load {path}: {e}");
+                std::process::exit(1);
+            }
         }
-
-        let cos_theta = dot(-unit_vector(r_in.direction()), rec.normal).min(1.0);
-        let sin_theta = (1.0 - cos_theta.powi(2)).sqrt();
-        let scatter;
-        if rind * sin_theta > 1.0 || (Diaelectric::reflectance(cos_theta, rind) > rand_norm()) {
-            // reflect
-            scatter = reflect(unit_vector(r_in.direction()), rec.normal);
-        } else {
-            scatter = refract(rind, unit_vector(r_in.direction()), rec.normal);
+    } else if let Some(path) = &options.gltf {
+        let imported = match load_gltf(path, options.subdivision) {
+            Ok(imported) => imported,
+            Err(e) => {
+                eprintln!("could not load {path}: {e}");
+                std::process::exit(1);
+            }
+        };
+        for warning in &imported.warnings {
+            eprintln!("{path}: {warning}");
         }
-        scattered.set(rec.p, scatter);
-        attenuation.set(1.0, 1.0, 1.0);
-        true
-    }
-}
-
-impl Diaelectric {
-    pub fn from(ind: f64) -> impl Material {
-        Diaelectric {
-            refractive_index: ind,
+        let camera = match imported.cameras.into_iter().next() {
+            Some(camera) => camera,
+            None => overview(&imported.scene.world.bounding_box()),
+        };
+        SceneSetup {
+            scene: imported.scene,
+            camera,
         }
+    } else if let Some(path) = &options.mesh {
+        let mesh = match load_mesh(path, options.crease_angle, options.subdivision) {
+            Ok(mesh) => mesh,
+            Err(e) => {
+                eprintln!("could not load {path}: {e}");
+                std::process::exit(1);
+            }
+        };
+        eprintln!("{path}: {} triangles", mesh.triangle_count());
+        let mut scene = Scene::new();
+        scene.world.add(Rc::new(RefCell::new(mesh)));
+        let camera = overview(&scene.world.bounding_box());
+        SceneSetup { scene, camera }
+    } else {
+        match builtin_scene(&options.builtin) {
+            Some(builtin) => builtin.build(options.scene_seed),
+            None => {
+                eprintln!("unknown scene {}, see --list-scenes", options.builtin);
+                std::process::exit(2);
+            }
+        }
+    };
+    let resume = options
+        .resume
+        .as_ref()
+        .map(|path| match Checkpoint::load(path) {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                eprintln!("could not load {path}: {e}");
+                std::process::exit(1);
+            }
+        });
+    let bvh_build = take_bvh_build_time();
+    stats.borrow_mut().add_phase(
+        "scene build",
+        scene_start.elapsed().saturating_sub(bvh_build),
+    );
+    stats.borrow_mut().add_phase("bvh build", bvh_build);
+    // Only settings given on the command line override a loaded camera.
+    let mut builder = view;
+    if let Some(checkpoint) = &resume {
+        // Taken over unless overridden, so resuming needs no more flags
+        // than the first run.
+        builder = builder
+            .width(checkpoint.w)
+            .height(checkpoint.h)
+            .filter(checkpoint.filter)
+            .sampler(checkpoint.sampler, checkpoint.seed);
     }
-
-    pub fn reflectance(cosine: f64, n1: f64) -> f64 {
-        let mut r0 = (1.0 - n1) / (1.0 + n1);
-        r0 *= r0;
-        r0 + ((1.0 - r0) * (1.0 - cosine).powi(5))
+    if let Some(width) = options.width {
+        builder = builder.width(width);
     }
-}
-
-impl Material for Lambertian {
-    fn scatter(
-        &mut self,
-        r_in: Ray,
-        rec: &mut HitRecord,
-        attenuation: &mut Color3,
-        scattered: &mut Ray,
-    ) -> bool {
-        let mut dir = rec.normal + rand_unit_vector();
-        if dir.near_zero() {
-            dir = rec.normal;
+    if let Some(samples_per_pixel) = options.samples_per_pixel {
+        builder = builder.samples_per_pixel(samples_per_pixel);
+    }
+    if let Some(film) = options.film {
+        builder = builder.film(film);
+    }
+    if let Some(filter) = options.filter {
+        builder = builder.filter(filter);
+    }
+    if let Some((sampler, seed)) = options.sampler {
+        builder = builder.sampler(sampler, seed);
+    }
+    if let Some(aperture) = &options.aperture {
+        builder = builder.aperture(aperture.clone());
+    }
+    if let Some(projection) = options.projection {
+        builder = builder.projection(projection);
+    }
+    if let Some(height) = options.height {
+        builder = builder.height(height);
+    }
+    if let Some((focal_length, f_number)) = options.lens {
+        builder = builder.lens(focal_length, f_number);
+    }
+    if let Some(path) = &options.save_scene {
+        match save_scene(path, &scene, &builder) {
+            Ok(warnings) => {
+                for warning in &warnings {
+                    eprintln!("{path}: {warning}");
+                }
+            }
+            Err(e) => {
+                eprintln!("could not write {path}: {e}");
+                std::process::exit(1);
+            }
         }
-        scattered.set(rec.p, dir);
-        attenuation.copy(self.albedo);
-        // eprintln!("{attenuation:?} {:?}", self.albedo);
-        true
     }
-}
-
-impl Material for Metal {
-    fn scatter(
-        &mut self,
-        r_in: Ray,
-        rec: &mut HitRecord,
-        attenuation: &mut Color3,
-        scattered: &mut Ray,
-    ) -> bool {
-        let mut reflected = reflect(r_in.direction(), rec.normal);
-        reflected = unit_vector(reflected) + (self.fuzz * rand_unit_vector());
-        scattered.set(rec.p, reflected);
-        attenuation.copy(self.albedo);
-        dot(reflected, rec.normal) > 0.0
+    // Workers rebuild the scene from its scene file description.
+    let distributed = if options.workers.is_empty() {
+        None
+    } else {
+        let (json, warnings) = scene_to_json(&scene, &builder);
+        if !warnings.is_empty() {
+            for warning in &warnings {
+                eprintln!("cannot send the scene to workers: {warning}");
+            }
+            std::process::exit(1);
+        }
+        Some(json)
+    };
+    let mut camera = match builder.build() {
+        Ok(camera) => camera,
+        Err(e) => {
+            eprintln!("invalid camera: {e}");
+            std::process::exit(2);
+        }
+    };
+    if options.autofocus {
+        match camera.autofocus(&scene.world) {
+            Some(dist) => eprintln!("autofocus: focus distance {dist}"),
+            None => eprintln!("autofocus: nothing under the image center"),
+        }
     }
-}
-
-impl Metal {
-    fn from(albedo: Color3, fuzz: f64) -> impl Material {
-        Metal {
-            albedo,
-            fuzz: fuzz.min(1.0),
+    let mut settings = RenderSettings::new()
+        .with_stats(stats.clone())
+        .with_warnings(|message| eprintln!("{message}"));
+    if let Some(checkpoint) = resume {
+        if let Err(e) = camera.check_checkpoint(&checkpoint) {
+            eprintln!("cannot resume: {e}");
+            std::process::exit(2);
         }
+        let rows = checkpoint.row_samples.len().max(1) as f64;
+        let average = checkpoint.row_samples.iter().sum::<u64>() as f64 / rows;
+        eprintln!("resuming at {average:.1} samples per pixel");
+        settings = settings.with_resume(checkpoint);
     }
-}
-
-impl Lambertian {
-    fn from(albedo: Color3) -> impl Material {
-        Lambertian { albedo }
+    if let Some(path) = options.checkpoint.clone().or(options.resume.clone()) {
+        let interval = Duration::from_secs_f64(options.checkpoint_interval);
+        settings = settings.with_checkpoint(interval, move |checkpoint| {
+            if let Err(e) = checkpoint.write(&path) {
+                eprintln!("could not write {path}: {e}");
+            }
+        });
     }
-}
-
-pub struct Sphere {
-    center: Point3,
-    radius: f64,
-    mat: Rc<RefCell<dyn Material>>,
-}
-
-impl Hittable for Sphere {
-    fn hit(&mut self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
-        let cmq = self.center - r.origin();
-        let ai = r.direction().length_squared();
-        let h = dot(r.direction(), cmq);
-        let c = cmq.length_squared() - (self.radius * self.radius);
-        let det_in = (h * h) - (ai * c);
-
-        if det_in < 0.0 {
-            return false;
-        } else {
-            let sqrtd = det_in.sqrt();
-            let mut quad_form = (h - sqrtd) / (ai);
-            if !ray_root.surrounds(quad_form) {
-                quad_form = (h + sqrtd) / (ai);
-                if !ray_root.surrounds(quad_form) {
-                    return false;
+    if !options.quiet {
+        let bar = ProgressBar::new();
+        settings = settings.with_progress(move |p| bar.update(p));
+    }
+    if options.aov_exr.is_none() && options.aov_dir.is_none() && options.denoiser.is_none() {
+        let image = match &distributed {
+            Some(json) => {
+                let mut coordinator = Coordinator::new(options.workers.clone())
+                    .with_connections(options.worker_connections)
+                    .with_tile_rows(options.tile_rows)
+                    .with_autofocus(options.autofocus)
+                    .with_timeout(Duration::from_secs_f64(options.worker_timeout));
+                if let Some(samples) = options.tile_samples {
+                    coordinator = coordinator.with_tile_samples(samples);
+                }
+                match coordinator.render(json, &camera, &settings) {
+                    Ok(image) => image,
+                    Err(e) => {
+                        eprintln!("distributed render failed: {e}");
+                        std::process::exit(1);
+                    }
                 }
             }
-
-            rec.t = quad_form;
-            rec.p = r.at(rec.t);
-            let out_norm = (rec.p - self.center) / self.radius;
-            rec.set_face_normal(r, out_norm);
-            rec.mat = self.mat.clone();
-            return true;
+            None => match render(&scene, &camera, &settings) {
+                Ok(image) => image,
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(2);
+                }
+            },
         };
+        let output_start = Instant::now();
+        camera.film().write_ppm(&image);
+        stats
+            .borrow_mut()
+            .add_phase("output", output_start.elapsed());
+        report_stats(options, &stats.borrow());
+        return;
     }
-}
-
-impl Sphere {
-    pub fn new(center: Point3, radius: f64, mat: Rc<RefCell<dyn Material>>) -> impl Hittable {
-        Sphere {
-            center,
-            radius,
-            mat,
+    let (image, aovs) = match render_aovs(&scene, &camera, &settings) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+    let denoised = options.denoiser.as_ref().map(|denoiser| {
+        let denoise_start = Instant::now();
+        let denoised = denoiser.apply(&image, &aovs);
+        stats
+            .borrow_mut()
+            .add_phase("denoise", denoise_start.elapsed());
+        denoised
+    });
+    let output_start = Instant::now();
+    camera.film().write_ppm(denoised.as_ref().unwrap_or(&image));
+    if let Some(path) = &options.aov_exr {
+        // The EXR keeps the raw beauty the AOVs belong to.
+        if let Err(e) = aovs.write_exr(&image, denoised.as_ref(), path) {
+            eprintln!("could not write {path}: {e}");
         }
     }
-}
-
-pub struct HittableList {
-    objects: Vec<Rc<RefCell<dyn Hittable>>>,
-}
-
-impl Hittable for HittableList {
-    fn hit(&mut self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
-        let mut temp_rec = HitRecord::new();
-        let mut hit_any = false;
-        let mut closest = ray_root.max;
-
-        for i in self.objects.iter() {
-            if i.borrow_mut()
-                .hit(r, Interval::from(ray_root.min, closest), &mut temp_rec)
-            {
-                hit_any = true;
-                closest = temp_rec.t;
-                rec.copy(&temp_rec);
-            }
+    if let Some(dir) = &options.aov_dir {
+        if let Err(e) = aovs.write_pfm(dir) {
+            eprintln!("could not write AOVs to {dir}: {e}");
         }
-
-        return hit_any;
     }
+    stats
+        .borrow_mut()
+        .add_phase("output", output_start.elapsed());
+    report_stats(options, &stats.borrow());
 }
 
-pub trait List {
-    fn clear(&mut self);
-    fn add(&mut self, el: Rc<RefCell<dyn Hittable>>);
-}
-
-impl List for HittableList {
-    fn add(&mut self, el: Rc<RefCell<dyn Hittable>>) {
-        self.objects.push(el);
-    }
-
-    fn clear(&mut self) {
-        self.objects.clear();
+/// Prints the summary for `--stats` and writes the `--stats-json` file.
+fn report_stats(options: &Options, stats: &RenderStats) {
+    if options.stats {
+        eprintln!("{stats}");
     }
-}
-
-impl HittableList {
-    pub fn new() -> impl Hittable + List {
-        HittableList {
-            objects: Vec::new(),
+    if let Some(path) = &options.stats_json {
+        if let Err(e) = stats.write_json(path) {
+            eprintln!("could not write {path}: {e}");
         }
     }
 }
 
-fn create3Scene(world: &mut (impl Hittable + List)) {
-    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.8, 0.8, 0.0))));
-    let center_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.1, 0.2, 0.5))));
-    // let left_mat = Rc::new(RefCell::new(Metal::from(Color3::from(0.8, 0.8, 0.8), 0.3)));
-    let left_mat = Rc::new(RefCell::new(Diaelectric::from(1.50)));
-    let bubble_mat = Rc::new(RefCell::new(Diaelectric::from(1.00 / 1.50)));
-    let right_mat = Rc::new(RefCell::new(Metal::from(Color3::from(0.8, 0.6, 0.2), 1.0)));
-
-    world.add(Rc::new(RefCell::new(Sphere::new(
-        Point3::from(0.0, -100.5, -1.0),
-        100.0,
-        ground_mat,
-    ))));
-    world.add(Rc::new(RefCell::new(Sphere::new(
-        Point3::from(0.0, 0.0, -1.2),
-        0.5,
-        center_mat,
-    ))));
-
-    world.add(Rc::new(RefCell::new(Sphere::new(
-        Point3::from(-1.0, 0.0, -1.0),
-        0.5,
-        left_mat,
-    ))));
-
-    world.add(Rc::new(RefCell::new(Sphere::new(
-        Point3::from(-1.0, 0.0, -1.0),
-        0.4,
-        bubble_mat,
-    ))));
-
-    world.add(Rc::new(RefCell::new(Sphere::new(
-        Point3::from(1.0, 0.0, -1.0),
-        0.5,
-        right_mat,
-    ))));
+struct Options {
+    width: Option<u64>,
+    height: Option<u64>,
+    samples_per_pixel: Option<u64>,
+    film: Option<Film>,
+    filter: Option<Filter>,
+    sampler: Option<(SamplerKind, u64)>,
+    aperture: Option<Aperture>,
+    lens: Option<(f64, f64)>,
+    autofocus: bool,
+    projection: Option<Projection>,
+    aov_exr: Option<String>,
+    aov_dir: Option<String>,
+    denoiser: Option<Denoiser>,
+    gltf: Option<String>,
+    mesh: Option<String>,
+    crease_angle: f64,
+    subdivision: Option<(Subdivision, u32)>,
+    scene: Option<String>,
+    save_scene: Option<String>,
+    builtin: String,
+    scene_seed: u64,
+    stats: bool,
+    stats_json: Option<String>,
+    quiet: bool,
+    checkpoint: Option<String>,
+    checkpoint_interval: f64,
+    resume: Option<String>,
+    serve: Option<String>,
+    serve_connections: Option<usize>,
+    max_scene_mb: Option<usize>,
+    workers: Vec<String>,
+    worker_connections: usize,
+    tile_rows: u64,
+    tile_samples: Option<u64>,
+    worker_timeout: f64,
 }
 
-fn createFOVScene(world: &mut (impl Hittable + List)) {
-    let r = f64::cos(PI / 4.0);
-    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(1.0, 0.0, 0.0))));
-    let center_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.0, 1.0, 0.0))));
-    world.add(Rc::new(RefCell::new(Sphere::new(
-        Point3::from(-r, 0.0, -1.0),
-        r,
-        ground_mat,
-    ))));
-    world.add(Rc::new(RefCell::new(Sphere::new(
-        Point3::from(r, 0.0, -1.0),
-        r,
-        center_mat,
-    ))));
+fn parse_value<T: std::str::FromStr>(arg: &str, value: Option<&String>) -> Result<T, String> {
+    value
+        .ok_or(format!("missing value for {arg}"))?
+        .parse()
+        .map_err(|_| format!("invalid value for {arg}"))
 }
 
-fn createFinalScene(world: &mut (impl Hittable + List)) {
-    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.8, 0.8, 0.0))));
-    world.add(Rc::new(RefCell::new(Sphere::new(
-        Point3::from(0.0, -1000.0, -0.0),
-        1000.0,
-        ground_mat,
-    ))));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let center = Vec3::from(
-                a as f64 + 0.9 * rand_norm(),
-                0.2,
-                b as f64 + 0.9 * rand_norm(),
-            );
-            let prob = rand_norm();
-
-            if (center - Point3::from(4.0, 0.2, 0.0)).length() > 0.9 {
-                // let mat;
-                if prob < 0.8 {
-                    let albedo = Vec3::rand_norm();
-                    let lamb_mat = Rc::new(RefCell::new(Lambertian::from(albedo)));
-                    world.add(Rc::new(RefCell::new(Sphere::new(center, 0.2, lamb_mat))));
-                } else if prob < 0.95 {
-                    let metal_mat = Rc::new(RefCell::new(Metal::from(
-                        Vec3::rand_from(0.5, 1.0),
-                        rand_from(0.0, 0.5),
-                    )));
-                    world.add(Rc::new(RefCell::new(Sphere::new(center, 0.2, metal_mat))));
-                } else {
-                    let dia_mat = Rc::new(RefCell::new(Diaelectric::from(1.50)));
-                    world.add(Rc::new(RefCell::new(Sphere::new(center, 0.2, dia_mat))));
-                }
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut width = None;
+    let mut height = None;
+    let mut samples_per_pixel = None;
+    let mut exposure = None;
+    let mut white = None;
+    let mut tone = None;
+    let mut filter = None;
+    let mut filter_radius = None;
+    let mut sampler = None;
+    let mut seed = None;
+    let mut blades = None;
+    let mut aperture_rotation = 0.0;
+    let mut bokeh = None;
+    let mut focal_length = 0.05;
+    let mut f_number = None;
+    let mut autofocus = false;
+    let mut projection = None;
+    let mut ortho_height = 2.0;
+    let mut fisheye_fov = 180.0;
+    let mut aov_exr = None;
+    let mut aov_dir = None;
+    let mut denoiser = None;
+    let mut gltf = None;
+    let mut mesh = None;
+    let mut crease_angle = 30.0;
+    let mut subdivide = None;
+    let mut subdivide_levels = None;
+    let mut scene = None;
+    let mut save_scene = None;
+    let mut builtin = "random-spheres".to_string();
+    let mut scene_seed = 0;
+    let mut stats = false;
+    let mut stats_json = None;
+    let mut quiet = false;
+    let mut checkpoint = None;
+    let mut checkpoint_interval: f64 = 600.0;
+    let mut resume = None;
+    let mut serve = None;
+    let mut serve_connections = None;
+    let mut max_scene_mb = None;
+    let mut workers = Vec::new();
+    let mut worker_connections = 1;
+    let mut tile_rows = 16;
+    let mut tile_samples = None;
+    let mut worker_timeout: f64 = 600.0;
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let arg = arg.as_str();
+        match arg {
+            "--width" => width = Some(parse_value(arg, it.next())?),
+            "--height" => height = Some(parse_value(arg, it.next())?),
+            "--spp" => samples_per_pixel = Some(parse_value(arg, it.next())?),
+            "--exposure" => exposure = Some(parse_value(arg, it.next())?),
+            "--tonemap" => tone = Some(parse_value::<String>(arg, it.next())?),
+            "--white" => white = Some(parse_value(arg, it.next())?),
+            "--filter" => filter = Some(parse_value::<String>(arg, it.next())?),
+            "--filter-radius" => filter_radius = Some(parse_value(arg, it.next())?),
+            "--sampler" => sampler = Some(parse_value::<String>(arg, it.next())?),
+            "--seed" => seed = Some(parse_value(arg, it.next())?),
+            "--blades" => blades = Some(parse_value(arg, it.next())?),
+            "--aperture-rotation" => aperture_rotation = parse_value(arg, it.next())?,
+            "--bokeh" => bokeh = Some(parse_value::<String>(arg, it.next())?),
+            "--focal-length" => focal_length = parse_value(arg, it.next())?,
+            "--fstop" => f_number = Some(parse_value(arg, it.next())?),
+            "--autofocus" => autofocus = true,
+            "--projection" => projection = Some(parse_value::<String>(arg, it.next())?),
+            "--ortho-height" => ortho_height = parse_value(arg, it.next())?,
+            "--fisheye-fov" => fisheye_fov = parse_value(arg, it.next())?,
+            "--aov-exr" => aov_exr = Some(parse_value(arg, it.next())?),
+            "--aov-dir" => aov_dir = Some(parse_value(arg, it.next())?),
+            "--denoise" => denoiser = Some(parse_value::<String>(arg, it.next())?),
+            "--gltf" => gltf = Some(parse_value(arg, it.next())?),
+            "--mesh" => mesh = Some(parse_value(arg, it.next())?),
+            "--crease" => crease_angle = parse_value(arg, it.next())?,
+            "--subdivide" => subdivide = Some(parse_value(arg, it.next())?),
+            "--subdivide-levels" => subdivide_levels = Some(parse_value(arg, it.next())?),
+            "--scene" => scene = Some(parse_value(arg, it.next())?),
+            "--save-scene" => save_scene = Some(parse_value(arg, it.next())?),
+            "--builtin" => builtin = parse_value(arg, it.next())?,
+            "--scene-seed" => scene_seed = parse_value(arg, it.next())?,
+            "--stats" => stats = true,
+            "--stats-json" => stats_json = Some(parse_value(arg, it.next())?),
+            "--quiet" => quiet = true,
+            "--checkpoint" => checkpoint = Some(parse_value(arg, it.next())?),
+            "--checkpoint-interval" => checkpoint_interval = parse_value(arg, it.next())?,
+            "--resume" => resume = Some(parse_value(arg, it.next())?),
+            "--serve" => serve = Some(parse_value(arg, it.next())?),
+            "--serve-connections" => serve_connections = Some(parse_value(arg, it.next())?),
+            "--max-scene-mb" => max_scene_mb = Some(parse_value(arg, it.next())?),
+            "--workers" => {
+                let list: String = parse_value(arg, it.next())?;
+                workers.extend(list.split(',').filter(|w| !w.is_empty()).map(String::from));
             }
-
-            let mat1 = Rc::new(RefCell::new(Diaelectric::from(1.50)));
-            world.add(Rc::new(RefCell::new(Sphere::new(
-                Point3::from(0.0, 1.0, 0.0),
-                1.0,
-                mat1,
-            ))));
-
-            let mat2 = Rc::new(RefCell::new(Lambertian::from(Vec3::from(0.4, 0.2, 0.1))));
-            world.add(Rc::new(RefCell::new(Sphere::new(
-                Point3::from(-4.0, 1.0, 0.0),
-                1.0,
-                mat2,
-            ))));
-
-            let mat3 = Rc::new(RefCell::new(Metal::from(Vec3::from(0.7, 0.6, 0.5), 0.0)));
-            world.add(Rc::new(RefCell::new(Sphere::new(
-                Point3::from(4.0, 1.0, 0.0),
-                1.0,
-                mat3,
-            ))));
+            "--worker-connections" => worker_connections = parse_value(arg, it.next())?,
+            "--tile-rows" => tile_rows = parse_value(arg, it.next())?,
+            "--tile-samples" => tile_samples = Some(parse_value(arg, it.next())?),
+            "--worker-timeout" => worker_timeout = parse_value(arg, it.next())?,
+            _ => return Err(format!("unknown argument {arg}")),
         }
     }
+    if !(checkpoint_interval >= 0.0 && checkpoint_interval.is_finite()) {
+        return Err("invalid value for --checkpoint-interval".to_string());
+    }
+    if blades.is_some_and(|n: u32| n < 3) {
+        return Err("invalid value for --blades".to_string());
+    }
+    if filter_radius.is_some_and(|r: f64| !(r > 0.0 && r.is_finite())) {
+        return Err("invalid value for --filter-radius".to_string());
+    }
+    if !(worker_timeout > 0.0 && worker_timeout.is_finite()) {
+        return Err("invalid value for --worker-timeout".to_string());
+    }
+    if serve_connections == Some(0) {
+        return Err("invalid value for --serve-connections".to_string());
+    }
+    if max_scene_mb.is_some_and(|mb: usize| mb == 0 || mb.checked_mul(1 << 20).is_none()) {
+        return Err("invalid value for --max-scene-mb".to_string());
+    }
+    if (checkpoint.is_some() || resume.is_some())
+        && (aov_exr.is_some() || aov_dir.is_some() || denoiser.is_some())
+    {
+        return Err("checkpoints cannot be combined with AOVs or denoising".to_string());
+    }
+    if !workers.is_empty()
+        && (checkpoint.is_some()
+            || resume.is_some()
+            || aov_exr.is_some()
+            || aov_dir.is_some()
+            || denoiser.is_some())
+    {
+        return Err("workers cannot render checkpoints, AOVs or denoised images".to_string());
+    }
+    let film = if exposure.is_some() || tone.is_some() || white.is_some() {
+        let tone = tone.unwrap_or("clamp".to_string());
+        let tone_map = match tone.as_str() {
+            "clamp" => ToneMap::Clamp,
+            "reinhard" => ToneMap::Reinhard,
+            "extended-reinhard" => ToneMap::ExtendedReinhard {
+                white: white.unwrap_or(4.0),
+            },
+            "aces" => ToneMap::AcesFilmic,
+            "agx" => ToneMap::AgX,
+            _ => return Err(format!("unknown tone map {tone}")),
+        };
+        Some(Film::from(exposure.unwrap_or(0.0), tone_map))
+    } else {
+        None
+    };
+    let filter = if filter.is_some() || filter_radius.is_some() {
+        let filter = filter.unwrap_or("box".to_string());
+        let default_radius = match filter.as_str() {
+            "box" => 0.5,
+            "tent" => 1.0,
+            "gaussian" => 1.5,
+            _ => 2.0,
+        };
+        Some(
+            Filter::from(&filter, filter_radius.unwrap_or(default_radius))
+                .ok_or(format!("unknown filter {filter}"))?,
+        )
+    } else {
+        None
+    };
+    let sampler = if sampler.is_some() || seed.is_some() {
+        let sampler = sampler.unwrap_or("stratified".to_string());
+        let kind = SamplerKind::from(&sampler).ok_or(format!("unknown sampler {sampler}"))?;
+        Some((kind, seed.unwrap_or(0)))
+    } else {
+        None
+    };
+    let subdivision = if subdivide.is_some() || subdivide_levels.is_some() {
+        let scheme = subdivide.unwrap_or("loop".to_string());
+        let scheme =
+            Subdivision::from(&scheme).ok_or(format!("unknown subdivision scheme {scheme}"))?;
+        // Every level multiplies the face count by four.
+        let levels = subdivide_levels.unwrap_or(2);
+        if levels > 8 {
+            return Err("invalid value for --subdivide-levels".to_string());
+        }
+        Some((scheme, levels))
+    } else {
+        None
+    };
+    let aperture = match (bokeh, blades) {
+        (Some(path), _) => {
+            let image = Image::load_pnm(&path).map_err(|e| e.to_string())?;
+            let mask = BokehMask::from(&image).ok_or(format!("{path} is completely black"))?;
+            Some(Aperture::Mask(Rc::new(mask)))
+        }
+        (None, Some(blades)) => Some(Aperture::Polygon {
+            blades,
+            rotation: aperture_rotation,
+        }),
+        (None, None) => None,
+    };
+    Ok(Options {
+        width,
+        height,
+        samples_per_pixel,
+        film,
+        filter,
+        sampler,
+        aperture,
+        lens: f_number.map(|n| (focal_length, n)),
+        autofocus,
+        projection: match projection {
+            Some(name) => Some(
+                Projection::from(&name, ortho_height, fisheye_fov)
+                    .ok_or(format!("unknown projection {name}"))?,
+            ),
+            None => None,
+        },
+        aov_exr,
+        aov_dir,
+        denoiser: match denoiser {
+            Some(name) => Some(Denoiser::from(&name).ok_or(format!("unknown denoiser {name}"))?),
+            None => None,
+        },
+        gltf,
+        mesh,
+        crease_angle,
+        subdivision,
+        scene,
+        save_scene,
+        builtin,
+        scene_seed,
+        stats,
+        stats_json,
+        quiet,
+        checkpoint,
+        checkpoint_interval,
+        resume,
+        serve,
+        serve_connections,
+        max_scene_mb,
+        workers,
+        worker_connections,
+        tile_rows,
+        tile_samples,
+        worker_timeout,
+    })
 }
 
-fn generate_img(w: u64) {
-    let aspectRatio: f64 = 16.0 / 9.0;
-    let mut world = HittableList::new();
-    let fov = 20.0;
-    let lookfrom = Vec3::from(13.0, 2.0, 3.0);
-    let lookat = Vec3::from(0.0, 0.0, 0.0);
-    let vup = Vec3::from(0.0, 1.0, 0.0);
-    let defocus_angle = 0.01;
-    let focus_dist = 10.0;
-    // create3Scene(&mut world);
-    createFinalScene(&mut world);
-    // createFOVScene(&mut world);
-    let camera = Camera::new(
-        aspectRatio,
-        w,
-        100,
-        50,
-        fov,
-        lookfrom,
-        lookat,
-        vup,
-        defocus_angle,
-        focus_dist,
-    );
-    camera.render(&mut world);
-}
+const USAGE: &str = "usage: raytracer [--width W] [--height H] [--spp N] [--exposure STOPS] \
+[--tonemap clamp|reinhard|extended-reinhard|aces|agx] [--white L] \
+[--filter box|tent|gaussian|mitchell|lanczos] [--filter-radius PIXELS] \
+[--sampler independent|stratified|halton|sobol] [--seed N] \
+[--blades N] [--aperture-rotation DEG] [--bokeh MASK.pgm] \
+[--fstop N] [--focal-length L] [--autofocus] \
+[--projection perspective|orthographic|fisheye|equirect|cubemap] \
+[--ortho-height H] [--fisheye-fov DEG] [--aov-exr FILE.exr] [--aov-dir DIR] \
+[--denoise bilateral|nlm|atrous] [--gltf SCENE.gltf|SCENE.glb] \
+[--mesh MODEL.ply|MODEL.stl] [--crease DEG] [--subdivide loop|catmull-clark] \
+[--subdivide-levels N] [--scene SCENE.json] \
+[--save-scene SCENE.json] [--builtin NAME] [--scene-seed N] [--list-scenes] \
+[--stats] [--stats-json FILE.json] [--quiet] [--checkpoint FILE] \
+[--checkpoint-interval SECONDS] [--resume FILE] [--serve HOST:PORT] \
+[--serve-connections N] [--max-scene-mb MB] \
+[--workers HOST:PORT,...] [--worker-connections N] [--tile-rows N] [--tile-samples N] \
+[--worker-timeout SECONDS]";
 
 fn main() {
-    generate_img(400);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--list-scenes") {
+        for builtin in BUILTIN_SCENES {
+            println!("{:<16} {}", builtin.name, builtin.description);
+        }
+        return;
+    }
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    if let Some(address) = &options.serve {
+        let listener = match TcpListener::bind(address) {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("could not listen on {address}: {e}");
+                std::process::exit(1);
+            }
+        };
+        if let Ok(local) = listener.local_addr() {
+            eprintln!("worker listening on {local}");
+        }
+        let mut worker = Worker::new();
+        if let Some(connections) = options.serve_connections {
+            worker = worker.with_max_connections(connections);
+        }
+        if let Some(mb) = options.max_scene_mb {
+            worker = worker.with_max_scene_bytes(mb << 20);
+        }
+        let failed = |peer, e| eprintln!("worker: connection from {peer} failed: {e}");
+        if let Err(e) = worker.serve(listener, failed) {
+            eprintln!("worker stopped: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    generate_img(&options);
 }
@@ -0,0 +1,217 @@
+use std::rc::Rc;
+
+use crate::{
+    dot, reflect, refract, sample_unit_vector, unit_vector, Color3, HitRecord, Json, Lobe, Ray,
+    Sampler, SceneWriter, Texture, Vec3, PI,
+};
+
+pub trait Material {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: &mut HitRecord,
+        attenuation: &mut Color3,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool;
+
+    /// BSDF times cosine for light arriving along `wi`, used for delta lights.
+    /// Specular materials cannot be lit by a delta light, hence the default.
+    fn eval(&self, _r_in: Ray, _rec: &HitRecord, _wi: Vec3) -> Color3 {
+        Color3::new()
+    }
+
+    /// Adjusts the shading normal of `rec` before the hit is shaded, e.g.
+    /// from a normal or bump map.
+    fn perturb_normal(&self, _rec: &mut HitRecord) {}
+
+    /// Surface color for the albedo AOV.
+    fn albedo(&self, _rec: &HitRecord) -> Color3 {
+        Color3::from(1.0, 1.0, 1.0)
+    }
+
+    /// Lobe the material scatters into. Specular scattering that crosses the
+    /// surface is reported as `Lobe::Transmission` by the camera.
+    fn lobe(&self) -> Lobe {
+        Lobe::Diffuse
+    }
+
+    /// Scene file description, `None` for materials that cannot be saved.
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        None
+    }
+}
+
+/// Diffuse reflector. The albedo is multiplied by `texture` when present
+/// and by the vertex color of the hit.
+pub struct Lambertian {
+    albedo: Color3,
+    texture: Option<Rc<dyn Texture>>,
+}
+
+#[derive(Debug)]
+pub struct Metal {
+    albedo: Color3,
+    fuzz: f64,
+}
+
+#[derive(Debug)]
+pub struct Diaelectric {
+    refractive_index: f64,
+}
+
+impl Material for Diaelectric {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: &mut HitRecord,
+        attenuation: &mut Color3,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        let mut rind = self.refractive_index;
+        if rec.front_face {
+            rind = 1.0 / self.refractive_index;
+        }
+
+        let cos_theta = dot(-unit_vector(r_in.direction()), rec.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta.powi(2)).sqrt();
+        let scatter = if rind * sin_theta > 1.0
+            || (Diaelectric::reflectance(cos_theta, rind) > sampler.get_1d())
+        {
+            // reflect
+            reflect(unit_vector(r_in.direction()), rec.normal)
+        } else {
+            refract(rind, unit_vector(r_in.direction()), rec.normal)
+        };
+        scattered.set(rec.p, scatter);
+        attenuation.set(1.0, 1.0, 1.0);
+        true
+    }
+
+    fn lobe(&self) -> Lobe {
+        Lobe::Specular
+    }
+
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "dielectric".into()),
+            ("ior", self.refractive_index.into()),
+        ]))
+    }
+}
+
+impl Diaelectric {
+    pub fn from(ind: f64) -> impl Material {
+        Diaelectric {
+            refractive_index: ind,
+        }
+    }
+
+    pub fn reflectance(cosine: f64, n1: f64) -> f64 {
+        let mut r0 = (1.0 - n1) / (1.0 + n1);
+        r0 *= r0;
+        r0 + ((1.0 - r0) * (1.0 - cosine).powi(5))
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(
+        &self,
+        _r_in: Ray,
+        rec: &mut HitRecord,
+        attenuation: &mut Color3,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        let mut dir = rec.normal + sample_unit_vector(sampler.get_2d());
+        if dir.near_zero() {
+            dir = rec.normal;
+        }
+        scattered.set(rec.p, dir);
+        attenuation.copy(self.albedo(rec));
+        // eprintln!("{attenuation:?} {:?}", self.albedo);
+        true
+    }
+
+    fn eval(&self, _r_in: Ray, rec: &HitRecord, wi: Vec3) -> Color3 {
+        self.albedo(rec) * (dot(rec.normal, wi).max(0.0) / PI)
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color3 {
+        match &self.texture {
+            Some(texture) => self.albedo * rec.color * texture.value(rec.u, rec.v, rec.p),
+            None => self.albedo * rec.color,
+        }
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        let mut json = Json::object([
+            ("type", "lambertian".into()),
+            ("albedo", self.albedo.into()),
+        ]);
+        if let (Some(texture), Json::Object(members)) = (&self.texture, &mut json) {
+            members.insert("texture".to_string(), out.texture(texture)?);
+        }
+        Some(json)
+    }
+}
+
+impl Material for Metal {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        rec: &mut HitRecord,
+        attenuation: &mut Color3,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        let mut reflected = reflect(r_in.direction(), rec.normal);
+        reflected = unit_vector(reflected) + (self.fuzz * sample_unit_vector(sampler.get_2d()));
+        scattered.set(rec.p, reflected);
+        attenuation.copy(self.albedo(rec));
+        dot(reflected, rec.normal) > 0.0
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color3 {
+        self.albedo * rec.color
+    }
+
+    fn lobe(&self) -> Lobe {
+        Lobe::Specular
+    }
+
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "metal".into()),
+            ("albedo", self.albedo.into()),
+            ("fuzz", self.fuzz.into()),
+        ]))
+    }
+}
+
+impl Metal {
+    pub fn from(albedo: Color3, fuzz: f64) -> impl Material {
+        Metal {
+            albedo,
+            fuzz: fuzz.min(1.0),
+        }
+    }
+}
+
+impl Lambertian {
+    pub fn from(albedo: Color3) -> impl Material {
+        Lambertian {
+            albedo,
+            texture: None,
+        }
+    }
+
+    /// Albedo `albedo` times the color of `texture` at each hit.
+    pub fn from_texture(albedo: Color3, texture: Rc<dyn Texture>) -> impl Material {
+        Lambertian {
+            albedo,
+            texture: Some(texture),
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{cross, unit_vector, Color3, Point3, Vec3};
+
+/// Subdivision scheme applied to a `Mesh` before rendering.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Subdivision {
+    /// Loop subdivision; polygons are triangulated first.
+    Loop,
+    /// Catmull–Clark subdivision; every level outputs quads.
+    CatmullClark,
+}
+
+impl Subdivision {
+    pub fn from(name: &str) -> Option<Subdivision> {
+        match name {
+            "loop" => Some(Subdivision::Loop),
+            "catmull-clark" => Some(Subdivision::CatmullClark),
+            _ => None,
+        }
+    }
+}
+
+/// Indexed polygon mesh as loaded from a file. `normals`, `uvs` and
+/// `colors` are either empty or hold one entry per position. Faces list vertex indices
+/// counter-clockwise seen from outside.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<(f64, f64)>,
+    /// Linear vertex colors.
+    pub colors: Vec<Color3>,
+    pub faces: Vec<Vec<usize>>,
+}
+
+/// Adjacency of one undirected edge: the faces using it and, for each, the
+/// vertex opposite the edge when that face is a triangle.
+#[derive(Default)]
+struct EdgeInfo {
+    faces: Vec<usize>,
+    opposite: Vec<usize>,
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh::default()
+    }
+
+    /// Smooth vertex normals: the area-weighted average of the normals of
+    /// the faces around each vertex.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vec3::new(); self.positions.len()];
+        for face in &self.faces {
+            let n = self.face_area_normal(face);
+            for v in face {
+                normals[*v] += n;
+            }
+        }
+        self.normals = normals
+            .into_iter()
+            .map(|n| if n.near_zero() { n } else { unit_vector(n) })
+            .collect();
+    }
+
+    /// Normal of a polygon scaled by twice its area.
+    pub fn face_area_normal(&self, face: &[usize]) -> Vec3 {
+        let p0 = self.positions[face[0]];
+        let mut n = Vec3::new();
+        for k in 1..face.len().saturating_sub(1) {
+            n += cross(
+                self.positions[face[k]] - p0,
+                self.positions[face[k + 1]] - p0,
+            );
+        }
+        n
+    }
+
+    /// Splits every polygon into a fan of triangles.
+    pub fn triangulate(&self) -> Mesh {
+        let mut faces = Vec::new();
+        for face in &self.faces {
+            for k in 1..face.len().saturating_sub(1) {
+                faces.push(vec![face[0], face[k], face[k + 1]]);
+            }
+        }
+        Mesh {
+            faces,
+            ..self.clone()
+        }
+    }
+
+    /// Applies `levels` rounds of `scheme` and recomputes smooth normals.
+    /// UVs and vertex colors are interpolated linearly.
+    pub fn subdivide(&self, scheme: Subdivision, levels: u32) -> Mesh {
+        let mut mesh = self.clone();
+        for _ in 0..levels {
+            mesh = match scheme {
+                Subdivision::Loop => mesh.triangulate().loop_step(),
+                Subdivision::CatmullClark => mesh.catmull_clark_step(),
+            };
+        }
+        mesh.compute_normals();
+        mesh
+    }
+
+    fn edges(&self) -> BTreeMap<(usize, usize), EdgeInfo> {
+        let mut edges: BTreeMap<(usize, usize), EdgeInfo> = BTreeMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            let n = face.len();
+            for k in 0..n {
+                let info = edges
+                    .entry(edge_key(face[k], face[(k + 1) % n]))
+                    .or_default();
+                info.faces.push(f);
+                info.opposite.push(face[(k + 2) % n]);
+            }
+        }
+        edges
+    }
+
+    /// Neighbors of every vertex across boundary edges.
+    fn boundary_neighbors(&self, edges: &BTreeMap<(usize, usize), EdgeInfo>) -> Vec<Vec<usize>> {
+        let mut boundary = vec![Vec::new(); self.positions.len()];
+        for ((a, b), info) in edges {
+            if info.faces.len() == 1 {
+                boundary[*a].push(*b);
+                boundary[*b].push(*a);
+            }
+        }
+        boundary
+    }
+
+    fn lerp_uv(&self, weights: &[(usize, f64)]) -> (f64, f64) {
+        weights.iter().fold((0.0, 0.0), |acc, (v, w)| {
+            (acc.0 + w * self.uvs[*v].0, acc.1 + w * self.uvs[*v].1)
+        })
+    }
+
+    fn lerp_color(&self, weights: &[(usize, f64)]) -> Color3 {
+        weights
+            .iter()
+            .fold(Color3::new(), |acc, (v, w)| acc + *w * self.colors[*v])
+    }
+
+    /// One level of Loop subdivision of a triangle mesh.
+    fn loop_step(&self) -> Mesh {
+        let edges = self.edges();
+        let boundary = self.boundary_neighbors(&edges);
+        let has_uvs = !self.uvs.is_empty();
+        let has_colors = !self.colors.is_empty();
+        let nv = self.positions.len();
+
+        let mut neighbors = vec![Vec::new(); nv];
+        for (a, b) in edges.keys() {
+            neighbors[*a].push(*b);
+            neighbors[*b].push(*a);
+        }
+        let mut positions: Vec<Point3> = (0..nv)
+            .map(|v| {
+                let p = self.positions[v];
+                if boundary[v].len() == 2 {
+                    let (b0, b1) = (
+                        self.positions[boundary[v][0]],
+                        self.positions[boundary[v][1]],
+                    );
+                    0.75 * p + 0.125 * (b0 + b1)
+                } else if !boundary[v].is_empty() || neighbors[v].is_empty() {
+                    // Non-manifold corner: keep it where it is.
+                    p
+                } else {
+                    let n = neighbors[v].len() as f64;
+                    let beta = if neighbors[v].len() == 3 {
+                        3.0 / 16.0
+                    } else {
+                        3.0 / (8.0 * n)
+                    };
+                    let sum = neighbors[v]
+                        .iter()
+                        .fold(Vec3::new(), |acc, u| acc + self.positions[*u]);
+                    (1.0 - n * beta) * p + beta * sum
+                }
+            })
+            .collect();
+        let mut uvs = if has_uvs {
+            self.uvs.clone()
+        } else {
+            Vec::new()
+        };
+        let mut colors = self.colors.clone();
+
+        let mut edge_vertex = HashMap::new();
+        for (&(a, b), info) in &edges {
+            let (pa, pb) = (self.positions[a], self.positions[b]);
+            let p = if info.faces.len() == 2 {
+                let (c, d) = (
+                    self.positions[info.opposite[0]],
+                    self.positions[info.opposite[1]],
+                );
+                0.375 * (pa + pb) + 0.125 * (c + d)
+            } else {
+                0.5 * (pa + pb)
+            };
+            edge_vertex.insert((a, b), positions.len());
+            positions.push(p);
+            if has_uvs {
+                uvs.push(self.lerp_uv(&[(a, 0.5), (b, 0.5)]));
+            }
+            if has_colors {
+                colors.push(self.lerp_color(&[(a, 0.5), (b, 0.5)]));
+            }
+        }
+
+        let mut faces = Vec::with_capacity(self.faces.len() * 4);
+        for face in &self.faces {
+            let (a, b, c) = (face[0], face[1], face[2]);
+            let ab = edge_vertex[&edge_key(a, b)];
+            let bc = edge_vertex[&edge_key(b, c)];
+            let ca = edge_vertex[&edge_key(c, a)];
+            faces.push(vec![a, ab, ca]);
+            faces.push(vec![ab, b, bc]);
+            faces.push(vec![ca, bc, c]);
+            faces.push(vec![ab, bc, ca]);
+        }
+        Mesh {
+            positions,
+            normals: Vec::new(),
+            uvs,
+            colors,
+            faces,
+        }
+    }
+
+    /// One level of Catmull–Clark subdivision of a polygon mesh.
+    fn catmull_clark_step(&self) -> Mesh {
+        let edges = self.edges();
+        let boundary = self.boundary_neighbors(&edges);
+        let has_uvs = !self.uvs.is_empty();
+        let has_colors = !self.colors.is_empty();
+        let nv = self.positions.len();
+
+        let face_points: Vec<Point3> = self
+            .faces
+            .iter()
+            .map(|face| {
+                face.iter()
+                    .fold(Vec3::new(), |acc, v| acc + self.positions[*v])
+                    / face.len() as f64
+            })
+            .collect();
+
+        // Per vertex: sums of adjacent face points and incident edge
+        // midpoints, and the number of incident edges.
+        let mut face_sum = vec![(Vec3::new(), 0.0); nv];
+        for (f, face) in self.faces.iter().enumerate() {
+            for v in face {
+                face_sum[*v].0 += face_points[f];
+                face_sum[*v].1 += 1.0;
+            }
+        }
+        let mut edge_sum = vec![(Vec3::new(), 0.0); nv];
+        for (a, b) in edges.keys() {
+            let mid = 0.5 * (self.positions[*a] + self.positions[*b]);
+            for v in [*a, *b] {
+                edge_sum[v].0 += mid;
+                edge_sum[v].1 += 1.0;
+            }
+        }
+
+        let mut positions: Vec<Point3> = (0..nv)
+            .map(|v| {
+                let p = self.positions[v];
+                if boundary[v].len() == 2 {
+                    let (b0, b1) = (
+                        self.positions[boundary[v][0]],
+                        self.positions[boundary[v][1]],
+                    );
+                    0.75 * p + 0.125 * (b0 + b1)
+                } else if !boundary[v].is_empty() || edge_sum[v].1 == 0.0 {
+                    p
+                } else {
+                    let n = edge_sum[v].1;
+                    let f = face_sum[v].0 / face_sum[v].1;
+                    let r = edge_sum[v].0 / n;
+                    (f + 2.0 * r + (n - 3.0) * p) / n
+                }
+            })
+            .collect();
+        let mut uvs = if has_uvs {
+            self.uvs.clone()
+        } else {
+            Vec::new()
+        };
+        let mut colors = self.colors.clone();
+
+        let mut face_vertex = Vec::with_capacity(self.faces.len());
+        for (f, face) in self.faces.iter().enumerate() {
+            face_vertex.push(positions.len());
+            positions.push(face_points[f]);
+            let w = 1.0 / face.len() as f64;
+            let weights: Vec<(usize, f64)> = face.iter().map(|v| (*v, w)).collect();
+            if has_uvs {
+                uvs.push(self.lerp_uv(&weights));
+            }
+            if has_colors {
+                colors.push(self.lerp_color(&weights));
+            }
+        }
+        let mut edge_vertex = HashMap::new();
+        for (&(a, b), info) in &edges {
+            let mid = self.positions[a] + self.positions[b];
+            let p = if info.faces.len() == 2 {
+                (mid + face_points[info.faces[0]] + face_points[info.faces[1]]) / 4.0
+            } else {
+                mid / 2.0
+            };
+            edge_vertex.insert((a, b), positions.len());
+            positions.push(p);
+            if has_uvs {
+                uvs.push(self.lerp_uv(&[(a, 0.5), (b, 0.5)]));
+            }
+            if has_colors {
+                colors.push(self.lerp_color(&[(a, 0.5), (b, 0.5)]));
+            }
+        }
+
+        let mut faces = Vec::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            let n = face.len();
+            for k in 0..n {
+                let (prev, v, next) = (face[(k + n - 1) % n], face[k], face[(k + 1) % n]);
+                faces.push(vec![
+                    v,
+                    edge_vertex[&edge_key(v, next)],
+                    face_vertex[f],
+                    edge_vertex[&edge_key(prev, v)],
+                ]);
+            }
+        }
+        Mesh {
+            positions,
+            normals: Vec::new(),
+            uvs,
+            colors,
+            faces,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    count, dot, polynomial_roots, solve_quadratic, unit_vector, Aabb, Counter, HitRecord, Hittable,
+    Interval, Json, Material, MaterialVisitor, Point3, Ray, SceneWriter, Vec3,
+};
+
+/// Blobby implicit surface: the level set `Σ f_i(p) = threshold` of
+/// overlapping fields `f_i(p) = (1 - |p - c_i|² / R_i²)³`, each zero beyond
+/// its radius `R_i`. Along a ray the sum is piecewise a degree-6
+/// polynomial, so it is intersected exactly, piece by piece, instead of
+/// by marching. UVs are not set.
+pub struct Metaballs {
+    balls: Vec<(Point3, f64)>,
+    threshold: f64,
+    bbox: Aabb,
+    mat: Rc<RefCell<dyn Material>>,
+}
+
+impl Metaballs {
+    /// `threshold` in `(0, 1)`: a lone ball has a surface radius of
+    /// `R sqrt(1 - threshold^(1/3))`.
+    pub fn new(threshold: f64, mat: Rc<RefCell<dyn Material>>) -> Metaballs {
+        Metaballs {
+            balls: Vec::new(),
+            threshold,
+            bbox: Aabb::new(),
+            mat,
+        }
+    }
+
+    pub fn add(&mut self, center: Point3, radius: f64) {
+        let r = Vec3::from(radius, radius, radius);
+        self.bbox = Aabb::from_boxes(&self.bbox, &Aabb::from(center - r, center + r));
+        self.balls.push((center, radius));
+    }
+
+    /// Coefficients of `1 - |o + t d - c|² / R²` in `t`, constant first.
+    fn falloff(&self, r: Ray, ball: usize) -> [f64; 3] {
+        let (center, radius) = self.balls[ball];
+        let oc = r.origin() - center;
+        let inv_r2 = 1.0 / (radius * radius);
+        [
+            1.0 - oc.length_squared() * inv_r2,
+            -2.0 * dot(oc, r.direction()) * inv_r2,
+            -r.direction().length_squared() * inv_r2,
+        ]
+    }
+
+    /// Outward normal: minus the gradient of the summed field.
+    fn normal(&self, p: Point3) -> Vec3 {
+        let mut n = Vec3::new();
+        for (center, radius) in &self.balls {
+            let r2 = radius * radius;
+            let g = 1.0 - (p - *center).length_squared() / r2;
+            if g > 0.0 {
+                n += (6.0 * g * g / r2) * (p - *center);
+            }
+        }
+        unit_vector(n)
+    }
+}
+
+impl Hittable for Metaballs {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        count(Counter::IntersectionTests);
+        if !self.bbox.hit(r, ray_root.clone()) {
+            return false;
+        }
+
+        // Where the ray is within reach of each ball.
+        let mut spans = Vec::new();
+        let mut breaks = Vec::new();
+        for ball in 0..self.balls.len() {
+            let [c, b, a] = self.falloff(r, ball);
+            let roots = solve_quadratic(a, b, c);
+            if roots.len() == 2 && roots[1] > ray_root.min && roots[0] < ray_root.max {
+                spans.push((ball, roots[0], roots[1]));
+                breaks.push(roots[0].max(ray_root.min));
+                breaks.push(roots[1].min(ray_root.max));
+            }
+        }
+        breaks.sort_by(f64::total_cmp);
+
+        // Between consecutive span boundaries the set of contributing balls
+        // is fixed and the field is one polynomial.
+        for w in breaks.windows(2) {
+            let (lo, hi) = (w[0], w[1]);
+            if hi <= lo {
+                continue;
+            }
+            let mid = 0.5 * (lo + hi);
+            let mut poly = [0.0; 7];
+            poly[0] = -self.threshold;
+            for (ball, t0, t1) in &spans {
+                if *t0 < mid && mid < *t1 {
+                    let g = self.falloff(r, *ball);
+                    let g2 = multiply(&g, &g);
+                    for (k, c) in multiply(&g2, &g).iter().enumerate() {
+                        poly[k] += c;
+                    }
+                }
+            }
+            let Some(t) = polynomial_roots(&poly, lo, hi)
+                .into_iter()
+                .find(|t| ray_root.surrounds(*t))
+            else {
+                continue;
+            };
+            rec.t = t;
+            rec.p = r.at(t);
+            rec.set_face_normal(r, self.normal(rec.p));
+            (rec.u, rec.v) = (0.0, 0.0);
+            rec.mat = self.mat.clone();
+            return true;
+        }
+        false
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        let balls: Vec<Json> = self
+            .balls
+            .iter()
+            .flat_map(|(c, r)| [c.x().into(), c.y().into(), c.z().into(), (*r).into()])
+            .collect();
+        Some(Json::object([
+            ("type", "metaballs".into()),
+            ("threshold", self.threshold.into()),
+            ("balls", balls.into()),
+            ("material", out.material(&self.mat)),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        f(&self.mat);
+    }
+}
+
+fn multiply(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            out[i + j] += x * y;
+        }
+    }
+    out
+}
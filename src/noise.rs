@@ -0,0 +1,81 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{dot, unit_vector, Point3, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+/// Perlin gradient noise with reproducible, seeded tables.
+pub struct Perlin {
+    randvec: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let randvec = (0..POINT_COUNT)
+            .map(|_| {
+                unit_vector(Vec3::from(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                ))
+            })
+            .collect();
+        let mut perm = || {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(&mut rng);
+            p
+        };
+        Perlin {
+            perm_x: perm(),
+            perm_y: perm(),
+            perm_z: perm(),
+            randvec,
+        }
+    }
+
+    /// Smooth noise in roughly `[-1, 1]`.
+    pub fn noise(&self, p: Point3) -> f64 {
+        let (fx, fy, fz) = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (u, v, w) = (p.x() - fx, p.y() - fy, p.z() - fz);
+        let (i, j, k) = (fx as i64, fy as i64, fz as i64);
+
+        let mut accum = 0.0;
+        let (uu, vv, ww) = (hermite(u), hermite(v), hermite(w));
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let idx = self.perm_x[((i + di) & 255) as usize]
+                        ^ self.perm_y[((j + dj) & 255) as usize]
+                        ^ self.perm_z[((k + dk) & 255) as usize];
+                    let weight = Vec3::from(u - di as f64, v - dj as f64, w - dk as f64);
+                    let (a, b, c) = (di as f64, dj as f64, dk as f64);
+                    accum += (a * uu + (1.0 - a) * (1.0 - uu))
+                        * (b * vv + (1.0 - b) * (1.0 - vv))
+                        * (c * ww + (1.0 - c) * (1.0 - ww))
+                        * dot(self.randvec[idx], weight);
+                }
+            }
+        }
+        accum
+    }
+
+    /// Fractal sum of `octaves` noise layers, each at twice the frequency
+    /// and half the amplitude of the previous one.
+    pub fn fbm(&self, p: Point3, octaves: u32) -> f64 {
+        let (mut sum, mut amplitude, mut p) = (0.0, 1.0, p);
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(p);
+            amplitude *= 0.5;
+            p = 2.0 * p;
+        }
+        sum
+    }
+}
+
+fn hermite(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
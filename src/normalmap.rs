@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    cross, dot, luminance, unit_vector, Color3, HitRecord, Json, Lobe, Material, Ray, Sampler,
+    SceneWriter, Texture, Vec3,
+};
+
+/// Step in `u` and `v` for the finite differences of a bump map.
+const BUMP_DELTA: f64 = 5e-4;
+
+/// Outward shading normal of `rec`, undoing the flip towards the ray.
+fn outward_normal(rec: &HitRecord) -> Vec3 {
+    if rec.front_face {
+        rec.normal
+    } else {
+        -rec.normal
+    }
+}
+
+/// Tangent-space normal map on top of another material. Texels encode the
+/// normal as `0.5 * (n + 1)`, with x along `dpdu`, y along `dpdv` and z
+/// along the surface normal.
+pub struct NormalMap {
+    inner: Rc<RefCell<dyn Material>>,
+    map: Rc<dyn Texture>,
+    strength: f64,
+}
+
+impl NormalMap {
+    /// `strength` scales the tangential part: 0 disables the map, 1 uses
+    /// it as authored.
+    pub fn from(
+        inner: Rc<RefCell<dyn Material>>,
+        map: Rc<dyn Texture>,
+        strength: f64,
+    ) -> impl Material {
+        NormalMap {
+            inner,
+            map,
+            strength,
+        }
+    }
+}
+
+/// Height map on top of another material: the surface shades as if it
+/// were displaced along its normal by `scale` times the luminance of
+/// `height`, without moving the geometry.
+pub struct BumpMap {
+    inner: Rc<RefCell<dyn Material>>,
+    height: Rc<dyn Texture>,
+    scale: f64,
+}
+
+impl BumpMap {
+    pub fn from(
+        inner: Rc<RefCell<dyn Material>>,
+        height: Rc<dyn Texture>,
+        scale: f64,
+    ) -> impl Material {
+        BumpMap {
+            inner,
+            height,
+            scale,
+        }
+    }
+}
+
+/// Forwards everything but `perturb_normal` to the wrapped material.
+macro_rules! delegate_material {
+    () => {
+        fn scatter(
+            &self,
+            r_in: Ray,
+            rec: &mut HitRecord,
+            attenuation: &mut Color3,
+            scattered: &mut Ray,
+            sampler: &mut dyn Sampler,
+        ) -> bool {
+            self.inner
+                .borrow()
+                .scatter(r_in, rec, attenuation, scattered, sampler)
+        }
+
+        fn eval(&self, r_in: Ray, rec: &HitRecord, wi: Vec3) -> Color3 {
+            self.inner.borrow().eval(r_in, rec, wi)
+        }
+
+        fn albedo(&self, rec: &HitRecord) -> Color3 {
+            self.inner.borrow().albedo(rec)
+        }
+
+        fn lobe(&self) -> Lobe {
+            self.inner.borrow().lobe()
+        }
+    };
+}
+
+impl Material for NormalMap {
+    delegate_material!();
+
+    fn perturb_normal(&self, rec: &mut HitRecord) {
+        self.inner.borrow().perturb_normal(rec);
+        let n = outward_normal(rec);
+        let t = rec.dpdu - dot(rec.dpdu, n) * n;
+        if t.near_zero() {
+            return;
+        }
+        let t = unit_vector(t);
+        let mut b = cross(n, t);
+        if dot(b, rec.dpdv) < 0.0 {
+            b = -b;
+        }
+        let m = 2.0 * self.map.value(rec.u, rec.v, rec.p) - Color3::from(1.0, 1.0, 1.0);
+        let mapped = self.strength * (m.x() * t + m.y() * b) + m.z() * n;
+        if !mapped.near_zero() {
+            rec.set_shading_normal(unit_vector(mapped));
+        }
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "normal_map".into()),
+            ("material", out.material(&self.inner)),
+            ("map", out.texture(&self.map)?),
+            ("strength", self.strength.into()),
+        ]))
+    }
+}
+
+impl Material for BumpMap {
+    delegate_material!();
+
+    fn perturb_normal(&self, rec: &mut HitRecord) {
+        self.inner.borrow().perturb_normal(rec);
+        let n = outward_normal(rec);
+        let height = |du: f64, dv: f64| {
+            let p = rec.p + du * rec.dpdu + dv * rec.dpdv;
+            self.scale * luminance(self.height.value(rec.u + du, rec.v + dv, p))
+        };
+        let h = height(0.0, 0.0);
+        let dhdu = (height(BUMP_DELTA, 0.0) - h) / BUMP_DELTA;
+        let dhdv = (height(0.0, BUMP_DELTA) - h) / BUMP_DELTA;
+        let bumped = cross(rec.dpdu + dhdu * n, rec.dpdv + dhdv * n);
+        if bumped.near_zero() {
+            return;
+        }
+        // The cross product is outward only for a right-handed (u, v) frame.
+        let flip = dot(cross(rec.dpdu, rec.dpdv), n) < 0.0;
+        rec.set_shading_normal(unit_vector(if flip { -bumped } else { bumped }));
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "bump_map".into()),
+            ("material", out.material(&self.inner)),
+            ("height", out.texture(&self.height)?),
+            ("scale", self.scale.into()),
+        ]))
+    }
+}
@@ -0,0 +1,33 @@
+use crate::{cross, dot, unit_vector, Vec3};
+
+/// Orthonormal basis, used to intersect shapes in a local frame whose `w`
+/// axis is the shape's axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    /// Right-handed basis around `n`, which need not be normalized.
+    pub fn from_w(n: Vec3) -> Onb {
+        let w = unit_vector(n);
+        let a = if w.x().abs() > 0.9 {
+            Vec3::from(0.0, 1.0, 0.0)
+        } else {
+            Vec3::from(1.0, 0.0, 0.0)
+        };
+        let v = unit_vector(cross(w, a));
+        let u = cross(v, w);
+        Onb { u, v, w }
+    }
+
+    pub fn to_local(&self, a: Vec3) -> Vec3 {
+        Vec3::from(dot(a, self.u), dot(a, self.v), dot(a, self.w))
+    }
+
+    pub fn to_world(&self, a: Vec3) -> Vec3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+}
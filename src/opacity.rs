@@ -0,0 +1,93 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    luminance, Aabb, HitRecord, Hittable, Interval, Json, MaterialVisitor, Ray, SceneWriter,
+    Texture,
+};
+
+/// Cutout wrapper: hits where the luminance of `mask` is 0 are skipped and
+/// the search goes on behind them within the same interval, hits where it
+/// is 1 are kept. In between, a hit is kept with probability equal to the
+/// opacity, decided by hashing the ray and hit distance so that every
+/// query along the same ray agrees and the average converges to the
+/// partial coverage.
+pub struct OpacityMask {
+    object: Rc<RefCell<dyn Hittable>>,
+    mask: Rc<dyn Texture>,
+}
+
+impl OpacityMask {
+    pub fn new(object: Rc<RefCell<dyn Hittable>>, mask: Rc<dyn Texture>) -> OpacityMask {
+        OpacityMask { object, mask }
+    }
+
+    fn opaque(&self, r: Ray, rec: &HitRecord) -> bool {
+        let alpha = luminance(self.mask.value(rec.u, rec.v, rec.p));
+        if alpha >= 1.0 {
+            return true;
+        }
+        if alpha <= 0.0 {
+            return false;
+        }
+        let (o, d) = (r.origin(), r.direction());
+        hash_unit(&[o.x(), o.y(), o.z(), d.x(), d.y(), d.z(), rec.t]) < alpha
+    }
+}
+
+impl Hittable for OpacityMask {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        let mut t_min = ray_root.min;
+        let mut temp_rec = HitRecord::new();
+        while self
+            .object
+            .borrow()
+            .hit(r, Interval::from(t_min, ray_root.max), &mut temp_rec)
+        {
+            if self.opaque(r, &temp_rec) {
+                rec.copy(&temp_rec);
+                return true;
+            }
+            t_min = temp_rec.t + 1e-9 * temp_rec.t.abs().max(1.0);
+        }
+        false
+    }
+
+    fn hit_all(&self, r: Ray, ray_root: Interval, hits: &mut Vec<HitRecord>) -> bool {
+        let start = hits.len();
+        let mut all = Vec::new();
+        self.object.borrow().hit_all(r, ray_root, &mut all);
+        hits.extend(all.into_iter().filter(|rec| self.opaque(r, rec)));
+        hits.len() > start
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object.borrow().bounding_box()
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "opacity_mask".into()),
+            ("object", out.object(&self.object)?),
+            ("mask", out.texture(&self.mask)?),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        self.object.borrow().for_each_material(f);
+    }
+}
+
+/// Uniform number in `[0, 1)` derived from the bits of `values`.
+fn hash_unit(values: &[f64]) -> f64 {
+    let mut h: u64 = 0x9e37_79b9_7f4a_7c15;
+    for v in values {
+        // SplitMix64 finalizer over the running state.
+        h ^= v.to_bits();
+        h = h.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^= h >> 31;
+    }
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,424 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::rc::Rc;
+
+use crate::{srgb_to_linear, vertex_normals, Color3, Material, Mesh, Point3, TriangleMesh, Vec3};
+
+/// Scalar property types of the PLY header.
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn from(name: &str) -> Option<Scalar> {
+        match name {
+            "char" | "int8" => Some(Scalar::I8),
+            "uchar" | "uint8" => Some(Scalar::U8),
+            "short" | "int16" => Some(Scalar::I16),
+            "ushort" | "uint16" => Some(Scalar::U16),
+            "int" | "int32" => Some(Scalar::I32),
+            "uint" | "uint32" => Some(Scalar::U32),
+            "float" | "float32" => Some(Scalar::F32),
+            "double" | "float64" => Some(Scalar::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// Scale mapping an integer color channel to `[0, 1]`.
+    fn color_scale(self) -> f64 {
+        match self {
+            Scalar::U8 | Scalar::I8 => 1.0 / 255.0,
+            Scalar::U16 | Scalar::I16 => 1.0 / 65535.0,
+            _ => 1.0,
+        }
+    }
+}
+
+enum Property {
+    Scalar(String, Scalar),
+    /// Name, type of the item count, type of the items.
+    List(String, Scalar, Scalar),
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads property values from the body in either encoding.
+struct Body<R> {
+    input: R,
+    /// `None` for ASCII, otherwise whether the data is little endian.
+    little_endian: Option<bool>,
+    line: String,
+    pos: usize,
+}
+
+impl<R: BufRead> Body<R> {
+    fn value(&mut self, ty: Scalar) -> io::Result<f64> {
+        let Some(little) = self.little_endian else {
+            let token = self.token()?;
+            return token
+                .parse()
+                .map_err(|_| invalid(format!("bad number {token}")));
+        };
+        let mut buf = [0; 8];
+        let bytes = &mut buf[..ty.size()];
+        self.input.read_exact(bytes)?;
+        if !little {
+            bytes.reverse();
+        }
+        Ok(match ty {
+            Scalar::I8 => buf[0] as i8 as f64,
+            Scalar::U8 => buf[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(buf),
+        })
+    }
+
+    /// Reads and discards the values of `property`.
+    fn skip(&mut self, property: &Property) -> io::Result<()> {
+        match property {
+            Property::Scalar(_, ty) => {
+                self.value(*ty)?;
+            }
+            Property::List(_, count, item) => {
+                for _ in 0..self.value(*count)? as usize {
+                    self.value(*item)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Next whitespace-separated token of an ASCII body.
+    fn token(&mut self) -> io::Result<String> {
+        loop {
+            let rest = &self.line[self.pos..];
+            let start = rest.len() - rest.trim_start().len();
+            let rest = &rest[start..];
+            if !rest.is_empty() {
+                let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let token = rest[..len].to_string();
+                self.pos += start + len;
+                return Ok(token);
+            }
+            self.line.clear();
+            self.pos = 0;
+            if self.input.read_line(&mut self.line)? == 0 {
+                return Err(invalid("unexpected end of file".to_string()));
+            }
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Header line without its line ending; `None` at the end of the file.
+fn header_line(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = Vec::new();
+    if input.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&line).trim_end().to_string()))
+}
+
+/// Loads an ASCII or binary PLY as a triangle mesh. Vertices need `x`, `y`
+/// and `z`; normals (`nx`, `ny`, `nz`), texture coordinates (`u`/`v` or
+/// `s`/`t`) and colors (`red`, `green`, `blue`, taken as sRGB) are used
+/// when present. Polygons are split into fans, and smooth normals are
+/// generated when the file has none. The body is streamed, so only the
+/// mesh itself is held in memory.
+pub fn load_ply(path: &str, mat: Rc<RefCell<dyn Material>>) -> io::Result<TriangleMesh> {
+    let mut triangles = Vec::new();
+    let Vertices {
+        positions,
+        mut normals,
+        uvs,
+        colors,
+    } = read(path, |polygon| {
+        for k in 1..polygon.len().saturating_sub(1) {
+            triangles.push([polygon[0], polygon[k], polygon[k + 1]]);
+        }
+    })?;
+    if let Some(t) = triangles
+        .iter()
+        .flatten()
+        .find(|v| **v as usize >= positions.len())
+    {
+        return Err(invalid(format!("vertex index {t} out of range")));
+    }
+    if normals.is_empty() {
+        normals = vertex_normals(&positions, &triangles);
+    }
+    Ok(TriangleMesh::from_triangles(
+        positions, normals, uvs, colors, triangles, mat,
+    ))
+}
+
+/// Reads a PLY like `load_ply`, but into a `Mesh` that keeps the polygons
+/// of the file, e.g. to subdivide it. Normals are only those of the file.
+pub fn read_ply(path: &str) -> io::Result<Mesh> {
+    let mut faces: Vec<Vec<usize>> = Vec::new();
+    let Vertices {
+        positions,
+        normals,
+        uvs,
+        colors,
+    } = read(path, |polygon| {
+        if polygon.len() >= 3 {
+            faces.push(polygon.iter().map(|v| *v as usize).collect());
+        }
+    })?;
+    if let Some(v) = faces.iter().flatten().find(|v| **v >= positions.len()) {
+        return Err(invalid(format!("vertex index {v} out of range")));
+    }
+    Ok(Mesh {
+        positions,
+        normals,
+        uvs,
+        colors,
+        faces,
+    })
+}
+
+/// Vertex attributes of a PLY body; each is empty or holds one entry per
+/// position.
+#[derive(Default)]
+struct Vertices {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+    colors: Vec<Color3>,
+}
+
+/// Reads the PLY at `path`, handing every face to `face` as the vertex
+/// indices of its polygon.
+fn read(path: &str, mut face: impl FnMut(&[u32])) -> io::Result<Vertices> {
+    let mut input = BufReader::new(File::open(path)?);
+    if header_line(&mut input)?.as_deref() != Some("ply") {
+        return Err(invalid(format!("{path} is not a PLY file")));
+    }
+    let mut little_endian = None;
+    let mut elements: Vec<Element> = Vec::new();
+    loop {
+        let Some(line) = header_line(&mut input)? else {
+            return Err(invalid("missing end_header".to_string()));
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", "ascii", _] => little_endian = None,
+            ["format", "binary_little_endian", _] => little_endian = Some(true),
+            ["format", "binary_big_endian", _] => little_endian = Some(false),
+            ["format", ..] => return Err(invalid(format!("unsupported format: {line}"))),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| invalid(format!("bad element count: {line}")))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let (Some(count), Some(item)) = (Scalar::from(count), Scalar::from(item)) else {
+                    return Err(invalid(format!("unknown property type: {line}")));
+                };
+                let element = elements
+                    .last_mut()
+                    .ok_or(invalid("property before element".to_string()))?;
+                element
+                    .properties
+                    .push(Property::List(name.to_string(), count, item));
+            }
+            ["property", ty, name] => {
+                let ty =
+                    Scalar::from(ty).ok_or(invalid(format!("unknown property type: {line}")))?;
+                let element = elements
+                    .last_mut()
+                    .ok_or(invalid("property before element".to_string()))?;
+                element
+                    .properties
+                    .push(Property::Scalar(name.to_string(), ty));
+            }
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(invalid(format!("bad header line: {line}"))),
+        }
+    }
+
+    let mut body = Body {
+        input,
+        little_endian,
+        line: String::new(),
+        pos: 0,
+    };
+    let mut vertices = Vertices::default();
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => read_vertices(
+                &mut body,
+                element,
+                &mut vertices.positions,
+                &mut vertices.normals,
+                &mut vertices.uvs,
+                &mut vertices.colors,
+            )?,
+            "face" => read_faces(&mut body, element, &mut face)?,
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        body.skip(property)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(vertices)
+}
+
+/// Which vertex attribute a property feeds, and the component.
+enum Slot {
+    Position(usize),
+    Normal(usize),
+    Uv(usize),
+    Color(usize, f64),
+    Skip,
+}
+
+/// Element counts come from the file, so capacity reserved up front is capped.
+const MAX_RESERVE: usize = 1 << 24;
+
+fn read_vertices<R: BufRead>(
+    body: &mut Body<R>,
+    element: &Element,
+    positions: &mut Vec<Point3>,
+    normals: &mut Vec<Vec3>,
+    uvs: &mut Vec<(f64, f64)>,
+    colors: &mut Vec<Color3>,
+) -> io::Result<()> {
+    let slots: Vec<Slot> = element
+        .properties
+        .iter()
+        .map(|property| match property {
+            Property::Scalar(name, ty) => match name.as_str() {
+                "x" => Slot::Position(0),
+                "y" => Slot::Position(1),
+                "z" => Slot::Position(2),
+                "nx" => Slot::Normal(0),
+                "ny" => Slot::Normal(1),
+                "nz" => Slot::Normal(2),
+                "u" | "s" | "texture_u" => Slot::Uv(0),
+                "v" | "t" | "texture_v" => Slot::Uv(1),
+                "red" | "diffuse_red" => Slot::Color(0, ty.color_scale()),
+                "green" | "diffuse_green" => Slot::Color(1, ty.color_scale()),
+                "blue" | "diffuse_blue" => Slot::Color(2, ty.color_scale()),
+                _ => Slot::Skip,
+            },
+            Property::List(..) => Slot::Skip,
+        })
+        .collect();
+    let has = |f: fn(&Slot) -> bool| slots.iter().filter(|s| f(s)).count();
+    if has(|s| matches!(s, Slot::Position(_))) != 3 {
+        return Err(invalid("vertices need x, y and z".to_string()));
+    }
+    let has_normals = has(|s| matches!(s, Slot::Normal(_))) == 3;
+    let has_uvs = has(|s| matches!(s, Slot::Uv(_))) == 2;
+    let has_colors = has(|s| matches!(s, Slot::Color(..))) == 3;
+
+    let reserve = element.count.min(MAX_RESERVE);
+    positions.reserve(reserve);
+    if has_normals {
+        normals.reserve(reserve);
+    }
+    if has_uvs {
+        uvs.reserve(reserve);
+    }
+    if has_colors {
+        colors.reserve(reserve);
+    }
+    for _ in 0..element.count {
+        let (mut p, mut n, mut uv, mut c) = ([0.0; 3], [0.0; 3], [0.0; 2], [0.0; 3]);
+        for (property, slot) in element.properties.iter().zip(&slots) {
+            let Property::Scalar(_, ty) = property else {
+                body.skip(property)?;
+                continue;
+            };
+            let value = body.value(*ty)?;
+            match slot {
+                Slot::Position(k) => p[*k] = value,
+                Slot::Normal(k) => n[*k] = value,
+                Slot::Uv(k) => uv[*k] = value,
+                Slot::Color(k, scale) => c[*k] = value * scale,
+                Slot::Skip => {}
+            }
+        }
+        positions.push(Point3::from(p[0], p[1], p[2]));
+        if has_normals {
+            normals.push(Vec3::from(n[0], n[1], n[2]));
+        }
+        if has_uvs {
+            uvs.push((uv[0], uv[1]));
+        }
+        if has_colors {
+            colors.push(Color3::from(
+                srgb_to_linear(c[0]),
+                srgb_to_linear(c[1]),
+                srgb_to_linear(c[2]),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn read_faces<R: BufRead>(
+    body: &mut Body<R>,
+    element: &Element,
+    face: &mut impl FnMut(&[u32]),
+) -> io::Result<()> {
+    let mut polygon = Vec::new();
+    for _ in 0..element.count {
+        for property in &element.properties {
+            match property {
+                Property::List(name, count, item)
+                    if name == "vertex_indices" || name == "vertex_index" =>
+                {
+                    polygon.clear();
+                    for _ in 0..body.value(*count)? as usize {
+                        let v = body.value(*item)?;
+                        if !(0.0..u32::MAX as f64).contains(&v) {
+                            return Err(invalid(format!("vertex index {v} out of range")));
+                        }
+                        polygon.push(v as u32);
+                    }
+                    face(&polygon);
+                }
+                _ => body.skip(property)?,
+            }
+        }
+    }
+    Ok(())
+}
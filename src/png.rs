@@ -0,0 +1,350 @@
+use crate::{Color3, Image};
+
+/// Decodes a non-interlaced PNG of any bit depth and color type. Returns
+/// the color samples normalized to `[0, 1]` as stored, without decoding
+/// sRGB, and one alpha value per pixel (1 where the file has none).
+pub fn decode_png(bytes: &[u8]) -> Option<(Image, Vec<f64>)> {
+    if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return None;
+    }
+    let mut pos = 8;
+    let mut header = None;
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut transparency: Vec<u8> = Vec::new();
+    let mut data = Vec::new();
+    while pos + 8 <= bytes.len() {
+        let len = u32::from_be_bytes(bytes[pos..pos + 4].try_into().ok()?) as usize;
+        let kind = &bytes[pos + 4..pos + 8];
+        let body = bytes.get(pos + 8..pos + 8 + len)?;
+        // Skip the length, type and trailing CRC.
+        pos += 12 + len;
+        match kind {
+            b"IHDR" => {
+                let w = u32::from_be_bytes(body.get(0..4)?.try_into().ok()?) as u64;
+                let h = u32::from_be_bytes(body.get(4..8)?.try_into().ok()?) as u64;
+                // Bit depth, color type and interlace method.
+                header = Some((w, h, *body.get(8)?, *body.get(9)?, *body.get(12)?));
+            }
+            b"PLTE" => palette = body.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+            b"tRNS" => transparency = body.to_vec(),
+            b"IDAT" => data.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+    let (w, h, depth, color_type, interlace) = header?;
+    let channels = match color_type {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return None,
+    };
+    if interlace != 0 || w == 0 || h == 0 || !matches!(depth, 1 | 2 | 4 | 8 | 16) {
+        return None;
+    }
+
+    let raw = inflate_zlib(&data)?;
+    let bits_per_pixel = channels * depth as usize;
+    let stride = (w as usize * bits_per_pixel).div_ceil(8);
+    let bpp = bits_per_pixel.div_ceil(8);
+    if raw.len() < (stride + 1) * h as usize {
+        return None;
+    }
+    let mut lines = vec![0u8; stride * h as usize];
+    for y in 0..h as usize {
+        let filter = raw[y * (stride + 1)];
+        let src = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (done, rest) = lines.split_at_mut(y * stride);
+        let prev = if y > 0 {
+            &done[(y - 1) * stride..]
+        } else {
+            &[][..]
+        };
+        let line = &mut rest[..stride];
+        for x in 0..stride {
+            let a = if x >= bpp { line[x - bpp] } else { 0 };
+            let b = prev.get(x).copied().unwrap_or(0);
+            let c = if x >= bpp {
+                prev.get(x - bpp).copied().unwrap_or(0)
+            } else {
+                0
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return None,
+            };
+            line[x] = src[x].wrapping_add(predictor);
+        }
+    }
+
+    // Sample `k` of a line, for any bit depth.
+    let sample = |line: &[u8], k: usize| -> u32 {
+        match depth {
+            16 => u16::from_be_bytes([line[2 * k], line[2 * k + 1]]) as u32,
+            8 => line[k] as u32,
+            _ => {
+                let bit = k * depth as usize;
+                let shift = 8 - depth as usize - bit % 8;
+                ((line[bit / 8] >> shift) & ((1 << depth) - 1)) as u32
+            }
+        }
+    };
+    let max = ((1u32 << depth) - 1) as f64;
+    let mut pixels = Vec::with_capacity((w * h) as usize);
+    let mut alpha = Vec::with_capacity((w * h) as usize);
+    for line in lines.chunks(stride) {
+        for x in 0..w as usize {
+            let s = |c: usize| sample(line, x * channels + c);
+            let (clr, a) = match color_type {
+                3 => {
+                    let index = s(0) as usize;
+                    let [r, g, b] = *palette.get(index)?;
+                    let a = transparency.get(index).copied().unwrap_or(255);
+                    let rgb = Color3::from(r as f64, g as f64, b as f64) / 255.0;
+                    (rgb, a as f64 / 255.0)
+                }
+                0 | 4 => {
+                    let v = s(0) as f64 / max;
+                    let a = if color_type == 4 {
+                        s(1) as f64 / max
+                    } else if transparency.len() >= 2
+                        && s(0) == u16::from_be_bytes([transparency[0], transparency[1]]) as u32
+                    {
+                        0.0
+                    } else {
+                        1.0
+                    };
+                    (Color3::from(v, v, v), a)
+                }
+                _ => {
+                    let rgb = Color3::from(s(0) as f64, s(1) as f64, s(2) as f64) / max;
+                    let a = if color_type == 6 {
+                        s(3) as f64 / max
+                    } else {
+                        1.0
+                    };
+                    (rgb, a)
+                }
+            };
+            pixels.push(clr);
+            alpha.push(a);
+        }
+    }
+    Some((Image { w, h, pixels }, alpha))
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Decompresses a zlib stream (RFC 1950) without checking its checksum.
+pub fn inflate_zlib(data: &[u8]) -> Option<Vec<u8>> {
+    let (cmf, flg) = (*data.first()?, *data.get(1)?);
+    if cmf & 0x0f != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) || flg & 0x20 != 0 {
+        return None;
+    }
+    inflate(&data[2..])
+}
+
+/// Decompresses a raw DEFLATE stream (RFC 1951).
+pub fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut bits = BitReader { data, pos: 0 };
+    let mut out = Vec::new();
+    loop {
+        let last = bits.read(1)? == 1;
+        match bits.read(2)? {
+            0 => {
+                bits.align();
+                let start = bits.pos / 8;
+                let len = u16::from_le_bytes([*data.get(start)?, *data.get(start + 1)?]);
+                let block = data.get(start + 4..start + 4 + len as usize)?;
+                out.extend_from_slice(block);
+                bits.pos = (start + 4 + len as usize) * 8;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let lit = Huffman::new(&lengths)?;
+                let dist = Huffman::new(&[5; 30])?;
+                inflate_block(&mut bits, &mut out, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut bits)?;
+                inflate_block(&mut bits, &mut out, &lit, &dist)?;
+            }
+            _ => return None,
+        }
+        if last {
+            return Some(out);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Position in bits.
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    /// Next `n` bits, least significant first.
+    fn read(&mut self, n: u32) -> Option<u32> {
+        let mut v = 0;
+        for k in 0..n {
+            let byte = *self.data.get(self.pos / 8)?;
+            v |= (((byte >> (self.pos % 8)) & 1) as u32) << k;
+            self.pos += 1;
+        }
+        Some(v)
+    }
+
+    fn align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+}
+
+/// Canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+    /// Number of codes of each length.
+    counts: [u16; 16],
+    /// Symbols ordered by code.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Option<Huffman> {
+        let mut counts = [0u16; 16];
+        for l in lengths {
+            counts[*l as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for l in 1..16 {
+            offsets[l] = offsets[l - 1] + counts[l - 1];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (s, l) in lengths.iter().enumerate() {
+            if *l != 0 {
+                symbols[offsets[*l as usize] as usize] = s as u16;
+                offsets[*l as usize] += 1;
+            }
+        }
+        Some(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> Option<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.read(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return self.symbols.get((index + code - first) as usize).copied();
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+fn dynamic_tables(bits: &mut BitReader) -> Option<(Huffman, Huffman)> {
+    const ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+    let nlen = bits.read(5)? as usize + 257;
+    let ndist = bits.read(5)? as usize + 1;
+    let ncode = bits.read(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for k in ORDER.iter().take(ncode) {
+        code_lengths[*k] = bits.read(3)? as u8;
+    }
+    let code = Huffman::new(&code_lengths)?;
+
+    let mut lengths = Vec::with_capacity(nlen + ndist);
+    while lengths.len() < nlen + ndist {
+        let sym = code.decode(bits)?;
+        let (value, repeat) = match sym {
+            0..=15 => (sym as u8, 1),
+            16 => (*lengths.last()?, 3 + bits.read(2)?),
+            17 => (0, 3 + bits.read(3)?),
+            18 => (0, 11 + bits.read(7)?),
+            _ => return None,
+        };
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+    if lengths.len() != nlen + ndist {
+        return None;
+    }
+    Some((
+        Huffman::new(&lengths[..nlen])?,
+        Huffman::new(&lengths[nlen..])?,
+    ))
+}
+
+fn inflate_block(
+    bits: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> Option<()> {
+    loop {
+        let sym = lit.decode(bits)? as usize;
+        if sym < 256 {
+            out.push(sym as u8);
+            continue;
+        }
+        if sym == 256 {
+            return Some(());
+        }
+        let k = sym - 257;
+        let len = *LENGTH_BASE.get(k)? as usize + bits.read(*LENGTH_EXTRA.get(k)? as u32)? as usize;
+        let d = dist.decode(bits)? as usize;
+        let back = *DIST_BASE.get(d)? as usize + bits.read(*DIST_EXTRA.get(d)? as u32)? as usize;
+        if back > out.len() {
+            return None;
+        }
+        let start = out.len() - back;
+        for i in 0..len {
+            out.push(out[start + i]);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::cell::Cell;
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crate::RenderProgress;
+
+/// Terminal progress reporter for `RenderSettings::with_progress`: a bar
+/// with percentage, elapsed time, ETA and throughput on stderr. Redraws in
+/// place on a terminal; otherwise, e.g. when stderr goes to a log file, it
+/// prints a line every tenth of the way.
+pub struct ProgressBar {
+    interactive: bool,
+    /// Bar length in characters.
+    width: usize,
+    last_draw: Cell<Option<Instant>>,
+    last_tenth: Cell<u64>,
+}
+
+/// Redraws closer together than this are skipped.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressBar {
+    pub fn new() -> ProgressBar {
+        ProgressBar {
+            interactive: io::stderr().is_terminal(),
+            width: 30,
+            last_draw: Cell::new(None),
+            last_tenth: Cell::new(0),
+        }
+    }
+
+    pub fn update(&self, p: RenderProgress) {
+        if self.interactive {
+            let due = self
+                .last_draw
+                .get()
+                .is_none_or(|t| t.elapsed() >= REDRAW_INTERVAL);
+            if due || p.finished {
+                self.last_draw.set(Some(Instant::now()));
+                let end = if p.finished { "\n" } else { "" };
+                // Clears what is left of a longer previous line.
+                eprint!("\r{}\x1b[K{end}", self.line(&p));
+                let _ = io::stderr().flush();
+            }
+        } else {
+            let tenth = (p.fraction() * 10.0) as u64;
+            if tenth > self.last_tenth.get() || p.finished {
+                self.last_tenth.set(tenth);
+                eprintln!("{}", self.line(&p));
+            }
+        }
+    }
+
+    fn line(&self, p: &RenderProgress) -> String {
+        let filled = ((p.fraction() * self.width as f64) as usize).min(self.width);
+        let eta = match p.eta() {
+            Some(eta) if !p.finished => format!("  ETA {}", format_duration(eta)),
+            _ => String::new(),
+        };
+        format!(
+            "[{}{}] {:5.1}%  {}{eta}  {} samples/s",
+            "#".repeat(filled),
+            "-".repeat(self.width - filled),
+            100.0 * p.fraction(),
+            format_duration(p.elapsed),
+            format_rate(p.samples_per_second()),
+        )
+    }
+}
+
+/// `m:ss`, or `h:mm:ss` from an hour on.
+fn format_duration(d: Duration) -> String {
+    let s = d.as_secs();
+    if s >= 3600 {
+        format!("{}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
+    } else {
+        format!("{}:{:02}", s / 60, s % 60)
+    }
+}
+
+fn format_rate(rate: f64) -> String {
+    if rate >= 1e6 {
+        format!("{:.2}M", rate / 1e6)
+    } else if rate >= 1e3 {
+        format!("{:.1}k", rate / 1e3)
+    } else {
+        format!("{rate:.0}")
+    }
+}
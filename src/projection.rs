@@ -0,0 +1,99 @@
+use crate::{deg2rad, Vec3, PI};
+
+/// How image positions map to camera rays. All models share the camera's
+/// `lookfrom`/`lookat`/`vup` framing; only `Perspective` uses the thin lens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective,
+    /// Parallel rays along the view direction through a viewport `height`
+    /// scene units tall, centered on `lookfrom`.
+    Orthographic {
+        height: f64,
+    },
+    /// Equidistant fisheye: the angle from the view direction grows linearly
+    /// with distance from the image center, reaching `fov / 2` (degrees) on
+    /// the circle inscribed in the image.
+    Fisheye {
+        fov: f64,
+    },
+    /// Full 360 x 180 degree latitude/longitude panorama, view direction in
+    /// the middle. Use a 2:1 image.
+    Equirectangular,
+    /// Six 90 degree faces in a 3 x 2 grid: front, right, back on the top
+    /// row and left, up, down below. Use a 3:2 image.
+    CubeMap,
+}
+
+/// Camera frame: `forward` is the view direction, `right`/`up` span the image.
+pub struct Frame {
+    pub forward: Vec3,
+    pub right: Vec3,
+    pub up: Vec3,
+}
+
+impl Projection {
+    pub fn from(name: &str, ortho_height: f64, fisheye_fov: f64) -> Option<Projection> {
+        match name {
+            "perspective" => Some(Projection::Perspective),
+            "orthographic" => Some(Projection::Orthographic {
+                height: ortho_height,
+            }),
+            "fisheye" => Some(Projection::Fisheye { fov: fisheye_fov }),
+            "equirect" => Some(Projection::Equirectangular),
+            "cubemap" => Some(Projection::CubeMap),
+            _ => None,
+        }
+    }
+
+    /// Ray direction for the panoramic models given the image position
+    /// `(sx, sy)` in `[0, 1)`, with `sy` growing downwards. `None` means the
+    /// position lies outside the projection (the corners of a fisheye).
+    pub fn direction(&self, frame: &Frame, sx: f64, sy: f64, aspect: f64) -> Option<Vec3> {
+        match *self {
+            Projection::Fisheye { fov } => {
+                // Normalize so the inscribed circle has radius 1.
+                let (mut x, mut y) = (2.0 * sx - 1.0, 1.0 - 2.0 * sy);
+                if aspect > 1.0 {
+                    x *= aspect;
+                } else {
+                    y /= aspect;
+                }
+                let r = (x * x + y * y).sqrt();
+                if r > 1.0 {
+                    return None;
+                }
+                let theta = r * deg2rad(fov) / 2.0;
+                let (cos_phi, sin_phi) = if r > 0.0 { (x / r, y / r) } else { (1.0, 0.0) };
+                Some(
+                    theta.cos() * frame.forward
+                        + theta.sin() * (cos_phi * frame.right + sin_phi * frame.up),
+                )
+            }
+            Projection::Equirectangular => {
+                let phi = 2.0 * PI * (sx - 0.5);
+                let theta = PI * sy;
+                Some(
+                    theta.sin() * (phi.cos() * frame.forward + phi.sin() * frame.right)
+                        + theta.cos() * frame.up,
+                )
+            }
+            Projection::CubeMap => {
+                let col = (sx * 3.0).floor().min(2.0);
+                let row = (sy * 2.0).floor().min(1.0);
+                let a = 2.0 * (sx * 3.0 - col) - 1.0;
+                let b = 1.0 - 2.0 * (sy * 2.0 - row);
+                let (f, r, u) = (frame.forward, frame.right, frame.up);
+                let (face, right, up) = match (row as u32, col as u32) {
+                    (0, 0) => (f, r, u),
+                    (0, 1) => (r, -f, u),
+                    (0, _) => (-f, -r, u),
+                    (_, 0) => (-r, f, u),
+                    (_, 1) => (u, r, -f),
+                    _ => (-u, r, f),
+                };
+                Some(face + a * right + b * up)
+            }
+            Projection::Perspective | Projection::Orthographic { .. } => None,
+        }
+    }
+}
@@ -9,7 +9,7 @@ pub struct Ray {
 impl Ray {
     pub fn from(orig: Point3, dir: Vec3) -> Ray {
         Ray {
-            orig: orig,
+            orig,
             direction: dir,
         }
     }
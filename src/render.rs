@@ -0,0 +1,214 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::{Aovs, Camera, Checkpoint, Image, RenderCounters, RenderStats, Scene};
+
+/// Snapshot passed to the progress callback after each finished block of
+/// work. Counts are in camera samples, so they stay meaningful whatever
+/// order rows or tiles complete in.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderProgress {
+    pub samples_done: u64,
+    pub samples_total: u64,
+    /// Wall-clock time since the render started.
+    pub elapsed: Duration,
+    /// Set on the last report of a render, complete or cancelled.
+    pub finished: bool,
+}
+
+impl RenderProgress {
+    /// Share of the samples done, in `[0, 1]`.
+    pub fn fraction(&self) -> f64 {
+        if self.samples_total == 0 {
+            return 1.0;
+        }
+        self.samples_done as f64 / self.samples_total as f64
+    }
+
+    pub fn samples_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.samples_done as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Remaining time at the throughput so far; `None` before any sample
+    /// is done.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.samples_done == 0 {
+            return None;
+        }
+        let rest = self.samples_total.saturating_sub(self.samples_done) as f64;
+        Some(self.elapsed.mul_f64(rest / self.samples_done as f64))
+    }
+}
+
+/// Receives the render state for `RenderSettings::with_checkpoint`.
+type CheckpointSaver = Box<dyn Fn(&Checkpoint)>;
+
+/// Receives the messages for `RenderSettings::with_warnings`.
+type WarningSink = Box<dyn Fn(&str)>;
+
+/// Host-side controls for a render: progress reporting, cancellation,
+/// statistics and checkpoints.
+#[derive(Default)]
+pub struct RenderSettings {
+    progress: Option<Box<dyn Fn(RenderProgress)>>,
+    cancel: Option<Arc<AtomicBool>>,
+    stats: Option<Rc<RefCell<RenderStats>>>,
+    checkpoint: Option<CheckpointSaver>,
+    checkpoint_interval: Duration,
+    resume: Option<Checkpoint>,
+    warn: Option<WarningSink>,
+}
+
+impl RenderSettings {
+    pub fn new() -> RenderSettings {
+        RenderSettings {
+            progress: None,
+            cancel: None,
+            stats: None,
+            checkpoint: None,
+            checkpoint_interval: Duration::ZERO,
+            resume: None,
+            warn: None,
+        }
+    }
+
+    /// Calls `progress` as the render advances; `ProgressBar` draws it on
+    /// the terminal.
+    pub fn with_progress(mut self, progress: impl Fn(RenderProgress) + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Calls `warn` with problems the render works around, such as a
+    /// worker that failed and whose jobs went to the others.
+    pub fn with_warnings(mut self, warn: impl Fn(&str) + 'static) -> Self {
+        self.warn = Some(Box::new(warn));
+        self
+    }
+
+    /// Rendering stops at the next row boundary once `cancel` is set.
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Each render adds its ray counters and a "render" phase to `stats`.
+    pub fn with_stats(mut self, stats: Rc<RefCell<RenderStats>>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Hands the render state to `save` at the first row boundary after
+    /// every `interval`, and once more when the render ends, finished or
+    /// cancelled. Only `render` checkpoints; `render_aovs` does not.
+    pub fn with_checkpoint(
+        mut self,
+        interval: Duration,
+        save: impl Fn(&Checkpoint) + 'static,
+    ) -> Self {
+        self.checkpoint = Some(Box::new(save));
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    /// Continues from `checkpoint` up to the camera's samples per pixel
+    /// instead of starting from black. `render` fails if the checkpoint
+    /// does not match the camera, see `Camera::check_checkpoint`, and
+    /// `render_aovs` always fails, as AOVs are not checkpointed.
+    pub fn with_resume(mut self, checkpoint: Checkpoint) -> Self {
+        self.resume = Some(checkpoint);
+        self
+    }
+
+    pub fn resume(&self) -> Option<&Checkpoint> {
+        self.resume.as_ref()
+    }
+
+    /// Passes `checkpoint` to the saver if `last` is an interval ago, or
+    /// always with `force`, and returns when it was last saved.
+    pub fn save_checkpoint(&self, checkpoint: &Checkpoint, last: Instant, force: bool) -> Instant {
+        match &self.checkpoint {
+            Some(save) if force || last.elapsed() >= self.checkpoint_interval => {
+                save(checkpoint);
+                Instant::now()
+            }
+            _ => last,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|c| c.load(Ordering::Relaxed))
+    }
+
+    pub fn warn(&self, message: &str) {
+        if let Some(f) = &self.warn {
+            f(message);
+        }
+    }
+
+    pub fn report(&self, progress: RenderProgress) {
+        if let Some(f) = &self.progress {
+            f(progress);
+        }
+    }
+
+    pub fn record(&self, counters: RenderCounters, elapsed: Duration) {
+        if let Some(stats) = &self.stats {
+            let mut stats = stats.borrow_mut();
+            stats.counters += counters;
+            stats.add_phase("render", elapsed);
+        }
+    }
+}
+
+/// Why a render could not start.
+#[derive(Debug)]
+pub enum RenderError {
+    /// The checkpoint to resume does not match the camera; holds the
+    /// reason from `Camera::check_checkpoint`.
+    Checkpoint(String),
+    /// `render_aovs` was asked to resume a checkpoint.
+    ResumeAovs,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderError::Checkpoint(e) => write!(f, "cannot resume: {e}"),
+            RenderError::ResumeAovs => write!(f, "AOV renders cannot resume a checkpoint"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// Renders `scene` through `camera` into a linear float RGB image, before
+/// any exposure or tone mapping. A cancelled render returns the rows
+/// finished so far, the rest left black.
+pub fn render(
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+) -> Result<Image, RenderError> {
+    camera.render(scene, settings)
+}
+
+/// Like `render`, but also returns the auxiliary passes listed in `Aovs`.
+pub fn render_aovs(
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+) -> Result<(Image, Aovs), RenderError> {
+    camera.render_aovs(scene, settings)
+}
@@ -0,0 +1,156 @@
+use crate::PI;
+
+/// Real roots of `a x² + b x + c`, ascending. Falls back to the linear
+/// equation when `a` vanishes.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < 1e-12 {
+        if b.abs() < 1e-12 {
+            return Vec::new();
+        }
+        return vec![-c / b];
+    }
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return Vec::new();
+    }
+    // Avoids cancellation between `-b` and the square root.
+    let q = -0.5 * (b + b.signum() * disc.sqrt());
+    if q == 0.0 {
+        return vec![0.0, 0.0];
+    }
+    let (x0, x1) = (q / a, c / q);
+    if x0 < x1 {
+        vec![x0, x1]
+    } else {
+        vec![x1, x0]
+    }
+}
+
+/// Real roots of the monic cubic `x³ + a x² + b x + c`, ascending.
+pub fn solve_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (2.0 * a * a * a - 9.0 * a * b + 27.0 * c) / 54.0;
+    let q3 = q * q * q;
+    if r * r < q3 {
+        let theta = (r / q3.sqrt()).clamp(-1.0, 1.0).acos();
+        let m = -2.0 * q.sqrt();
+        let mut roots: Vec<f64> = (0..3)
+            .map(|k| m * ((theta + 2.0 * PI * k as f64) / 3.0).cos() - a / 3.0)
+            .collect();
+        roots.sort_by(f64::total_cmp);
+        return roots;
+    }
+    let big_a = -r.signum() * (r.abs() + (r * r - q3).sqrt()).cbrt();
+    let big_b = if big_a == 0.0 { 0.0 } else { q / big_a };
+    vec![big_a + big_b - a / 3.0]
+}
+
+/// Real roots of the monic quartic `x⁴ + a x³ + b x² + c x + d`, ascending.
+/// Uses Ferrari's method and polishes every root with Newton steps on the
+/// original polynomial, which the closed form alone is too inaccurate for.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    // Depressed quartic y⁴ + p y² + q y + r with x = y - a / 4.
+    let a2 = a * a;
+    let p = b - 3.0 * a2 / 8.0;
+    let q = c - a * b / 2.0 + a2 * a / 8.0;
+    let r = d - a * c / 4.0 + a2 * b / 16.0 - 3.0 * a2 * a2 / 256.0;
+
+    let mut ys = Vec::new();
+    if q.abs() < 1e-12 {
+        // Biquadratic: solve for y².
+        for z in solve_quadratic(1.0, p, r) {
+            if z >= 0.0 {
+                ys.push(z.sqrt());
+                ys.push(-z.sqrt());
+            }
+        }
+    } else {
+        // Any positive root of the resolvent cubic splits the quartic into
+        // two quadratics.
+        let Some(m) = solve_cubic(p, p * p / 4.0 - r, -q * q / 8.0)
+            .into_iter()
+            .rev()
+            .find(|m| *m > 0.0)
+        else {
+            return Vec::new();
+        };
+        let s = (2.0 * m).sqrt();
+        ys.extend(solve_quadratic(1.0, -s, p / 2.0 + m + q / (2.0 * s)));
+        ys.extend(solve_quadratic(1.0, s, p / 2.0 + m - q / (2.0 * s)));
+    }
+
+    let mut roots: Vec<f64> = ys
+        .into_iter()
+        .map(|y| {
+            let mut x = y - a / 4.0;
+            for _ in 0..2 {
+                let f = (((x + a) * x + b) * x + c) * x + d;
+                let df = ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + c;
+                if df == 0.0 {
+                    break;
+                }
+                x -= f / df;
+            }
+            x
+        })
+        .collect();
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+/// Real roots inside `[lo, hi]` of the polynomial with coefficients
+/// `coeffs` (constant term first), ascending. Roots of the derivative,
+/// found recursively, split the range into monotone pieces, and each piece
+/// with a sign change is bisected, so no simple root is missed.
+pub fn polynomial_roots(coeffs: &[f64], lo: f64, hi: f64) -> Vec<f64> {
+    let degree = coeffs.iter().rposition(|c| *c != 0.0).unwrap_or(0);
+    let coeffs = &coeffs[..=degree];
+    if degree == 0 {
+        return Vec::new();
+    }
+    if degree == 1 {
+        let x = -coeffs[0] / coeffs[1];
+        return if (lo..=hi).contains(&x) {
+            vec![x]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let derivative: Vec<f64> = (1..=degree).map(|k| k as f64 * coeffs[k]).collect();
+    let mut bounds = vec![lo];
+    bounds.extend(polynomial_roots(&derivative, lo, hi));
+    bounds.push(hi);
+
+    let eval = |x: f64| coeffs.iter().rev().fold(0.0, |acc, c| acc * x + c);
+    let mut roots = Vec::new();
+    if eval(lo) == 0.0 {
+        roots.push(lo);
+    }
+    for w in bounds.windows(2) {
+        let (mut a, mut b) = (w[0], w[1]);
+        let (fa, fb) = (eval(a), eval(b));
+        if fb == 0.0 {
+            if roots.last() != Some(&b) {
+                roots.push(b);
+            }
+            continue;
+        }
+        if fa == 0.0 || fa.signum() == fb.signum() {
+            continue;
+        }
+        for _ in 0..64 {
+            let m = 0.5 * (a + b);
+            if m <= a || m >= b {
+                break;
+            }
+            if eval(m).signum() == fa.signum() {
+                a = m;
+            } else {
+                b = m;
+            }
+        }
+        roots.push(0.5 * (a + b));
+    }
+    roots
+}
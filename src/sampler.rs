@@ -0,0 +1,390 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Largest `f64` below 1.0, so samples always land in `[0, 1)`.
+pub const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+/// Source of the random numbers behind every decision made for one camera
+/// sample: pixel position, lens position and each scattering event.
+///
+/// `start_pixel_sample` positions the sampler at sample `index` of `pixel`;
+/// each following `get_1d`/`get_2d` call consumes the next dimension(s) of
+/// that sample's point. Results only depend on `(pixel, index, dimension)`,
+/// so renders are reproducible and can be resumed sample by sample.
+pub trait Sampler {
+    fn start_pixel_sample(&mut self, pixel: (u64, u64), index: u64);
+    fn get_1d(&mut self) -> f64;
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+/// Sampler implementations selectable on the camera.
+#[derive(Debug, Clone, Copy)]
+pub enum SamplerKind {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerKind {
+    pub fn from(name: &str) -> Option<SamplerKind> {
+        match name {
+            "independent" => Some(SamplerKind::Independent),
+            "stratified" => Some(SamplerKind::Stratified),
+            "halton" => Some(SamplerKind::Halton),
+            "sobol" => Some(SamplerKind::Sobol),
+            _ => None,
+        }
+    }
+
+    /// Inverse of `SamplerKind::from`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SamplerKind::Independent => "independent",
+            SamplerKind::Stratified => "stratified",
+            SamplerKind::Halton => "halton",
+            SamplerKind::Sobol => "sobol",
+        }
+    }
+
+    pub fn build(&self, samples_per_pixel: u64, seed: u64) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(samples_per_pixel, seed)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(samples_per_pixel, seed)),
+        }
+    }
+}
+
+/// Uniform random samples with no stratification.
+pub struct IndependentSampler {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> IndependentSampler {
+        IndependentSampler {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, pixel: (u64, u64), index: u64) {
+        self.rng = StdRng::seed_from_u64(hash(&[pixel.0, pixel.1, index, self.seed]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.gen::<f64>().min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+/// Jittered sampling: each dimension is split into one stratum per sample
+/// and every pixel sample visits a different stratum, in an order shuffled
+/// independently per dimension. In 2D the strata form the largest
+/// near-square grid with at most one cell per sample; when the sample count
+/// is not a product of its sides, the samples left over after every cell
+/// is visited are uniform.
+pub struct StratifiedSampler {
+    samples_per_pixel: u64,
+    grid: (u64, u64),
+    seed: u64,
+    pixel: (u64, u64),
+    index: u64,
+    dimension: u64,
+    rng: StdRng,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: u64, seed: u64) -> StratifiedSampler {
+        let spp = samples_per_pixel.max(1);
+        let nx = ((spp as f64).sqrt() as u64).max(1);
+        let ny = spp / nx;
+        StratifiedSampler {
+            samples_per_pixel: spp,
+            grid: (nx, ny),
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    fn stratum(&mut self) -> u64 {
+        let h = hash(&[self.pixel.0, self.pixel.1, self.dimension, self.seed]);
+        self.dimension += 1;
+        let spp = self.samples_per_pixel;
+        permutation_element(self.index % spp, spp, h)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, pixel: (u64, u64), index: u64) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dimension = 0;
+        self.rng = StdRng::seed_from_u64(hash(&[pixel.0, pixel.1, index, self.seed]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let stratum = self.stratum();
+        let jitter: f64 = self.rng.gen();
+        ((stratum as f64 + jitter) / self.samples_per_pixel as f64).min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let stratum = self.stratum();
+        let (nx, ny) = self.grid;
+        let (jx, jy): (f64, f64) = (self.rng.gen(), self.rng.gen());
+        if stratum >= nx * ny {
+            return (jx.min(ONE_MINUS_EPSILON), jy.min(ONE_MINUS_EPSILON));
+        }
+        (
+            (((stratum % nx) as f64 + jx) / nx as f64).min(ONE_MINUS_EPSILON),
+            (((stratum / nx) as f64 + jy) / ny as f64).min(ONE_MINUS_EPSILON),
+        )
+    }
+}
+
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Halton sequence with per-pixel Owen scrambling of the radical inverse.
+/// Dimensions past the prime table fall back to uniform random numbers.
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: (u64, u64),
+    index: u64,
+    dimension: u64,
+    rng: StdRng,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> HaltonSampler {
+        HaltonSampler {
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, pixel: (u64, u64), index: u64) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dimension = 0;
+        self.rng = StdRng::seed_from_u64(hash(&[pixel.0, pixel.1, index, self.seed]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dim = self.dimension as usize;
+        self.dimension += 1;
+        if dim >= PRIMES.len() {
+            return self.rng.gen::<f64>().min(ONE_MINUS_EPSILON);
+        }
+        let h = hash(&[self.pixel.0, self.pixel.1, dim as u64, self.seed]);
+        owen_scrambled_radical_inverse(PRIMES[dim], self.index, h)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+/// Owen-scrambled Sobol points. Every 1D/2D request is drawn from the first
+/// two Sobol dimensions with its own scramble and its own shuffle of the
+/// sample index ("padding"), which keeps the well-stratified low
+/// dimensions for all path vertices.
+pub struct SobolSampler {
+    samples_per_pixel: u64,
+    seed: u64,
+    pixel: (u64, u64),
+    index: u64,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(samples_per_pixel: u64, seed: u64) -> SobolSampler {
+        SobolSampler {
+            samples_per_pixel: samples_per_pixel.max(1),
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn padded_index(&mut self) -> (u32, u64) {
+        let h = hash(&[self.pixel.0, self.pixel.1, self.dimension, self.seed]);
+        self.dimension += 1;
+        let spp = self.samples_per_pixel;
+        let index = permutation_element(self.index % spp, spp, h);
+        (index as u32, mix_bits(h))
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, pixel: (u64, u64), index: u64) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (index, h) = self.padded_index();
+        to_unit(owen_scramble(sobol_dim0(index), h as u32))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (index, h) = self.padded_index();
+        (
+            to_unit(owen_scramble(sobol_dim0(index), h as u32)),
+            to_unit(owen_scramble(sobol_dim1(index), (h >> 32) as u32)),
+        )
+    }
+}
+
+#[inline(always)]
+fn to_unit(v: u32) -> f64 {
+    (v as f64 / 4294967296.0).min(ONE_MINUS_EPSILON)
+}
+
+/// First Sobol dimension: the base-2 van der Corput sequence.
+fn sobol_dim0(index: u32) -> u32 {
+    index.reverse_bits()
+}
+
+/// Second Sobol dimension, generated by the Pascal matrix mod 2.
+fn sobol_dim1(mut index: u32) -> u32 {
+    let mut v = 1u32 << 31;
+    let mut result = 0;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+/// Laine–Karras style hash-based Owen scrambling of a 32-bit fixed point value.
+fn owen_scramble(v: u32, seed: u32) -> u32 {
+    let mut v = v.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x05526c56);
+    v ^= v.wrapping_mul(0x53a22864);
+    v.reverse_bits()
+}
+
+fn owen_scrambled_radical_inverse(base: u64, mut a: u64, h: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let limit = u64::MAX / base - base;
+    let mut inv_base_m = 1.0;
+    let mut reversed_digits: u64 = 0;
+    while 1.0 - (base as f64 - 1.0) * inv_base_m < 1.0 && reversed_digits < limit {
+        let next = a / base;
+        let digit = a - next * base;
+        let digit_hash = mix_bits(h ^ reversed_digits);
+        let digit = permutation_element(digit, base, digit_hash);
+        reversed_digits = reversed_digits * base + digit;
+        inv_base_m *= inv_base;
+        a = next;
+    }
+    (inv_base_m * reversed_digits as f64).min(ONE_MINUS_EPSILON)
+}
+
+/// Element `i` of a pseudo-random permutation of `0..l` selected by `p`
+/// (Kensler, "Correlated Multi-Jittered Sampling").
+fn permutation_element(i: u64, l: u64, p: u64) -> u64 {
+    let (mut i, l, p) = (i as u32, l as u32, p as u32);
+    let mut w = l.wrapping_sub(1);
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < l {
+            break;
+        }
+    }
+    (i.wrapping_add(p) % l) as u64
+}
+
+#[inline(always)]
+pub fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5d329728ea185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81dadef4bc2dd44d);
+    v ^= v >> 33;
+    v
+}
+
+pub fn hash(values: &[u64]) -> u64 {
+    values
+        .iter()
+        .fold(0x9e3779b97f4a7c15, |acc, v| mix_bits(acc ^ mix_bits(*v)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stratified_samples_visit_every_cell_once() {
+        for spp in 1..=50 {
+            let mut sampler = StratifiedSampler::new(spp, 7);
+            let (nx, ny) = sampler.grid;
+            assert!(nx * ny <= spp);
+            for dimension in 0..3 {
+                let mut hits = vec![0; (nx * ny) as usize];
+                for index in 0..spp {
+                    sampler.start_pixel_sample((3, 5), index);
+                    for _ in 0..dimension {
+                        sampler.get_2d();
+                    }
+                    let (x, y) = sampler.get_2d();
+                    let cell = (y * ny as f64) as u64 * nx + (x * nx as f64) as u64;
+                    hits[cell as usize] += 1;
+                }
+                // Leftover uniform samples may land anywhere.
+                assert!(hits.iter().all(|h| *h >= 1), "spp {spp}: {hits:?}");
+                assert_eq!(hits.iter().sum::<u64>(), spp);
+            }
+        }
+    }
+}
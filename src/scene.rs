@@ -0,0 +1,621 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    unit_vector, Aabb, BumpMap, CameraBuilder, Capsule, Color3, Cone, Csg, CsgOp, Cylinder,
+    Diaelectric, DirectionalLight, Ellipsoid, HeightField, Hittable, HittableList, Lambertian,
+    LightList, List, Material, Mesh, Metaballs, Metal, NoiseTexture, NormalMap, OpacityMask,
+    Point3, PointLight, SdfBox, SdfHittable, SdfRepeat, SdfRoundBox, SdfSmoothSubtraction,
+    SdfSmoothUnion, SdfSphere, SdfTorus, SdfTranslate, SdfTwist, Sphere, SpotLight, Subdivision,
+    Torus, TriangleMesh, Vec3, PI,
+};
+
+/// A scene together with camera settings that frame it.
+pub struct SceneSetup {
+    pub scene: Scene,
+    pub camera: CameraBuilder,
+}
+
+/// Everything `render` needs besides the camera.
+pub struct Scene {
+    pub world: HittableList,
+    pub lights: LightList,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene {
+            world: HittableList::new(),
+            lights: LightList::new(),
+        }
+    }
+}
+
+pub fn create_3_scene(world: &mut (impl Hittable + List)) {
+    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.8, 0.8, 0.0))));
+    let center_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.1, 0.2, 0.5))));
+    // let left_mat = Rc::new(RefCell::new(Metal::from(Color3::from(0.8, 0.8, 0.8), 0.3)));
+    let left_mat = Rc::new(RefCell::new(Diaelectric::from(1.50)));
+    let right_mat = Rc::new(RefCell::new(Metal::from(Color3::from(0.8, 0.6, 0.2), 1.0)));
+
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(0.0, -100.5, -1.0),
+        100.0,
+        ground_mat,
+    ))));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(0.0, 0.0, -1.2),
+        0.5,
+        center_mat,
+    ))));
+
+    // Hollow glass ball: a thick shell carved out with CSG.
+    world.add(Rc::new(RefCell::new(Csg::new(
+        CsgOp::Difference,
+        Rc::new(RefCell::new(Sphere::new(
+            Point3::from(-1.0, 0.0, -1.0),
+            0.5,
+            left_mat.clone(),
+        ))),
+        Rc::new(RefCell::new(Sphere::new(
+            Point3::from(-1.0, 0.0, -1.0),
+            0.4,
+            left_mat,
+        ))),
+    ))));
+
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(1.0, 0.0, -1.0),
+        0.5,
+        right_mat,
+    ))));
+}
+
+pub fn create_lights_scene(world: &mut (impl Hittable + List), lights: &mut LightList) {
+    create_3_scene(world);
+    lights.add(Rc::new(PointLight::new(
+        Point3::from(0.0, 2.0, 0.0),
+        Color3::from(2.0, 2.0, 2.0),
+    )));
+    lights.add(Rc::new(SpotLight::new(
+        Point3::from(-2.0, 2.0, 0.5),
+        Point3::from(-1.0, 0.0, -1.0),
+        Color3::from(6.0, 5.0, 4.0),
+        30.0,
+        20.0,
+    )));
+    lights.add(Rc::new(DirectionalLight::new(
+        Vec3::from(1.0, -1.0, -0.5),
+        Color3::from(0.5, 0.5, 0.45),
+    )));
+}
+
+pub fn create_fov_scene(world: &mut (impl Hittable + List)) {
+    let r = f64::cos(PI / 4.0);
+    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(1.0, 0.0, 0.0))));
+    let center_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.0, 1.0, 0.0))));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(-r, 0.0, -1.0),
+        r,
+        ground_mat,
+    ))));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(r, 0.0, -1.0),
+        r,
+        center_mat,
+    ))));
+}
+
+/// The cover scene of "Ray Tracing in One Weekend", laid out anew on every
+/// call.
+pub fn create_final_scene(world: &mut (impl Hittable + List)) {
+    create_random_spheres_scene(world, rand::thread_rng().gen());
+}
+
+/// Small spheres with random materials scattered on a grid around three
+/// large ones, seen from (13, 2, 3). The same `seed` gives the same layout.
+pub fn create_random_spheres_scene(world: &mut (impl Hittable + List), seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.8, 0.8, 0.0))));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(0.0, -1000.0, -0.0),
+        1000.0,
+        ground_mat,
+    ))));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let center = Vec3::from(
+                a as f64 + 0.9 * rng.gen::<f64>(),
+                0.2,
+                b as f64 + 0.9 * rng.gen::<f64>(),
+            );
+            let prob: f64 = rng.gen();
+
+            if (center - Point3::from(4.0, 0.2, 0.0)).length() > 0.9 {
+                if prob < 0.8 {
+                    let albedo = Color3::from(rng.gen(), rng.gen(), rng.gen());
+                    let lamb_mat = Rc::new(RefCell::new(Lambertian::from(albedo)));
+                    world.add(Rc::new(RefCell::new(Sphere::new(center, 0.2, lamb_mat))));
+                } else if prob < 0.95 {
+                    let albedo = Color3::from(
+                        rng.gen_range(0.5..1.0),
+                        rng.gen_range(0.5..1.0),
+                        rng.gen_range(0.5..1.0),
+                    );
+                    let metal_mat =
+                        Rc::new(RefCell::new(Metal::from(albedo, rng.gen_range(0.0..0.5))));
+                    world.add(Rc::new(RefCell::new(Sphere::new(center, 0.2, metal_mat))));
+                } else {
+                    let dia_mat = Rc::new(RefCell::new(Diaelectric::from(1.50)));
+                    world.add(Rc::new(RefCell::new(Sphere::new(center, 0.2, dia_mat))));
+                }
+            }
+        }
+    }
+
+    let mat1 = Rc::new(RefCell::new(Diaelectric::from(1.50)));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(0.0, 1.0, 0.0),
+        1.0,
+        mat1,
+    ))));
+
+    let mat2 = Rc::new(RefCell::new(Lambertian::from(Vec3::from(0.4, 0.2, 0.1))));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(-4.0, 1.0, 0.0),
+        1.0,
+        mat2,
+    ))));
+
+    let mat3 = Rc::new(RefCell::new(Metal::from(Vec3::from(0.7, 0.6, 0.5), 0.0)));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(4.0, 1.0, 0.0),
+        1.0,
+        mat3,
+    ))));
+}
+
+/// One of each analytic primitive on a ground plane, seen from (0, 2, 6).
+pub fn create_quadrics_scene(world: &mut (impl Hittable + List)) {
+    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.5, 0.5, 0.5))));
+    let red = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.7, 0.2, 0.2))));
+    let blue = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.2, 0.3, 0.7))));
+    let steel = Rc::new(RefCell::new(Metal::from(Color3::from(0.8, 0.8, 0.85), 0.1)));
+    let gold = Rc::new(RefCell::new(Metal::from(Color3::from(0.8, 0.6, 0.2), 0.3)));
+    let glass = Rc::new(RefCell::new(Diaelectric::from(1.50)));
+
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(0.0, -1000.0, 0.0),
+        1000.0,
+        ground_mat,
+    ))));
+    world.add(Rc::new(RefCell::new(
+        Cylinder::new(
+            Point3::from(-3.0, 0.0, 0.0),
+            Point3::from(-3.0, 1.5, 0.0),
+            0.5,
+            true,
+            red,
+        )
+        .expect("the cylinder has a height"),
+    )));
+    world.add(Rc::new(RefCell::new(
+        Cone::new(
+            Point3::from(-1.5, 0.0, -1.0),
+            Point3::from(-1.5, 1.5, -1.0),
+            0.6,
+            0.0,
+            true,
+            blue,
+        )
+        .expect("the cone has a height"),
+    )));
+    world.add(Rc::new(RefCell::new(Torus::new(
+        Point3::from(0.0, 0.6, 0.0),
+        Vec3::from(0.0, 1.0, 1.0),
+        0.7,
+        0.25,
+        gold,
+    ))));
+    world.add(Rc::new(RefCell::new(Capsule::new(
+        Point3::from(1.5, 0.4, 1.0),
+        Point3::from(2.5, 1.2, 0.5),
+        0.35,
+        steel,
+    ))));
+    world.add(Rc::new(RefCell::new(Ellipsoid::new(
+        Point3::from(3.0, 0.5, -1.0),
+        Vec3::from(0.8, 0.5, 0.5),
+        glass,
+    ))));
+}
+
+/// Distance-field shapes built from the `Sdf` blocks, seen from (0, 2, 6).
+pub fn create_sdf_scene(world: &mut (impl Hittable + List)) {
+    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.5, 0.5, 0.5))));
+    let pink = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.8, 0.4, 0.5))));
+    let teal = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.2, 0.6, 0.6))));
+    let steel = Rc::new(RefCell::new(Metal::from(Color3::from(0.8, 0.8, 0.85), 0.2)));
+    let glass = Rc::new(RefCell::new(Diaelectric::from(1.50)));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(0.0, -1000.0, 0.0),
+        1000.0,
+        ground_mat,
+    ))));
+
+    // Two spheres melted together.
+    let blob = SdfSmoothUnion {
+        a: Rc::new(SdfTranslate {
+            offset: Vec3::from(-0.3, 0.0, 0.0),
+            sdf: Rc::new(SdfSphere { radius: 0.5 }),
+        }),
+        b: Rc::new(SdfTranslate {
+            offset: Vec3::from(0.35, 0.3, 0.0),
+            sdf: Rc::new(SdfSphere { radius: 0.4 }),
+        }),
+        k: 0.3,
+    };
+    world.add(Rc::new(RefCell::new(SdfHittable::new(
+        Rc::new(SdfTranslate {
+            offset: Vec3::from(-2.5, 0.55, 0.0),
+            sdf: Rc::new(blob),
+        }),
+        Aabb::from(Point3::from(-3.4, 0.0, -0.6), Point3::from(-1.6, 1.3, 0.6)),
+        pink,
+    ))));
+
+    // A twisted rounded column.
+    let column = SdfTwist {
+        rate: 1.5,
+        sdf: Rc::new(SdfRoundBox {
+            half: Vec3::from(0.35, 0.8, 0.35),
+            radius: 0.08,
+        }),
+    };
+    world.add(Rc::new(RefCell::new(
+        SdfHittable::new(
+            Rc::new(SdfTranslate {
+                offset: Vec3::from(-0.8, 0.8, -0.5),
+                sdf: Rc::new(column),
+            }),
+            Aabb::from(Point3::from(-1.4, 0.0, -1.1), Point3::from(-0.2, 1.6, 0.1)),
+            teal,
+        )
+        .with_step_scale(0.5),
+    )));
+
+    // A box hollowed out by a sphere, next to a glass torus.
+    let cut = SdfSmoothSubtraction {
+        a: Rc::new(SdfBox {
+            half: Vec3::from(0.5, 0.5, 0.5),
+        }),
+        b: Rc::new(SdfTranslate {
+            offset: Vec3::from(0.0, 0.3, 0.3),
+            sdf: Rc::new(SdfSphere { radius: 0.55 }),
+        }),
+        k: 0.05,
+    };
+    world.add(Rc::new(RefCell::new(SdfHittable::new(
+        Rc::new(SdfTranslate {
+            offset: Vec3::from(0.8, 0.5, 0.0),
+            sdf: Rc::new(cut),
+        }),
+        Aabb::from(Point3::from(0.2, 0.0, -0.6), Point3::from(1.4, 1.1, 0.6)),
+        steel,
+    ))));
+    world.add(Rc::new(RefCell::new(SdfHittable::new(
+        Rc::new(SdfTranslate {
+            offset: Vec3::from(2.5, 0.25, 0.5),
+            sdf: Rc::new(SdfTorus {
+                major: 0.5,
+                minor: 0.2,
+            }),
+        }),
+        Aabb::from(Point3::from(1.7, 0.0, -0.3), Point3::from(3.3, 0.5, 1.3)),
+        glass,
+    ))));
+
+    // A row of small spheres from a single repeated one.
+    let balls = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.9, 0.7, 0.2))));
+    world.add(Rc::new(RefCell::new(SdfHittable::new(
+        Rc::new(SdfTranslate {
+            offset: Vec3::from(0.0, 0.15, 1.5),
+            sdf: Rc::new(SdfRepeat {
+                spacing: Vec3::from(0.5, 0.0, 0.0),
+                sdf: Rc::new(SdfSphere { radius: 0.15 }),
+            }),
+        }),
+        Aabb::from(
+            Point3::from(-2.75, 0.0, 1.35),
+            Point3::from(2.75, 0.3, 1.65),
+        ),
+        balls,
+    ))));
+}
+
+/// Noise terrain with a cluster of metaballs floating above it, seen from
+/// (0, 3, 7).
+pub fn create_terrain_scene(world: &mut (impl Hittable + List)) {
+    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.4, 0.5, 0.3))));
+    let terrain = HeightField::from_noise(
+        128,
+        128,
+        4.0,
+        6,
+        7,
+        Point3::from(-6.0, -0.5, -8.0),
+        Vec3::from(12.0, 1.5, 12.0),
+        ground_mat,
+    )
+    .expect("the terrain grid is 128x128");
+    world.add(Rc::new(RefCell::new(terrain)));
+
+    let blob_mat = Rc::new(RefCell::new(Metal::from(Color3::from(0.8, 0.5, 0.4), 0.1)));
+    let mut blobs = Metaballs::new(0.25, blob_mat);
+    blobs.add(Point3::from(-0.6, 1.6, 0.0), 1.0);
+    blobs.add(Point3::from(0.4, 1.9, 0.2), 0.9);
+    blobs.add(Point3::from(0.2, 1.2, -0.4), 0.8);
+    blobs.add(Point3::from(1.3, 1.5, 0.0), 0.6);
+    world.add(Rc::new(RefCell::new(blobs)));
+}
+
+/// Low-poly meshes next to their subdivided versions, seen from (0, 2, 6):
+/// a cube under Catmull–Clark and an octahedron under Loop subdivision.
+pub fn create_subdivision_scene(world: &mut (impl Hittable + List)) {
+    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.5, 0.5, 0.5))));
+    let red: Rc<RefCell<dyn Material>> =
+        Rc::new(RefCell::new(Lambertian::from(Color3::from(0.7, 0.2, 0.2))));
+    let copper: Rc<RefCell<dyn Material>> =
+        Rc::new(RefCell::new(Metal::from(Color3::from(0.9, 0.6, 0.4), 0.05)));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(0.0, -1000.0, 0.0),
+        1000.0,
+        ground_mat,
+    ))));
+
+    let mut cube = Mesh::new();
+    for k in 0..8 {
+        cube.positions.push(Point3::from(
+            if k & 1 == 0 { -0.5 } else { 0.5 },
+            if k & 2 == 0 { -0.5 } else { 0.5 },
+            if k & 4 == 0 { -0.5 } else { 0.5 },
+        ));
+    }
+    cube.faces = vec![
+        vec![0, 2, 3, 1],
+        vec![4, 5, 7, 6],
+        vec![0, 1, 5, 4],
+        vec![2, 6, 7, 3],
+        vec![0, 4, 6, 2],
+        vec![1, 3, 7, 5],
+    ];
+
+    let mut octahedron = Mesh::new();
+    octahedron.positions = vec![
+        Point3::from(0.6, 0.0, 0.0),
+        Point3::from(-0.6, 0.0, 0.0),
+        Point3::from(0.0, 0.6, 0.0),
+        Point3::from(0.0, -0.6, 0.0),
+        Point3::from(0.0, 0.0, 0.6),
+        Point3::from(0.0, 0.0, -0.6),
+    ];
+    octahedron.faces = vec![
+        vec![0, 2, 4],
+        vec![4, 2, 1],
+        vec![1, 2, 5],
+        vec![5, 2, 0],
+        vec![4, 3, 0],
+        vec![1, 3, 4],
+        vec![5, 3, 1],
+        vec![0, 3, 5],
+    ];
+
+    let place = |mesh: &Mesh, offset: Vec3| {
+        let mut mesh = mesh.clone();
+        for p in mesh.positions.iter_mut() {
+            *p += offset;
+        }
+        mesh
+    };
+    let meshes = [
+        (place(&cube, Vec3::from(-2.4, 0.5, 0.0)), red.clone()),
+        (
+            place(&cube, Vec3::from(-0.8, 0.5, 0.0)).subdivide(Subdivision::CatmullClark, 3),
+            red,
+        ),
+        (
+            place(&octahedron, Vec3::from(0.8, 0.6, 0.0)),
+            copper.clone(),
+        ),
+        (
+            place(&octahedron, Vec3::from(2.4, 0.6, 0.0)).subdivide(Subdivision::Loop, 3),
+            copper,
+        ),
+    ];
+    for (mesh, mat) in meshes {
+        world.add(Rc::new(RefCell::new(TriangleMesh::new(&mesh, mat))));
+    }
+}
+
+/// Bump- and normal-mapped surfaces, seen from (0, 2, 6): procedural
+/// bricks, a noisy metal ball, a woven cloth ball and a bricked torus.
+pub fn create_bump_scene(world: &mut (impl Hittable + List)) {
+    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.5, 0.5, 0.5))));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(0.0, -1000.0, 0.0),
+        1000.0,
+        ground_mat,
+    ))));
+
+    // Raised bricks, 16 around and 8 high, every other row offset by half
+    // a brick, with sloped edges down into the mortar.
+    let bricks = |u: f64, v: f64, _p: Point3| {
+        let row = (v * 8.0).floor();
+        let x = (u * 16.0 + 0.5 * row).fract();
+        let y = (v * 8.0).fract();
+        let edge = |s: f64| (s.min(1.0 - s) / 0.08).clamp(0.0, 1.0);
+        let h = edge(x).min(edge(y));
+        Color3::from(h, h, h)
+    };
+    let brick_red: Rc<RefCell<dyn Material>> = Rc::new(RefCell::new(Lambertian::from(
+        Color3::from(0.6, 0.25, 0.15),
+    )));
+    let brick_mat = Rc::new(RefCell::new(BumpMap::from(
+        brick_red.clone(),
+        Rc::new(bricks),
+        0.02,
+    )));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(-2.2, 0.8, 0.0),
+        0.8,
+        brick_mat,
+    ))));
+
+    let steel: Rc<RefCell<dyn Material>> = Rc::new(RefCell::new(Metal::from(
+        Color3::from(0.8, 0.8, 0.85),
+        0.05,
+    )));
+    let hammered = Rc::new(RefCell::new(BumpMap::from(
+        steel,
+        Rc::new(NoiseTexture::new(7, 6.0, 3)),
+        0.03,
+    )));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(0.0, 0.8, 0.0),
+        0.8,
+        hammered,
+    ))));
+
+    // Over-under weave encoded as a tangent-space normal map.
+    let weave = |u: f64, v: f64, _p: Point3| {
+        let (a, b) = (2.0 * PI * 48.0 * u, 2.0 * PI * 24.0 * v);
+        let n = unit_vector(Vec3::from(
+            0.4 * a.sin() * b.cos(),
+            0.4 * b.sin() * a.cos(),
+            1.0,
+        ));
+        0.5 * (n + Color3::from(1.0, 1.0, 1.0))
+    };
+    let cloth: Rc<RefCell<dyn Material>> =
+        Rc::new(RefCell::new(Lambertian::from(Color3::from(0.2, 0.3, 0.6))));
+    let cloth_mat = Rc::new(RefCell::new(NormalMap::from(cloth, Rc::new(weave), 1.0)));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(2.2, 0.8, 0.0),
+        0.8,
+        cloth_mat,
+    ))));
+
+    let brick_torus = Rc::new(RefCell::new(BumpMap::from(
+        brick_red,
+        Rc::new(bricks),
+        0.01,
+    )));
+    world.add(Rc::new(RefCell::new(Torus::new(
+        Point3::from(0.0, 0.25, 1.8),
+        Vec3::from(0.0, 1.0, 0.0),
+        0.6,
+        0.25,
+        brick_torus,
+    ))));
+}
+
+/// Cutout geometry seen from (0, 2, 6): a chain-link fence card in front
+/// of colored balls, leaf cards with soft edges and a half-opaque ball.
+pub fn create_cutout_scene(world: &mut (impl Hittable + List)) {
+    let ground_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.5, 0.5, 0.5))));
+    world.add(Rc::new(RefCell::new(Sphere::new(
+        Point3::from(0.0, -1000.0, 0.0),
+        1000.0,
+        ground_mat,
+    ))));
+    for (k, clr) in [
+        Color3::from(0.8, 0.2, 0.2),
+        Color3::from(0.2, 0.7, 0.3),
+        Color3::from(0.2, 0.3, 0.8),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        world.add(Rc::new(RefCell::new(Sphere::new(
+            Point3::from(-1.5 + 1.5 * k as f64, 0.5, -1.5),
+            0.5,
+            Rc::new(RefCell::new(Lambertian::from(clr))),
+        ))));
+    }
+
+    // Unit square card in the xy plane, UVs following x and y.
+    let card = |corner: Point3, right: Vec3, up: Vec3| {
+        let mut mesh = Mesh::new();
+        mesh.positions = vec![corner, corner + right, corner + right + up, corner + up];
+        mesh.uvs = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        mesh.faces = vec![vec![0, 1, 2, 3]];
+        mesh
+    };
+
+    // Wire diamonds: opaque within a thin band around the diagonals of
+    // each cell.
+    let chain_link = |u: f64, v: f64, _p: Point3| {
+        let (a, b) = ((24.0 * (u + v)).fract(), (24.0 * (u - v + 1.0)).fract());
+        let wire = |s: f64| s.min(1.0 - s) < 0.08;
+        let alpha = if wire(a) || wire(b) { 1.0 } else { 0.0 };
+        Color3::from(alpha, alpha, alpha)
+    };
+    let wire_mat = Rc::new(RefCell::new(Metal::from(Color3::from(0.7, 0.7, 0.7), 0.3)));
+    let fence = TriangleMesh::new(
+        &card(
+            Point3::from(-2.5, 0.0, 0.0),
+            Vec3::from(5.0, 0.0, 0.0),
+            Vec3::from(0.0, 1.6, 0.0),
+        ),
+        wire_mat,
+    );
+    world.add(Rc::new(RefCell::new(OpacityMask::new(
+        Rc::new(RefCell::new(fence)),
+        Rc::new(chain_link),
+    ))));
+
+    // Leaf silhouette whose opacity fades out over its rim.
+    let leaf = |u: f64, v: f64, _p: Point3| {
+        let width = 0.45 * (PI * v).sin();
+        let alpha = ((width - (u - 0.5).abs()) / 0.05).clamp(0.0, 1.0);
+        Color3::from(alpha, alpha, alpha)
+    };
+    let leaf_mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.2, 0.5, 0.1))));
+    for (corner, right, up) in [
+        (
+            Point3::from(1.6, 0.0, 1.2),
+            Vec3::from(0.6, 0.0, -0.2),
+            Vec3::from(0.1, 1.2, 0.2),
+        ),
+        (
+            Point3::from(2.0, 0.0, 1.0),
+            Vec3::from(0.5, 0.0, 0.3),
+            Vec3::from(0.3, 1.0, -0.1),
+        ),
+    ] {
+        let card = TriangleMesh::new(&card(corner, right, up), leaf_mat.clone());
+        world.add(Rc::new(RefCell::new(OpacityMask::new(
+            Rc::new(RefCell::new(card)),
+            Rc::new(leaf),
+        ))));
+    }
+
+    let ghost = Sphere::new(
+        Point3::from(-1.8, 0.5, 1.2),
+        0.5,
+        Rc::new(RefCell::new(Lambertian::from(Color3::from(0.9, 0.8, 0.2)))),
+    );
+    world.add(Rc::new(RefCell::new(OpacityMask::new(
+        Rc::new(RefCell::new(ghost)),
+        Rc::new(|_u: f64, _v: f64, _p: Point3| Color3::from(0.5, 0.5, 0.5)),
+    ))));
+}
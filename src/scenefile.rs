@@ -0,0 +1,754 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::rc::Rc;
+
+use crate::{
+    Aabb, Aperture, BumpMap, BvhNode, Camera, CameraBuilder, Capsule, Color3, Cone, Csg, CsgOp,
+    Diaelectric, DirectionalLight, Ellipsoid, Film, Filter, HeightField, Hittable, HittableList,
+    Image, ImageTexture, Instance, Json, Lambertian, Light, List, Material, Metaballs, Metal,
+    NoiseTexture, NormalMap, OpacityMask, Point3, PointLight, Projection, SamplerKind, Scene,
+    SceneSetup, Sdf, SdfBox, SdfHittable, SdfRepeat, SdfRoundBox, SdfSmoothSubtraction,
+    SdfSmoothUnion, SdfSphere, SdfTorus, SdfTranslate, SdfTwist, Sphere, SpotLight, Texture,
+    ToneMap, Torus, TriangleMesh, Vec3,
+};
+
+/// Value of the `format` member identifying a scene file.
+const FORMAT: &str = "raytracer-scene";
+/// Newest scene file version this build reads and the one it writes.
+const VERSION: usize = 1;
+
+/// Reasons `load_scene` and `save_scene` fail.
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    /// Malformed JSON, unknown object type, missing member or bad reference.
+    Invalid(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "{e}"),
+            SceneError::Invalid(what) => write!(f, "invalid scene file: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+    fn from(e: io::Error) -> SceneError {
+        SceneError::Io(e)
+    }
+}
+
+/// Collects a scene while its parts describe themselves through
+/// `to_json`. Materials and textures are written once each, however many
+/// objects share them, and referenced by index.
+#[derive(Default)]
+pub struct SceneWriter {
+    materials: Vec<Json>,
+    material_ids: HashMap<*const (), usize>,
+    textures: Vec<Json>,
+    texture_ids: HashMap<*const (), usize>,
+    prototypes: Vec<Json>,
+    prototype_ids: HashMap<*const (), usize>,
+    warnings: Vec<String>,
+}
+
+impl SceneWriter {
+    pub fn new() -> SceneWriter {
+        SceneWriter::default()
+    }
+
+    /// Notes something that could not be saved faithfully. Repeated
+    /// messages are kept once.
+    pub fn warn(&mut self, message: &str) {
+        if !self.warnings.iter().any(|w| w == message) {
+            self.warnings.push(message.to_string());
+        }
+    }
+
+    /// Description of `object`, or `None` with a warning when it cannot be
+    /// saved.
+    pub fn object(&mut self, object: &Rc<RefCell<dyn Hittable>>) -> Option<Json> {
+        let json = object.borrow().to_json(self);
+        if json.is_none() {
+            self.warn("skipped objects that cannot be saved");
+        }
+        json
+    }
+
+    /// Index of `object` in the prototype table, which holds objects
+    /// shared by instances once each, or `None` as for `object`.
+    pub fn prototype(&mut self, object: &Rc<RefCell<dyn Hittable>>) -> Option<Json> {
+        let key = Rc::as_ptr(object) as *const ();
+        if let Some(id) = self.prototype_ids.get(&key) {
+            return Some((*id).into());
+        }
+        let json = self.object(object)?;
+        self.prototypes.push(json);
+        self.prototype_ids.insert(key, self.prototypes.len() - 1);
+        Some((self.prototypes.len() - 1).into())
+    }
+
+    /// Index of `mat` in the material table. Materials that cannot be saved
+    /// are replaced by a gray diffuse one, with a warning.
+    pub fn material(&mut self, mat: &Rc<RefCell<dyn Material>>) -> Json {
+        let key = Rc::as_ptr(mat) as *const ();
+        if let Some(id) = self.material_ids.get(&key) {
+            return (*id).into();
+        }
+        let json = mat.borrow().to_json(self).unwrap_or_else(|| {
+            self.warn("replaced materials that cannot be saved by gray diffuse ones");
+            Json::object([
+                ("type", "lambertian".into()),
+                ("albedo", Color3::from(0.5, 0.5, 0.5).into()),
+            ])
+        });
+        self.materials.push(json);
+        self.material_ids.insert(key, self.materials.len() - 1);
+        (self.materials.len() - 1).into()
+    }
+
+    /// Index of `texture` in the texture table, or `None` with a warning
+    /// for textures that cannot be saved, such as closures.
+    pub fn texture(&mut self, texture: &Rc<dyn Texture>) -> Option<Json> {
+        let key = Rc::as_ptr(texture) as *const ();
+        if let Some(id) = self.texture_ids.get(&key) {
+            return Some((*id).into());
+        }
+        let Some(json) = texture.to_json(self) else {
+            self.warn("textures defined in code cannot be saved");
+            return None;
+        };
+        self.textures.push(json);
+        self.texture_ids.insert(key, self.textures.len() - 1);
+        Some((self.textures.len() - 1).into())
+    }
+
+    /// Description of a distance field, or `None` with a warning.
+    pub fn sdf(&mut self, sdf: &Rc<dyn Sdf>) -> Option<Json> {
+        let json = sdf.to_json(self);
+        if json.is_none() {
+            self.warn("distance fields defined in code cannot be saved");
+        }
+        json
+    }
+}
+
+/// Describes `scene` and the camera settings in `camera` as a scene file
+/// document, along with warnings about anything left out or replaced.
+pub fn scene_to_json(scene: &Scene, camera: &CameraBuilder) -> (Json, Vec<String>) {
+    let mut out = SceneWriter::new();
+    let objects: Vec<Json> = scene
+        .world
+        .objects()
+        .iter()
+        .filter_map(|o| out.object(o))
+        .collect();
+    let lights: Vec<Json> = scene
+        .lights
+        .iter()
+        .filter_map(|light| {
+            let json = light.to_json(&mut out);
+            if json.is_none() {
+                out.warn("skipped lights that cannot be saved");
+            }
+            json
+        })
+        .collect();
+    let camera = camera.to_json(&mut out);
+    let json = Json::object([
+        ("format", FORMAT.into()),
+        ("version", VERSION.into()),
+        ("camera", camera),
+        ("textures", out.textures.into()),
+        ("materials", out.materials.into()),
+        ("prototypes", out.prototypes.into()),
+        ("objects", objects.into()),
+        ("lights", lights.into()),
+    ]);
+    (json, out.warnings)
+}
+
+/// Writes `scene` and `camera` to a JSON scene file and returns the
+/// warnings of `scene_to_json`.
+pub fn save_scene(
+    path: &str,
+    scene: &Scene,
+    camera: &CameraBuilder,
+) -> Result<Vec<String>, SceneError> {
+    let (json, warnings) = scene_to_json(scene, camera);
+    fs::write(path, format!("{json:#}\n"))?;
+    Ok(warnings)
+}
+
+/// Reads a scene file written by `save_scene`.
+pub fn load_scene(path: &str) -> Result<SceneSetup, SceneError> {
+    let text = fs::read_to_string(path)?;
+    let json = Json::parse(&text).map_err(SceneError::Invalid)?;
+    scene_from_json(&json)
+}
+
+/// Rebuilds a scene and camera settings from a scene file document.
+pub fn scene_from_json(json: &Json) -> Result<SceneSetup, SceneError> {
+    if json.get("format").and_then(Json::as_str) != Some(FORMAT) {
+        return Err(invalid("not a scene file"));
+    }
+    match json.get("version").and_then(Json::as_usize) {
+        Some(v) if v <= VERSION => {}
+        _ => return Err(invalid("unsupported version")),
+    }
+    let mut reader = Reader {
+        textures: Vec::new(),
+        materials: Vec::new(),
+        prototypes: Vec::new(),
+    };
+    for texture in array(json, "textures")? {
+        let texture = reader.texture(texture)?;
+        reader.textures.push(texture);
+    }
+    for mat in array(json, "materials")? {
+        let mat = reader.material(mat)?;
+        reader.materials.push(mat);
+    }
+    for object in array(json, "prototypes")? {
+        let object = reader.object(object)?;
+        reader.prototypes.push(object);
+    }
+    let mut scene = Scene::new();
+    for object in array(json, "objects")? {
+        scene.world.add(reader.object(object)?);
+    }
+    for light in array(json, "lights")? {
+        scene.lights.add(reader.light(light)?);
+    }
+    let camera = match json.get("camera") {
+        Some(camera) => reader.camera(camera)?,
+        None => Camera::builder(),
+    };
+    Ok(SceneSetup { scene, camera })
+}
+
+fn invalid(what: &str) -> SceneError {
+    SceneError::Invalid(what.to_string())
+}
+
+fn kind(value: &Json) -> &str {
+    value.get("type").and_then(Json::as_str).unwrap_or("")
+}
+
+/// Error for a missing or malformed member `key` of `value`.
+fn bad(value: &Json, key: &str) -> SceneError {
+    match kind(value) {
+        "" => SceneError::Invalid(format!("bad or missing `{key}`")),
+        kind => SceneError::Invalid(format!("{kind}: bad or missing `{key}`")),
+    }
+}
+
+fn member<'a>(value: &'a Json, key: &str) -> Result<&'a Json, SceneError> {
+    value.get(key).ok_or_else(|| bad(value, key))
+}
+
+/// Array member `key`, empty when missing.
+fn array<'a>(value: &'a Json, key: &str) -> Result<&'a [Json], SceneError> {
+    match value.get(key) {
+        None => Ok(&[]),
+        Some(items) => items
+            .as_array()
+            .map(Vec::as_slice)
+            .ok_or_else(|| bad(value, key)),
+    }
+}
+
+fn number(value: &Json, key: &str) -> Result<f64, SceneError> {
+    member(value, key)?.as_f64().ok_or_else(|| bad(value, key))
+}
+
+fn count(value: &Json, key: &str) -> Result<usize, SceneError> {
+    member(value, key)?
+        .as_usize()
+        .ok_or_else(|| bad(value, key))
+}
+
+fn flag(value: &Json, key: &str) -> Result<bool, SceneError> {
+    member(value, key)?.as_bool().ok_or_else(|| bad(value, key))
+}
+
+fn vec3(value: &Json, key: &str) -> Result<Vec3, SceneError> {
+    member(value, key)?.as_vec3().ok_or_else(|| bad(value, key))
+}
+
+/// Flat number array member `key` holding groups of `n`, empty when
+/// missing.
+fn numbers(value: &Json, key: &str, n: usize) -> Result<Vec<f64>, SceneError> {
+    let numbers = match value.get(key) {
+        None => Vec::new(),
+        Some(items) => items.as_f64s().ok_or_else(|| bad(value, key))?,
+    };
+    if !numbers.len().is_multiple_of(n) {
+        return Err(bad(value, key));
+    }
+    Ok(numbers)
+}
+
+/// Resolves references into the texture, material and prototype tables
+/// while rebuilding objects through their public constructors.
+struct Reader {
+    textures: Vec<Rc<dyn Texture>>,
+    materials: Vec<Rc<RefCell<dyn Material>>>,
+    prototypes: Vec<Rc<RefCell<dyn Hittable>>>,
+}
+
+impl Reader {
+    fn texture_ref(&self, value: &Json, key: &str) -> Result<Rc<dyn Texture>, SceneError> {
+        let id = count(value, key)?;
+        self.textures
+            .get(id)
+            .cloned()
+            .ok_or_else(|| bad(value, key))
+    }
+
+    /// Material `key` of `value`, which may only refer to materials
+    /// listed before it.
+    fn material_ref(
+        &self,
+        value: &Json,
+        key: &str,
+    ) -> Result<Rc<RefCell<dyn Material>>, SceneError> {
+        let id = count(value, key)?;
+        self.materials
+            .get(id)
+            .cloned()
+            .ok_or_else(|| bad(value, key))
+    }
+
+    fn texture(&self, value: &Json) -> Result<Rc<dyn Texture>, SceneError> {
+        Ok(match kind(value) {
+            "image" => {
+                let (w, h) = (count(value, "width")?, count(value, "height")?);
+                let pixels: Vec<Color3> = numbers(value, "pixels", 3)?
+                    .chunks(3)
+                    .map(|c| Color3::from(c[0], c[1], c[2]))
+                    .collect();
+                if pixels.len() != w * h {
+                    return Err(bad(value, "pixels"));
+                }
+                Rc::new(ImageTexture::new(Image {
+                    w: w as u64,
+                    h: h as u64,
+                    pixels,
+                }))
+            }
+            "noise" => Rc::new(NoiseTexture::new(
+                member(value, "seed")?
+                    .as_u64()
+                    .ok_or_else(|| bad(value, "seed"))?,
+                number(value, "frequency")?,
+                count(value, "octaves")? as u32,
+            )),
+            other => return Err(SceneError::Invalid(format!("unknown texture {other:?}"))),
+        })
+    }
+
+    fn material(&self, value: &Json) -> Result<Rc<RefCell<dyn Material>>, SceneError> {
+        Ok(match kind(value) {
+            "lambertian" => {
+                let albedo = vec3(value, "albedo")?;
+                match value.get("texture") {
+                    Some(_) => Rc::new(RefCell::new(Lambertian::from_texture(
+                        albedo,
+                        self.texture_ref(value, "texture")?,
+                    ))),
+                    None => Rc::new(RefCell::new(Lambertian::from(albedo))),
+                }
+            }
+            "metal" => Rc::new(RefCell::new(Metal::from(
+                vec3(value, "albedo")?,
+                number(value, "fuzz")?,
+            ))),
+            "dielectric" => Rc::new(RefCell::new(Diaelectric::from(number(value, "ior")?))),
+            "normal_map" => Rc::new(RefCell::new(NormalMap::from(
+                self.material_ref(value, "material")?,
+                self.texture_ref(value, "map")?,
+                number(value, "strength")?,
+            ))),
+            "bump_map" => Rc::new(RefCell::new(BumpMap::from(
+                self.material_ref(value, "material")?,
+                self.texture_ref(value, "height")?,
+                number(value, "scale")?,
+            ))),
+            other => return Err(SceneError::Invalid(format!("unknown material {other:?}"))),
+        })
+    }
+
+    fn object(&self, value: &Json) -> Result<Rc<RefCell<dyn Hittable>>, SceneError> {
+        let objects = |key: &str| -> Result<Vec<Rc<RefCell<dyn Hittable>>>, SceneError> {
+            array(value, key)?.iter().map(|o| self.object(o)).collect()
+        };
+        Ok(match kind(value) {
+            "sphere" => Rc::new(RefCell::new(Sphere::new(
+                vec3(value, "center")?,
+                number(value, "radius")?,
+                self.material_ref(value, "material")?,
+            ))),
+            "ellipsoid" => Rc::new(RefCell::new(Ellipsoid::new(
+                vec3(value, "center")?,
+                vec3(value, "radii")?,
+                self.material_ref(value, "material")?,
+            ))),
+            "torus" => Rc::new(RefCell::new(Torus::new(
+                vec3(value, "center")?,
+                vec3(value, "axis")?,
+                number(value, "major")?,
+                number(value, "minor")?,
+                self.material_ref(value, "material")?,
+            ))),
+            "capsule" => Rc::new(RefCell::new(Capsule::new(
+                vec3(value, "a")?,
+                vec3(value, "b")?,
+                number(value, "radius")?,
+                self.material_ref(value, "material")?,
+            ))),
+            "cone" => Rc::new(RefCell::new(
+                Cone::new(
+                    vec3(value, "base")?,
+                    vec3(value, "top")?,
+                    number(value, "base_radius")?,
+                    number(value, "top_radius")?,
+                    flag(value, "capped")?,
+                    self.material_ref(value, "material")?,
+                )
+                .map_err(|_| bad(value, "top"))?,
+            )),
+            "csg" => {
+                let op = match member(value, "op")?.as_str() {
+                    Some("union") => CsgOp::Union,
+                    Some("intersection") => CsgOp::Intersection,
+                    Some("difference") => CsgOp::Difference,
+                    _ => return Err(bad(value, "op")),
+                };
+                Rc::new(RefCell::new(Csg::new(
+                    op,
+                    self.object(member(value, "a")?)?,
+                    self.object(member(value, "b")?)?,
+                )))
+            }
+            "heightfield" => {
+                let (nx, nz) = (count(value, "nx")?, count(value, "nz")?);
+                let heights = member(value, "heights")?
+                    .as_f64s()
+                    .ok_or_else(|| bad(value, "heights"))?;
+                let field = HeightField::new(
+                    heights,
+                    nx,
+                    nz,
+                    vec3(value, "corner")?,
+                    vec3(value, "size")?,
+                    self.material_ref(value, "material")?,
+                )
+                .map_err(|e| SceneError::Invalid(format!("heightfield: {e}")))?;
+                Rc::new(RefCell::new(field))
+            }
+            "metaballs" => {
+                let mut balls = Metaballs::new(
+                    number(value, "threshold")?,
+                    self.material_ref(value, "material")?,
+                );
+                for ball in numbers(value, "balls", 4)?.chunks(4) {
+                    balls.add(Point3::from(ball[0], ball[1], ball[2]), ball[3]);
+                }
+                Rc::new(RefCell::new(balls))
+            }
+            "sdf" => {
+                let bounds = Aabb::from(vec3(value, "min")?, vec3(value, "max")?);
+                Rc::new(RefCell::new(
+                    SdfHittable::new(
+                        self.sdf(member(value, "sdf")?)?,
+                        bounds,
+                        self.material_ref(value, "material")?,
+                    )
+                    .with_tolerance(number(value, "tolerance")?)
+                    .with_max_steps(count(value, "max_steps")? as u32)
+                    .with_step_scale(number(value, "step_scale")?),
+                ))
+            }
+            "opacity_mask" => Rc::new(RefCell::new(OpacityMask::new(
+                self.object(member(value, "object")?)?,
+                self.texture_ref(value, "mask")?,
+            ))),
+            "triangle_mesh" => {
+                let vec3s = |key: &str| -> Result<Vec<Vec3>, SceneError> {
+                    Ok(numbers(value, key, 3)?
+                        .chunks(3)
+                        .map(|p| Vec3::from(p[0], p[1], p[2]))
+                        .collect())
+                };
+                let positions = vec3s("positions")?;
+                let normals = vec3s("normals")?;
+                let colors = vec3s("colors")?;
+                let uvs: Vec<(f64, f64)> = numbers(value, "uvs", 2)?
+                    .chunks(2)
+                    .map(|uv| (uv[0], uv[1]))
+                    .collect();
+                let n = positions.len();
+                for (key, len) in [
+                    ("normals", normals.len()),
+                    ("uvs", uvs.len()),
+                    ("colors", colors.len()),
+                ] {
+                    if len != 0 && len != n {
+                        return Err(bad(value, key));
+                    }
+                }
+                let indices = numbers(value, "triangles", 3)?;
+                if indices
+                    .iter()
+                    .any(|i| i.fract() != 0.0 || *i < 0.0 || *i as usize >= n)
+                {
+                    return Err(bad(value, "triangles"));
+                }
+                let triangles = indices
+                    .chunks(3)
+                    .map(|t| [t[0] as u32, t[1] as u32, t[2] as u32])
+                    .collect();
+                Rc::new(RefCell::new(TriangleMesh::from_triangles(
+                    positions,
+                    normals,
+                    uvs,
+                    colors,
+                    triangles,
+                    self.material_ref(value, "material")?,
+                )))
+            }
+            "bvh" => {
+                let objects = objects("objects")?;
+                if objects.is_empty() {
+                    return Err(bad(value, "objects"));
+                }
+                Rc::new(RefCell::new(BvhNode::new(objects)))
+            }
+            "instance" => {
+                let id = count(value, "object")?;
+                let object = self
+                    .prototypes
+                    .get(id)
+                    .cloned()
+                    .ok_or_else(|| bad(value, "object"))?;
+                let scale = number(value, "scale")?;
+                if scale <= 0.0 {
+                    return Err(bad(value, "scale"));
+                }
+                Rc::new(RefCell::new(
+                    Instance::new(object)
+                        .with_rotation(vec3(value, "axis")?, number(value, "angle")?)
+                        .with_scale(scale)
+                        .with_offset(vec3(value, "offset")?),
+                ))
+            }
+            "list" => {
+                let mut list = HittableList::new();
+                for object in objects("objects")? {
+                    list.add(object);
+                }
+                Rc::new(RefCell::new(list))
+            }
+            other => return Err(SceneError::Invalid(format!("unknown object {other:?}"))),
+        })
+    }
+
+    fn sdf(&self, value: &Json) -> Result<Rc<dyn Sdf>, SceneError> {
+        let child = |key: &str| self.sdf(member(value, key)?);
+        Ok(match kind(value) {
+            "sphere" => Rc::new(SdfSphere {
+                radius: number(value, "radius")?,
+            }),
+            "box" => Rc::new(SdfBox {
+                half: vec3(value, "half")?,
+            }),
+            "round_box" => Rc::new(SdfRoundBox {
+                half: vec3(value, "half")?,
+                radius: number(value, "radius")?,
+            }),
+            "torus" => Rc::new(SdfTorus {
+                major: number(value, "major")?,
+                minor: number(value, "minor")?,
+            }),
+            "translate" => Rc::new(SdfTranslate {
+                offset: vec3(value, "offset")?,
+                sdf: child("sdf")?,
+            }),
+            "smooth_union" => Rc::new(SdfSmoothUnion {
+                a: child("a")?,
+                b: child("b")?,
+                k: number(value, "k")?,
+            }),
+            "smooth_subtraction" => Rc::new(SdfSmoothSubtraction {
+                a: child("a")?,
+                b: child("b")?,
+                k: number(value, "k")?,
+            }),
+            "repeat" => Rc::new(SdfRepeat {
+                spacing: vec3(value, "spacing")?,
+                sdf: child("sdf")?,
+            }),
+            "twist" => Rc::new(SdfTwist {
+                rate: number(value, "rate")?,
+                sdf: child("sdf")?,
+            }),
+            other => return Err(SceneError::Invalid(format!("unknown sdf {other:?}"))),
+        })
+    }
+
+    fn light(&self, value: &Json) -> Result<Rc<dyn Light>, SceneError> {
+        Ok(match kind(value) {
+            "point" => Rc::new(PointLight::new(
+                vec3(value, "position")?,
+                vec3(value, "intensity")?,
+            )),
+            "spot" => Rc::new(SpotLight::new(
+                vec3(value, "position")?,
+                vec3(value, "target")?,
+                vec3(value, "intensity")?,
+                number(value, "total_width")?,
+                number(value, "falloff_start")?,
+            )),
+            "directional" => Rc::new(DirectionalLight::new(
+                vec3(value, "direction")?,
+                vec3(value, "radiance")?,
+            )),
+            other => return Err(SceneError::Invalid(format!("unknown light {other:?}"))),
+        })
+    }
+
+    /// Camera settings; members left out keep the builder defaults.
+    fn camera(&self, value: &Json) -> Result<CameraBuilder, SceneError> {
+        let mut camera = Camera::builder();
+        let has = |key: &str| value.get(key).is_some();
+        if has("width") {
+            camera = camera.width(count(value, "width")? as u64);
+        }
+        if has("height") {
+            camera = camera.height(count(value, "height")? as u64);
+        }
+        if has("aspect_ratio") {
+            camera = camera.aspect_ratio(number(value, "aspect_ratio")?);
+        }
+        if has("samples_per_pixel") {
+            camera = camera.samples_per_pixel(count(value, "samples_per_pixel")? as u64);
+        }
+        if has("max_depth") {
+            camera = camera.max_depth(count(value, "max_depth")? as u32);
+        }
+        if has("fov") {
+            camera = camera.fov(number(value, "fov")?);
+        }
+        if has("lookfrom") {
+            camera = camera.lookfrom(vec3(value, "lookfrom")?);
+        }
+        if has("lookat") {
+            camera = camera.lookat(vec3(value, "lookat")?);
+        }
+        if has("vup") {
+            camera = camera.vup(vec3(value, "vup")?);
+        }
+        if has("defocus_angle") {
+            camera = camera.defocus_angle(number(value, "defocus_angle")?);
+        }
+        if has("focus_dist") {
+            camera = camera.focus_dist(number(value, "focus_dist")?);
+        }
+        if let Some(lens) = value.get("lens") {
+            camera = camera.lens(number(lens, "focal_length")?, number(lens, "f_number")?);
+        }
+        if let Some(aperture) = value.get("aperture") {
+            camera = camera.aperture(match kind(aperture) {
+                "circle" => Aperture::Circle,
+                "polygon" => Aperture::Polygon {
+                    blades: count(aperture, "blades")? as u32,
+                    rotation: number(aperture, "rotation")?,
+                },
+                _ => return Err(bad(value, "aperture")),
+            });
+        }
+        if let Some(projection) = value.get("projection") {
+            camera = camera.projection(match kind(projection) {
+                "perspective" => Projection::Perspective,
+                "orthographic" => Projection::Orthographic {
+                    height: number(projection, "height")?,
+                },
+                "fisheye" => Projection::Fisheye {
+                    fov: number(projection, "fov")?,
+                },
+                "equirect" => Projection::Equirectangular,
+                "cubemap" => Projection::CubeMap,
+                _ => return Err(bad(value, "projection")),
+            });
+        }
+        if let Some(film) = value.get("film") {
+            let tone = member(film, "tone_map")?;
+            let tone_map = match kind(tone) {
+                "clamp" => ToneMap::Clamp,
+                "reinhard" => ToneMap::Reinhard,
+                "extended-reinhard" => ToneMap::ExtendedReinhard {
+                    white: number(tone, "white")?,
+                },
+                "aces" => ToneMap::AcesFilmic,
+                "agx" => ToneMap::AgX,
+                _ => return Err(bad(film, "tone_map")),
+            };
+            camera = camera.film(Film::from(number(film, "exposure")?, tone_map));
+        }
+        if let Some(filter) = value.get("filter") {
+            camera = camera.filter(Filter::from_json(filter).ok_or_else(|| bad(value, "filter"))?);
+        }
+        if has("sampler") {
+            let sampler = member(value, "sampler")?
+                .as_str()
+                .and_then(SamplerKind::from)
+                .ok_or_else(|| bad(value, "sampler"))?;
+            let seed = match value.get("seed") {
+                Some(seed) => seed.as_u64().ok_or_else(|| bad(value, "seed"))?,
+                None => 0,
+            };
+            camera = camera.sampler(sampler, seed);
+        }
+        Ok(camera)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BUILTIN_SCENES;
+
+    #[test]
+    fn builtin_scenes_survive_a_round_trip() {
+        let round_trip = |json: &Json, name: &str| {
+            let setup =
+                scene_from_json(json).unwrap_or_else(|e| panic!("{name}: cannot read back: {e}"));
+            scene_to_json(&setup.scene, &setup.camera)
+        };
+        for builtin in BUILTIN_SCENES {
+            let setup = builtin.build(0);
+            let (mut first, warnings) = scene_to_json(&setup.scene, &setup.camera);
+            if !warnings.is_empty() {
+                // Closure textures are left out of the file, so compare
+                // what is left of the scene once they are gone.
+                first = round_trip(&first, builtin.name).0;
+            }
+            let (second, warnings) = round_trip(&first, builtin.name);
+            assert!(warnings.is_empty(), "{}: {warnings:?}", builtin.name);
+            // Not `assert_eq!`, which would print megabytes of meshes.
+            assert!(
+                first.to_string() == second.to_string(),
+                "{} changed on the way through a scene file",
+                builtin.name
+            );
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    count, Aabb, Counter, HitRecord, Hittable, Interval, Json, Material, MaterialVisitor, Point3,
+    Ray, SceneWriter, Vec3,
+};
+
+/// Signed distance to a surface: negative inside, positive outside. The
+/// magnitude must never overestimate the true distance, or sphere tracing
+/// steps through the surface.
+pub trait Sdf {
+    fn distance(&self, p: Point3) -> f64;
+
+    /// Scene file description, see `SceneWriter`. Closures cannot be saved.
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        None
+    }
+}
+
+/// Any `Fn(Point3) -> f64` closure is a distance function.
+impl<F: Fn(Point3) -> f64> Sdf for F {
+    fn distance(&self, p: Point3) -> f64 {
+        self(p)
+    }
+}
+
+/// Sphere of `radius` around the origin.
+pub struct SdfSphere {
+    pub radius: f64,
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: Point3) -> f64 {
+        p.length() - self.radius
+    }
+
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "sphere".into()),
+            ("radius", self.radius.into()),
+        ]))
+    }
+}
+
+/// Box centered on the origin with half-widths `half`.
+pub struct SdfBox {
+    pub half: Vec3,
+}
+
+impl Sdf for SdfBox {
+    fn distance(&self, p: Point3) -> f64 {
+        box_distance(p, self.half)
+    }
+
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "box".into()),
+            ("half", self.half.into()),
+        ]))
+    }
+}
+
+/// `SdfBox` with edges rounded by `radius`, within the same outer size.
+pub struct SdfRoundBox {
+    pub half: Vec3,
+    pub radius: f64,
+}
+
+impl Sdf for SdfRoundBox {
+    fn distance(&self, p: Point3) -> f64 {
+        let r = Vec3::from(self.radius, self.radius, self.radius);
+        box_distance(p, self.half - r) - self.radius
+    }
+
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "round_box".into()),
+            ("half", self.half.into()),
+            ("radius", self.radius.into()),
+        ]))
+    }
+}
+
+/// Torus around the y axis.
+pub struct SdfTorus {
+    pub major: f64,
+    pub minor: f64,
+}
+
+impl Sdf for SdfTorus {
+    fn distance(&self, p: Point3) -> f64 {
+        let ring = (p.x() * p.x() + p.z() * p.z()).sqrt() - self.major;
+        (ring * ring + p.y() * p.y()).sqrt() - self.minor
+    }
+
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "torus".into()),
+            ("major", self.major.into()),
+            ("minor", self.minor.into()),
+        ]))
+    }
+}
+
+/// `sdf` moved by `offset`.
+pub struct SdfTranslate {
+    pub offset: Vec3,
+    pub sdf: Rc<dyn Sdf>,
+}
+
+impl Sdf for SdfTranslate {
+    fn distance(&self, p: Point3) -> f64 {
+        self.sdf.distance(p - self.offset)
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "translate".into()),
+            ("offset", self.offset.into()),
+            ("sdf", out.sdf(&self.sdf)?),
+        ]))
+    }
+}
+
+/// Union blending the two shapes over a distance of about `k`; `k = 0`
+/// gives the plain union.
+pub struct SdfSmoothUnion {
+    pub a: Rc<dyn Sdf>,
+    pub b: Rc<dyn Sdf>,
+    pub k: f64,
+}
+
+impl Sdf for SdfSmoothUnion {
+    fn distance(&self, p: Point3) -> f64 {
+        let (d1, d2) = (self.a.distance(p), self.b.distance(p));
+        if self.k <= 0.0 {
+            return d1.min(d2);
+        }
+        let h = (0.5 + 0.5 * (d2 - d1) / self.k).clamp(0.0, 1.0);
+        mix(d2, d1, h) - self.k * h * (1.0 - h)
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "smooth_union".into()),
+            ("a", out.sdf(&self.a)?),
+            ("b", out.sdf(&self.b)?),
+            ("k", self.k.into()),
+        ]))
+    }
+}
+
+/// `a` with `b` carved out, the cut blended over about `k`.
+pub struct SdfSmoothSubtraction {
+    pub a: Rc<dyn Sdf>,
+    pub b: Rc<dyn Sdf>,
+    pub k: f64,
+}
+
+impl Sdf for SdfSmoothSubtraction {
+    fn distance(&self, p: Point3) -> f64 {
+        let (d1, d2) = (self.a.distance(p), -self.b.distance(p));
+        if self.k <= 0.0 {
+            return d1.max(d2);
+        }
+        let h = (0.5 - 0.5 * (d1 - d2) / self.k).clamp(0.0, 1.0);
+        mix(d1, d2, h) + self.k * h * (1.0 - h)
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "smooth_subtraction".into()),
+            ("a", out.sdf(&self.a)?),
+            ("b", out.sdf(&self.b)?),
+            ("k", self.k.into()),
+        ]))
+    }
+}
+
+/// Infinite copies of `sdf` on a grid with cell size `spacing`, each cell
+/// centered on a multiple of `spacing`. A zero spacing component leaves
+/// that axis unrepeated. The shape must fit in one cell.
+pub struct SdfRepeat {
+    pub spacing: Vec3,
+    pub sdf: Rc<dyn Sdf>,
+}
+
+impl Sdf for SdfRepeat {
+    fn distance(&self, p: Point3) -> f64 {
+        let fold = |x: f64, s: f64| {
+            if s > 0.0 {
+                x - s * (x / s).round()
+            } else {
+                x
+            }
+        };
+        let s = self.spacing;
+        self.sdf.distance(Vec3::from(
+            fold(p.x(), s.x()),
+            fold(p.y(), s.y()),
+            fold(p.z(), s.z()),
+        ))
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "repeat".into()),
+            ("spacing", self.spacing.into()),
+            ("sdf", out.sdf(&self.sdf)?),
+        ]))
+    }
+}
+
+/// `sdf` twisted around the y axis by `rate` radians per unit of height.
+/// Twisting stretches distances, so pair it with a `step_scale` below one.
+pub struct SdfTwist {
+    pub rate: f64,
+    pub sdf: Rc<dyn Sdf>,
+}
+
+impl Sdf for SdfTwist {
+    fn distance(&self, p: Point3) -> f64 {
+        let (sin, cos) = (self.rate * p.y()).sin_cos();
+        let q = Vec3::from(cos * p.x() - sin * p.z(), p.y(), sin * p.x() + cos * p.z());
+        self.sdf.distance(q)
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "twist".into()),
+            ("rate", self.rate.into()),
+            ("sdf", out.sdf(&self.sdf)?),
+        ]))
+    }
+}
+
+fn box_distance(p: Point3, half: Vec3) -> f64 {
+    let q = Vec3::from(
+        p.x().abs() - half.x(),
+        p.y().abs() - half.y(),
+        p.z().abs() - half.z(),
+    );
+    let outside = Vec3::from(q.x().max(0.0), q.y().max(0.0), q.z().max(0.0));
+    outside.length() + q.x().max(q.y()).max(q.z()).min(0.0)
+}
+
+fn mix(a: f64, b: f64, h: f64) -> f64 {
+    a + (b - a) * h
+}
+
+/// Hittable surface of a distance field, found by sphere tracing inside
+/// `bounds`. Normals are central differences of the field; UVs are not set.
+pub struct SdfHittable {
+    sdf: Rc<dyn Sdf>,
+    bounds: Aabb,
+    mat: Rc<RefCell<dyn Material>>,
+    tolerance: f64,
+    max_steps: u32,
+    step_scale: f64,
+}
+
+impl SdfHittable {
+    pub fn new(sdf: Rc<dyn Sdf>, bounds: Aabb, mat: Rc<RefCell<dyn Material>>) -> SdfHittable {
+        SdfHittable {
+            sdf,
+            bounds,
+            mat,
+            tolerance: 1e-4,
+            max_steps: 256,
+            step_scale: 1.0,
+        }
+    }
+
+    /// Distance below which the ray counts as having reached the surface.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Steps after which the ray is considered to miss.
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Fraction of the distance bound taken per step, for fields that
+    /// overestimate distances.
+    pub fn with_step_scale(mut self, step_scale: f64) -> Self {
+        self.step_scale = step_scale;
+        self
+    }
+
+    fn normal(&self, p: Point3) -> Vec3 {
+        let e = self.tolerance;
+        let d = |dx: f64, dy: f64, dz: f64| {
+            self.sdf.distance(p + Vec3::from(dx, dy, dz))
+                - self.sdf.distance(p - Vec3::from(dx, dy, dz))
+        };
+        let n = Vec3::from(d(e, 0.0, 0.0), d(0.0, e, 0.0), d(0.0, 0.0, e));
+        n / n.length()
+    }
+}
+
+impl Hittable for SdfHittable {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        count(Counter::IntersectionTests);
+        let Some(span) = self.bounds.clip(r, ray_root) else {
+            return false;
+        };
+        let len = r.direction().length();
+        let mut t = span.min;
+        // Points within `tolerance` of the surface are hits only while the
+        // distance shrinks along the ray. That skips the surface a ray
+        // leaves from but not one it starts on, e.g. at tight bounds.
+        let mut last = self.sdf.distance(r.at(t - self.tolerance / len)).abs();
+        for _ in 0..self.max_steps {
+            if t > span.max {
+                return false;
+            }
+            let p = r.at(t);
+            let dist = self.sdf.distance(p).abs();
+            if dist < self.tolerance && dist <= last {
+                rec.t = t;
+                rec.p = p;
+                rec.set_face_normal(r, self.normal(p));
+                (rec.u, rec.v) = (0.0, 0.0);
+                rec.mat = self.mat.clone();
+                return true;
+            }
+            last = dist;
+            t += self.step_scale * dist.max(self.tolerance) / len;
+        }
+        false
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds.clone()
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        let b = &self.bounds;
+        Some(Json::object([
+            ("type", "sdf".into()),
+            ("sdf", out.sdf(&self.sdf)?),
+            ("min", Vec3::from(b.x.min, b.y.min, b.z.min).into()),
+            ("max", Vec3::from(b.x.max, b.y.max, b.z.max).into()),
+            ("tolerance", self.tolerance.into()),
+            ("max_steps", self.max_steps.into()),
+            ("step_scale", self.step_scale.into()),
+            ("material", out.material(&self.mat)),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        f(&self.mat);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color3, Lambertian, INFINTY};
+
+    #[test]
+    fn hits_the_front_face_where_tight_bounds_start() {
+        let mat = Rc::new(RefCell::new(Lambertian::from(Color3::from(0.5, 0.5, 0.5))));
+        let bounds = Aabb::from(Point3::from(-1.0, -1.0, -1.0), Point3::from(1.0, 1.0, 1.0));
+        let sphere = SdfHittable::new(Rc::new(SdfSphere { radius: 1.0 }), bounds, mat);
+        let r = Ray::from(Point3::from(0.0, 0.0, -5.0), Vec3::from(0.0, 0.0, 1.0));
+        let mut rec = HitRecord::new();
+        assert!(sphere.hit(r, Interval::from(0.001, INFINTY), &mut rec));
+        assert!(rec.front_face);
+        assert!((rec.t - 4.0).abs() < 1e-3, "t = {}", rec.t);
+
+        // Leaving from that point, the ray goes through to the back face.
+        let inside = Ray::from(rec.p, r.direction());
+        assert!(sphere.hit(inside, Interval::from(0.001, INFINTY), &mut rec));
+        assert!(!rec.front_face);
+        assert!((rec.t - 2.0).abs() < 1e-3, "t = {}", rec.t);
+    }
+}
@@ -0,0 +1,124 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    count, dot, Aabb, Counter, HitRecord, Hittable, Interval, Json, Material, MaterialVisitor,
+    Point3, Ray, SceneWriter, Vec3, PI,
+};
+
+pub struct Sphere {
+    center: Point3,
+    radius: f64,
+    mat: Rc<RefCell<dyn Material>>,
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        count(Counter::IntersectionTests);
+        let cmq = self.center - r.origin();
+        let ai = r.direction().length_squared();
+        let h = dot(r.direction(), cmq);
+        let c = cmq.length_squared() - (self.radius * self.radius);
+        let det_in = (h * h) - (ai * c);
+
+        if det_in < 0.0 {
+            false
+        } else {
+            let sqrtd = det_in.sqrt();
+            let mut quad_form = (h - sqrtd) / (ai);
+            if !ray_root.surrounds(quad_form) {
+                quad_form = (h + sqrtd) / (ai);
+                if !ray_root.surrounds(quad_form) {
+                    return false;
+                }
+            }
+
+            rec.t = quad_form;
+            rec.p = r.at(rec.t);
+            let out_norm = (rec.p - self.center) / self.radius;
+            rec.set_face_normal(r, out_norm);
+            (rec.u, rec.v) = sphere_uv(out_norm);
+            let (dpdu, dpdv) = sphere_tangents(rec.p - self.center);
+            rec.set_tangents(dpdu, dpdv);
+            rec.mat = self.mat.clone();
+            true
+        }
+    }
+
+    fn hit_all(&self, r: Ray, ray_root: Interval, hits: &mut Vec<HitRecord>) -> bool {
+        count(Counter::IntersectionTests);
+        let cmq = self.center - r.origin();
+        let ai = r.direction().length_squared();
+        let h = dot(r.direction(), cmq);
+        let c = cmq.length_squared() - (self.radius * self.radius);
+        let det_in = (h * h) - (ai * c);
+        if det_in < 0.0 {
+            return false;
+        }
+
+        let sqrtd = det_in.sqrt();
+        let start = hits.len();
+        for t in [(h - sqrtd) / ai, (h + sqrtd) / ai] {
+            if !ray_root.surrounds(t) {
+                continue;
+            }
+            let mut rec = HitRecord::new();
+            rec.t = t;
+            rec.p = r.at(t);
+            let out_norm = (rec.p - self.center) / self.radius;
+            rec.set_face_normal(r, out_norm);
+            (rec.u, rec.v) = sphere_uv(out_norm);
+            let (dpdu, dpdv) = sphere_tangents(rec.p - self.center);
+            rec.set_tangents(dpdu, dpdv);
+            rec.mat = self.mat.clone();
+            hits.push(rec);
+        }
+        hits.len() > start
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = Vec3::from(self.radius, self.radius, self.radius);
+        Aabb::from(self.center - r, self.center + r)
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "sphere".into()),
+            ("center", self.center.into()),
+            ("radius", self.radius.into()),
+            ("material", out.material(&self.mat)),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        f(&self.mat);
+    }
+}
+
+impl Sphere {
+    pub fn new(center: Point3, radius: f64, mat: Rc<RefCell<dyn Material>>) -> Sphere {
+        Sphere {
+            center,
+            radius,
+            mat,
+        }
+    }
+}
+
+/// Longitude/latitude coordinates of a point `p` on the unit sphere: `u`
+/// runs around the y axis starting at -x, `v` from the south pole to the north.
+pub fn sphere_uv(p: Point3) -> (f64, f64) {
+    let theta = (-p.y()).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z()).atan2(p.x()) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// Derivatives of a point `p` on a sphere around the origin with respect
+/// to the `sphere_uv` coordinates. Not finite at the poles.
+pub fn sphere_tangents(p: Vec3) -> (Vec3, Vec3) {
+    let rho = (p.x() * p.x() + p.z() * p.z()).sqrt();
+    (
+        2.0 * PI * Vec3::from(p.z(), 0.0, -p.x()),
+        PI * Vec3::from(-p.x() * p.y() / rho, rho, -p.y() * p.z() / rho),
+    )
+}
@@ -0,0 +1,217 @@
+use std::cell::Cell;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::AddAssign;
+use std::time::{Duration, Instant};
+
+use crate::Json;
+
+/// Events tallied while tracing. The counts live in thread-local storage
+/// so the hot paths only bump a cell; `RenderCounters::take` collects them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    PrimaryRays,
+    SecondaryRays,
+    ShadowRays,
+    /// Ray tests against a single primitive: a sphere, a triangle, a
+    /// heightfield cell, and so on.
+    IntersectionTests,
+    /// Hierarchy nodes whose bounds were tested, in `BvhNode` trees and
+    /// inside triangle meshes.
+    BvhNodesVisited,
+}
+
+const COUNTERS: usize = 5;
+
+thread_local! {
+    static COUNTS: [Cell<u64>; COUNTERS] = const { [const { Cell::new(0) }; COUNTERS] };
+    static BVH_BUILD: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+pub fn count(counter: Counter) {
+    count_n(counter, 1);
+}
+
+pub fn count_n(counter: Counter, n: u64) {
+    COUNTS.with(|c| {
+        let cell = &c[counter as usize];
+        cell.set(cell.get() + n);
+    });
+}
+
+/// Runs a hierarchy build and adds its duration to the time reported by
+/// `take_bvh_build_time`. Nested builds are only timed once.
+pub fn time_bvh_build<T>(build: impl FnOnce() -> T) -> T {
+    thread_local! {
+        static DEPTH: Cell<u32> = const { Cell::new(0) };
+    }
+    let depth = DEPTH.with(|d| {
+        d.set(d.get() + 1);
+        d.get()
+    });
+    let start = Instant::now();
+    let result = build();
+    DEPTH.with(|d| d.set(d.get() - 1));
+    if depth == 1 {
+        BVH_BUILD.with(|t| t.set(t.get() + start.elapsed()));
+    }
+    result
+}
+
+/// Time this thread spent building hierarchies since the last call.
+pub fn take_bvh_build_time() -> Duration {
+    BVH_BUILD.with(|t| t.replace(Duration::ZERO))
+}
+
+/// Snapshot of the `Counter` tallies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderCounters {
+    pub primary_rays: u64,
+    pub secondary_rays: u64,
+    pub shadow_rays: u64,
+    pub intersection_tests: u64,
+    pub bvh_nodes_visited: u64,
+}
+
+impl RenderCounters {
+    /// Returns this thread's counts and resets them to zero.
+    pub fn take() -> RenderCounters {
+        let take = |counter: Counter| COUNTS.with(|c| c[counter as usize].replace(0));
+        RenderCounters {
+            primary_rays: take(Counter::PrimaryRays),
+            secondary_rays: take(Counter::SecondaryRays),
+            shadow_rays: take(Counter::ShadowRays),
+            intersection_tests: take(Counter::IntersectionTests),
+            bvh_nodes_visited: take(Counter::BvhNodesVisited),
+        }
+    }
+
+    pub fn rays(&self) -> u64 {
+        self.primary_rays + self.secondary_rays + self.shadow_rays
+    }
+
+    /// Camera and bounce rays per camera path, shadow rays left out.
+    pub fn average_path_length(&self) -> f64 {
+        if self.primary_rays == 0 {
+            return 0.0;
+        }
+        (self.primary_rays + self.secondary_rays) as f64 / self.primary_rays as f64
+    }
+}
+
+impl AddAssign for RenderCounters {
+    fn add_assign(&mut self, other: RenderCounters) {
+        self.primary_rays += other.primary_rays;
+        self.secondary_rays += other.secondary_rays;
+        self.shadow_rays += other.shadow_rays;
+        self.intersection_tests += other.intersection_tests;
+        self.bvh_nodes_visited += other.bvh_nodes_visited;
+    }
+}
+
+/// Counters and wall-clock phase timings of one render, for comparing
+/// scenes and releases. Prints as a human-readable summary.
+#[derive(Debug, Clone, Default)]
+pub struct RenderStats {
+    pub counters: RenderCounters,
+    /// Named phases in the order they ran, e.g. scene build, BVH build,
+    /// render and output.
+    pub phases: Vec<(String, Duration)>,
+}
+
+impl RenderStats {
+    pub fn new() -> RenderStats {
+        RenderStats {
+            counters: RenderCounters::default(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Adds `duration` to phase `name`, appending the phase if it is new.
+    pub fn add_phase(&mut self, name: &str, duration: Duration) {
+        match self.phases.iter_mut().find(|(n, _)| n == name) {
+            Some((_, d)) => *d += duration,
+            None => self.phases.push((name.to_string(), duration)),
+        }
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.phases.iter().map(|(_, d)| *d).sum()
+    }
+
+    fn render_seconds(&self) -> f64 {
+        self.phases
+            .iter()
+            .find(|(n, _)| n == "render")
+            .map_or(0.0, |(_, d)| d.as_secs_f64())
+    }
+
+    pub fn to_json(&self) -> Json {
+        let c = &self.counters;
+        let seconds = self.render_seconds();
+        let per_second = |n: u64| {
+            if seconds > 0.0 {
+                n as f64 / seconds
+            } else {
+                0.0
+            }
+        };
+        let phases: Vec<Json> = self
+            .phases
+            .iter()
+            .map(|(name, d)| {
+                Json::object([
+                    ("name", name.as_str().into()),
+                    ("seconds", d.as_secs_f64().into()),
+                ])
+            })
+            .collect();
+        Json::object([
+            ("rays", c.rays().into()),
+            ("primary_rays", c.primary_rays.into()),
+            ("secondary_rays", c.secondary_rays.into()),
+            ("shadow_rays", c.shadow_rays.into()),
+            ("intersection_tests", c.intersection_tests.into()),
+            ("bvh_nodes_visited", c.bvh_nodes_visited.into()),
+            ("average_path_length", c.average_path_length().into()),
+            ("rays_per_second", per_second(c.rays()).into()),
+            ("phases", phases.into()),
+            ("total_seconds", self.total_time().as_secs_f64().into()),
+        ])
+    }
+
+    pub fn write_json(&self, path: &str) -> io::Result<()> {
+        fs::write(path, format!("{:#}\n", self.to_json()))
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = &self.counters;
+        let seconds = self.render_seconds();
+        writeln!(f, "rays cast            {:>14}", c.rays())?;
+        writeln!(f, "  primary            {:>14}", c.primary_rays)?;
+        writeln!(f, "  secondary          {:>14}", c.secondary_rays)?;
+        writeln!(f, "  shadow             {:>14}", c.shadow_rays)?;
+        writeln!(f, "intersection tests   {:>14}", c.intersection_tests)?;
+        writeln!(f, "BVH nodes visited    {:>14}", c.bvh_nodes_visited)?;
+        writeln!(f, "average path length  {:>14.3}", c.average_path_length())?;
+        if seconds > 0.0 {
+            writeln!(
+                f,
+                "rays per second      {:>14.0}",
+                c.rays() as f64 / seconds
+            )?;
+        }
+        for (name, d) in &self.phases {
+            writeln!(f, "{:<20} {:>12.3} s", name, d.as_secs_f64())?;
+        }
+        write!(
+            f,
+            "{:<20} {:>12.3} s",
+            "total",
+            self.total_time().as_secs_f64()
+        )
+    }
+}
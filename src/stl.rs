@@ -0,0 +1,220 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::rc::Rc;
+
+use crate::{cross, dot, unit_vector, Material, Mesh, Point3, TriangleMesh, Vec3};
+
+/// Size of one binary facet: normal, three corners and an attribute word.
+const FACET_BYTES: u64 = 50;
+
+/// Merges corners with identical coordinates into shared vertices.
+#[derive(Default)]
+struct Welder {
+    positions: Vec<Point3>,
+    index: HashMap<[u32; 3], u32>,
+    triangles: Vec<[u32; 3]>,
+}
+
+impl Welder {
+    fn vertex(&mut self, p: [f32; 3]) -> u32 {
+        // Adding zero turns -0.0 into 0.0, so both weld together.
+        let key = p.map(|c| (c + 0.0).to_bits());
+        let next = self.positions.len() as u32;
+        *self.index.entry(key).or_insert_with(|| {
+            self.positions
+                .push(Point3::from(p[0] as f64, p[1] as f64, p[2] as f64));
+            next
+        })
+    }
+
+    fn facet(&mut self, corners: [[f32; 3]; 3]) {
+        let [a, b, c] = corners.map(|p| self.vertex(p));
+        // Facets collapsed by welding cannot be hit.
+        if a != b && b != c && c != a {
+            self.triangles.push([a, b, c]);
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Loads a binary or ASCII STL as a triangle mesh. STL stores every facet
+/// on its own, so coincident corners are welded into shared vertices, and
+/// the facet normals of the file are ignored in favor of generated vertex
+/// normals: facets meeting at less than `crease_angle` degrees are smoothed
+/// across their shared edge, sharper edges stay hard. An angle of 0 keeps
+/// every facet flat.
+pub fn load_stl(
+    path: &str,
+    crease_angle: f64,
+    mat: Rc<RefCell<dyn Material>>,
+) -> io::Result<TriangleMesh> {
+    let Welder {
+        positions,
+        triangles,
+        ..
+    } = weld(path)?;
+    let (positions, normals, triangles) = crease_normals(positions, triangles, crease_angle);
+    Ok(TriangleMesh::from_triangles(
+        positions,
+        normals,
+        Vec::new(),
+        Vec::new(),
+        triangles,
+        mat,
+    ))
+}
+
+/// Reads an STL like `load_stl`, welded but without normals, into a
+/// `Mesh`, e.g. to subdivide it.
+pub fn read_stl(path: &str) -> io::Result<Mesh> {
+    let welder = weld(path)?;
+    Ok(Mesh {
+        positions: welder.positions,
+        faces: welder
+            .triangles
+            .iter()
+            .map(|t| t.iter().map(|v| *v as usize).collect())
+            .collect(),
+        ..Mesh::new()
+    })
+}
+
+/// Reads the facets of the STL at `path` into a welded mesh.
+fn weld(path: &str) -> io::Result<Welder> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut input = BufReader::new(file);
+    let mut header = Vec::new();
+    (&mut input).take(84).read_to_end(&mut header)?;
+    let mut welder = Welder::default();
+    let count = header
+        .get(80..84)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]) as u64);
+    // ASCII files may also start with "solid", so the size decides.
+    if count.is_some_and(|n| 84 + n * FACET_BYTES == len) {
+        let count = count.unwrap_or(0);
+        welder.triangles.reserve(count as usize);
+        let mut facet = [0; FACET_BYTES as usize];
+        for _ in 0..count {
+            input.read_exact(&mut facet)?;
+            let float = |k: usize| {
+                let b = &facet[12 + 4 * k..16 + 4 * k];
+                f32::from_le_bytes([b[0], b[1], b[2], b[3]])
+            };
+            welder.facet([0, 1, 2].map(|c| [0, 1, 2].map(|k| float(3 * c + k))));
+        }
+    } else if header.starts_with(b"solid") {
+        read_ascii(
+            BufReader::new(Cursor::new(header).chain(input)),
+            &mut welder,
+        )?;
+    } else {
+        return Err(invalid(format!("{path} is not an STL file")));
+    }
+    Ok(welder)
+}
+
+/// Reads the `vertex x y z` lines of an ASCII STL, three per facet.
+fn read_ascii(input: impl BufRead, welder: &mut Welder) -> io::Result<()> {
+    let mut corners = Vec::with_capacity(3);
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        if words.next() != Some("vertex") {
+            continue;
+        }
+        let p: Vec<f32> = words
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid(format!("bad vertex: {}", line.trim())))?;
+        if p.len() != 3 {
+            return Err(invalid(format!("bad vertex: {}", line.trim())));
+        }
+        corners.push([p[0], p[1], p[2]]);
+        if corners.len() == 3 {
+            welder.facet([corners[0], corners[1], corners[2]]);
+            corners.clear();
+        }
+    }
+    Ok(())
+}
+
+/// Vertex normals that respect creases. Each corner averages the
+/// area-weighted normals of the triangles around its vertex that lie
+/// within `crease_angle` degrees of its own triangle; vertices whose
+/// corners end up with different normals are split.
+fn crease_normals(
+    positions: Vec<Point3>,
+    mut triangles: Vec<[u32; 3]>,
+    crease_angle: f64,
+) -> (Vec<Point3>, Vec<Vec3>, Vec<[u32; 3]>) {
+    let area_normals: Vec<Vec3> = triangles
+        .iter()
+        .map(|t| {
+            let [p0, p1, p2] = t.map(|v| positions[v as usize]);
+            cross(p1 - p0, p2 - p0)
+        })
+        .collect();
+    let unit_normals: Vec<Vec3> = area_normals
+        .iter()
+        .map(|n| {
+            if n.length_squared() > 0.0 {
+                unit_vector(*n)
+            } else {
+                *n
+            }
+        })
+        .collect();
+
+    // Triangles around each vertex, in compressed rows.
+    let mut start = vec![0usize; positions.len() + 1];
+    for t in &triangles {
+        for v in t {
+            start[*v as usize + 1] += 1;
+        }
+    }
+    for v in 0..positions.len() {
+        start[v + 1] += start[v];
+    }
+    let mut fill = start.clone();
+    let mut incident = vec![0u32; 3 * triangles.len()];
+    for (f, t) in triangles.iter().enumerate() {
+        for v in t {
+            incident[fill[*v as usize]] = f as u32;
+            fill[*v as usize] += 1;
+        }
+    }
+    drop(fill);
+
+    let threshold = crease_angle.to_radians().cos() - 1e-9;
+    let mut split: HashMap<(u32, [u64; 3]), u32> = HashMap::new();
+    let mut out_positions = Vec::with_capacity(positions.len());
+    let mut out_normals = Vec::with_capacity(positions.len());
+    for (f, t) in triangles.iter_mut().enumerate() {
+        for v in t.iter_mut() {
+            let vertex = *v as usize;
+            let around = &incident[start[vertex]..start[vertex + 1]];
+            let sum = around
+                .iter()
+                .filter(|g| dot(unit_normals[f], unit_normals[**g as usize]) >= threshold)
+                .fold(Vec3::new(), |acc, g| acc + area_normals[*g as usize]);
+            let n = if sum.length_squared() > 0.0 {
+                unit_vector(sum)
+            } else {
+                unit_normals[f]
+            };
+            let key = (*v, [n.x().to_bits(), n.y().to_bits(), n.z().to_bits()]);
+            *v = *split.entry(key).or_insert_with(|| {
+                out_positions.push(positions[vertex]);
+                out_normals.push(n);
+                out_positions.len() as u32 - 1
+            });
+        }
+    }
+    (out_positions, out_normals, triangles)
+}
@@ -0,0 +1,103 @@
+use crate::{Color3, Image, Json, Perlin, Point3, SceneWriter};
+
+/// Color looked up from surface coordinates `(u, v)` and the hit point.
+pub trait Texture {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color3;
+
+    /// Scene file description, `None` for textures that cannot be saved.
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        None
+    }
+}
+
+impl<F: Fn(f64, f64, Point3) -> Color3> Texture for F {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color3 {
+        self(u, v, p)
+    }
+}
+
+/// Image stretched over the unit UV square and repeated outside it, with
+/// `v = 0` at the bottom row. Lookups are filtered bilinearly and return
+/// the stored values, so normal and height maps are used as authored.
+pub struct ImageTexture {
+    image: Image,
+}
+
+impl ImageTexture {
+    pub fn new(image: Image) -> ImageTexture {
+        ImageTexture { image }
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color3 {
+        let (w, h) = (self.image.w as i64, self.image.h as i64);
+        self.image.pixels[(y.rem_euclid(h) * w + x.rem_euclid(w)) as usize]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: Point3) -> Color3 {
+        if self.image.pixels.is_empty() {
+            return Color3::new();
+        }
+        let x = u * self.image.w as f64 - 0.5;
+        let y = (1.0 - v) * self.image.h as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        (1.0 - fy) * ((1.0 - fx) * self.texel(x0, y0) + fx * self.texel(x0 + 1, y0))
+            + fy * ((1.0 - fx) * self.texel(x0, y0 + 1) + fx * self.texel(x0 + 1, y0 + 1))
+    }
+
+    /// The pixels are stored inline, so the file does not depend on the
+    /// image the texture was loaded from.
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        let pixels: Vec<Json> = self
+            .image
+            .pixels
+            .iter()
+            .flat_map(|c| [c.x().into(), c.y().into(), c.z().into()])
+            .collect();
+        Some(Json::object([
+            ("type", "image".into()),
+            ("width", self.image.w.into()),
+            ("height", self.image.h.into()),
+            ("pixels", pixels.into()),
+        ]))
+    }
+}
+
+/// Gray fractal Perlin noise over world space, with `frequency` base
+/// features per unit length. Values lie roughly in `[-1, 1]`.
+pub struct NoiseTexture {
+    seed: u64,
+    perlin: Perlin,
+    frequency: f64,
+    octaves: u32,
+}
+
+impl NoiseTexture {
+    pub fn new(seed: u64, frequency: f64, octaves: u32) -> NoiseTexture {
+        NoiseTexture {
+            seed,
+            perlin: Perlin::new(seed),
+            frequency,
+            octaves,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: Point3) -> Color3 {
+        let n = self.perlin.fbm(self.frequency * p, self.octaves);
+        Color3::from(n, n, n)
+    }
+
+    fn to_json(&self, _out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "noise".into()),
+            ("seed", self.seed.into()),
+            ("frequency", self.frequency.into()),
+            ("octaves", self.octaves.into()),
+        ]))
+    }
+}
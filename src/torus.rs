@@ -0,0 +1,112 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    around_axis, count, dot, solve_quartic, unit_vector, Aabb, Counter, HitRecord, Hittable,
+    Interval, Json, Material, MaterialVisitor, Onb, Point3, Ray, SceneWriter, Vec3, PI,
+};
+
+/// Ring torus around `axis`: a tube of radius `minor` swept along a circle
+/// of radius `major`. `u` goes around the axis, `v` around the tube.
+pub struct Torus {
+    center: Point3,
+    axis: Vec3,
+    frame: Onb,
+    major: f64,
+    minor: f64,
+    mat: Rc<RefCell<dyn Material>>,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major: f64,
+        minor: f64,
+        mat: Rc<RefCell<dyn Material>>,
+    ) -> Torus {
+        Torus {
+            center,
+            axis,
+            frame: Onb::from_w(axis),
+            major,
+            minor,
+            mat,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        count(Counter::IntersectionTests);
+        let o = self.frame.to_local(r.origin() - self.center);
+        let d = self.frame.to_local(r.direction());
+        let len = d.length();
+        let d = d / len;
+        // Solve from the point closest to the center, which keeps the
+        // quartic well conditioned for distant origins.
+        let shift = -dot(o, d);
+        let o = o + shift * d;
+        let bound = self.major + self.minor;
+        if o.length_squared() > bound * bound {
+            return false;
+        }
+
+        // (|p|² + R² - r²)² = 4 R² (x² + y²) along p = o + s d, |d| = 1.
+        let (r2, a2) = (self.minor * self.minor, self.major * self.major);
+        let e = o.length_squared() - a2 - r2;
+        let f = dot(o, d);
+        let roots = solve_quartic(
+            4.0 * f,
+            2.0 * e + 4.0 * f * f + 4.0 * a2 * d.z() * d.z(),
+            4.0 * f * e + 8.0 * a2 * o.z() * d.z(),
+            e * e - 4.0 * a2 * (r2 - o.z() * o.z()),
+        );
+        let Some(s) = roots
+            .into_iter()
+            .find(|s| ray_root.surrounds((shift + s) / len))
+        else {
+            return false;
+        };
+
+        let p = o + s * d;
+        // Gradient of the implicit function, divided by 4.
+        let g = p.length_squared() - a2 - r2;
+        let n = g * p + Vec3::from(0.0, 0.0, 2.0 * a2 * p.z());
+        rec.t = (shift + s) / len;
+        rec.p = r.at(rec.t);
+        rec.set_face_normal(r, unit_vector(self.frame.to_world(n)));
+        rec.u = around_axis(p);
+        let ring = (p.x() * p.x() + p.y() * p.y()).sqrt() - self.major;
+        rec.v = p.z().atan2(ring) / (2.0 * PI) + 0.5;
+        let rho = ring + self.major;
+        let dpdu = 2.0 * PI * Vec3::from(-p.y(), p.x(), 0.0);
+        let dpdv = 2.0 * PI * Vec3::from(-p.z() * p.x() / rho, -p.z() * p.y() / rho, ring);
+        rec.set_tangents(self.frame.to_world(dpdu), self.frame.to_world(dpdv));
+        rec.mat = self.mat.clone();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let w = self.frame.w;
+        let outer = self.major + self.minor;
+        let extent = |a: f64| outer * (1.0 - a * a).max(0.0).sqrt() + self.minor * a.abs();
+        let e = Vec3::from(extent(w.x()), extent(w.y()), extent(w.z()));
+        Aabb::from(self.center - e, self.center + e)
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        Some(Json::object([
+            ("type", "torus".into()),
+            ("center", self.center.into()),
+            ("axis", self.axis.into()),
+            ("major", self.major.into()),
+            ("minor", self.minor.into()),
+            ("material", out.material(&self.mat)),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        f(&self.mat);
+    }
+}
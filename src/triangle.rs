@@ -0,0 +1,334 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{
+    count, count_n, cross, dot, time_bvh_build, unit_vector, Aabb, Color3, Counter, HitRecord,
+    Hittable, Interval, Json, Material, MaterialVisitor, Mesh, Point3, Ray, SceneWriter, Vec3,
+};
+
+/// Möller–Trumbore ray/triangle test. Returns `(t, b1, b2)`, where the hit
+/// point is `(1 - b1 - b2) p0 + b1 p1 + b2 p2`.
+pub fn intersect_triangle(r: Ray, p0: Point3, p1: Point3, p2: Point3) -> Option<(f64, f64, f64)> {
+    let e1 = p1 - p0;
+    let e2 = p2 - p0;
+    let pvec = cross(r.direction(), e2);
+    let det = dot(e1, pvec);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let tvec = r.origin() - p0;
+    let b1 = dot(tvec, pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = cross(tvec, e1);
+    let b2 = dot(r.direction(), qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    Some((dot(e2, qvec) * inv_det, b1, b2))
+}
+
+/// Smooth vertex normals of an indexed triangle list: the area-weighted
+/// average of the normals of the triangles around each vertex.
+pub fn vertex_normals(positions: &[Point3], triangles: &[[u32; 3]]) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::new(); positions.len()];
+    for t in triangles {
+        let [p0, p1, p2] = t.map(|v| positions[v as usize]);
+        let n = cross(p1 - p0, p2 - p0);
+        for v in t {
+            normals[*v as usize] += n;
+        }
+    }
+    for n in &mut normals {
+        if n.length_squared() > 0.0 {
+            *n = unit_vector(*n);
+        }
+    }
+    normals
+}
+
+/// Most triangles kept in one leaf of a mesh hierarchy.
+const MAX_LEAF: usize = 4;
+
+/// Node of the flattened hierarchy of a `TriangleMesh`. Leaves hold `count`
+/// triangles starting at `offset`; interior nodes (`count == 0`) are
+/// followed by their first child and keep the second one at `offset`.
+struct MeshNode {
+    bbox: Aabb,
+    offset: u32,
+    count: u32,
+    /// Axis the children were split along, to visit the nearer one first.
+    axis: u8,
+}
+
+/// Renderable triangle mesh. Vertex data is shared between triangles and
+/// the hierarchy is a flat array, so meshes of millions of triangles stay
+/// compact. Vertex normals, when present, are interpolated as shading
+/// normals, and vertex colors tint the material's albedo.
+pub struct TriangleMesh {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+    colors: Vec<Color3>,
+    triangles: Vec<[u32; 3]>,
+    /// Where each of `triangles` came in, so that `to_json` writes them
+    /// back in their original order.
+    order: Vec<u32>,
+    nodes: Vec<MeshNode>,
+    mat: Rc<RefCell<dyn Material>>,
+}
+
+impl TriangleMesh {
+    /// Triangulates `mesh` into a fan per polygon.
+    pub fn new(mesh: &Mesh, mat: Rc<RefCell<dyn Material>>) -> TriangleMesh {
+        let index = |v: usize| u32::try_from(v).expect("mesh has too many vertices");
+        let triangles = mesh
+            .faces
+            .iter()
+            .flat_map(|f| {
+                (1..f.len().saturating_sub(1))
+                    .map(move |k| [index(f[0]), index(f[k]), index(f[k + 1])])
+            })
+            .collect();
+        TriangleMesh::from_triangles(
+            mesh.positions.clone(),
+            mesh.normals.clone(),
+            mesh.uvs.clone(),
+            mesh.colors.clone(),
+            triangles,
+            mat,
+        )
+    }
+
+    /// Builds a mesh directly from vertex arrays and counter-clockwise
+    /// triangles. `normals`, `uvs` and `colors` are either empty or hold
+    /// one entry per position.
+    pub fn from_triangles(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<(f64, f64)>,
+        colors: Vec<Color3>,
+        triangles: Vec<[u32; 3]>,
+        mat: Rc<RefCell<dyn Material>>,
+    ) -> TriangleMesh {
+        let mut mesh = TriangleMesh {
+            positions,
+            normals,
+            uvs,
+            colors,
+            triangles,
+            order: Vec::new(),
+            nodes: Vec::new(),
+            mat,
+        };
+        let mut order: Vec<u32> = (0..mesh.triangles.len() as u32).collect();
+        if !order.is_empty() {
+            let centroids: Vec<Point3> = mesh
+                .triangles
+                .iter()
+                .map(|t| {
+                    t.iter()
+                        .fold(Vec3::new(), |acc, v| acc + mesh.positions[*v as usize])
+                        / 3.0
+                })
+                .collect();
+            let mut nodes = Vec::with_capacity(2 * order.len() / MAX_LEAF + 1);
+            time_bvh_build(|| mesh.build(&mut nodes, &centroids, &mut order, 0));
+            mesh.nodes = nodes;
+        }
+        // Store triangles in leaf order.
+        mesh.triangles = order.iter().map(|k| mesh.triangles[*k as usize]).collect();
+        mesh.order = order;
+        mesh
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn has_vertex_colors(&self) -> bool {
+        !self.colors.is_empty()
+    }
+
+    /// Appends the subtree over the triangles `order`, which start at
+    /// `first` in leaf order, and returns the index of its root.
+    fn build(
+        &self,
+        nodes: &mut Vec<MeshNode>,
+        centroids: &[Point3],
+        order: &mut [u32],
+        first: usize,
+    ) -> usize {
+        // Padded so axis-aligned triangles keep a volume.
+        let pad = Vec3::from(1e-6, 1e-6, 1e-6);
+        let mut bbox = Aabb::new();
+        let mut centers = Aabb::new();
+        for k in order.iter() {
+            for v in self.triangles[*k as usize] {
+                let p = self.positions[v as usize];
+                bbox = Aabb::from_boxes(&bbox, &Aabb::from(p - pad, p + pad));
+            }
+            let c = centroids[*k as usize];
+            centers = Aabb::from_boxes(&centers, &Aabb::from(c, c));
+        }
+        let index = nodes.len();
+        nodes.push(MeshNode {
+            bbox,
+            offset: first as u32,
+            count: order.len() as u32,
+            axis: 0,
+        });
+        if order.len() <= MAX_LEAF {
+            return index;
+        }
+        let axis = centers.longest_axis();
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by(mid, |a, b| {
+            centroids[*a as usize][axis].total_cmp(&centroids[*b as usize][axis])
+        });
+        let (lower, upper) = order.split_at_mut(mid);
+        self.build(nodes, centroids, lower, first);
+        let second = self.build(nodes, centroids, upper, first + mid);
+        nodes[index].offset = second as u32;
+        nodes[index].count = 0;
+        nodes[index].axis = axis as u8;
+        index
+    }
+
+    /// Fills `rec` for a hit on triangle `k` at barycentrics `(b1, b2)`.
+    fn fill_record(&self, k: usize, r: Ray, t: f64, b1: f64, b2: f64, rec: &mut HitRecord) {
+        let [i0, i1, i2] = self.triangles[k].map(|v| v as usize);
+        let p = &self.positions;
+        let (p0, p1, p2) = (p[i0], p[i1], p[i2]);
+        let b0 = 1.0 - b1 - b2;
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_face_normal(r, unit_vector(cross(p1 - p0, p2 - p0)));
+        if !self.normals.is_empty() {
+            let n = &self.normals;
+            let shading = b0 * n[i0] + b1 * n[i1] + b2 * n[i2];
+            if !shading.near_zero() {
+                rec.set_shading_normal(unit_vector(shading));
+            }
+        }
+        if self.uvs.is_empty() {
+            (rec.u, rec.v) = (b1, b2);
+            rec.set_tangents(p1 - p0, p2 - p0);
+        } else {
+            let uv = &self.uvs;
+            rec.u = b0 * uv[i0].0 + b1 * uv[i1].0 + b2 * uv[i2].0;
+            rec.v = b0 * uv[i0].1 + b1 * uv[i1].1 + b2 * uv[i2].1;
+            // Solve p_k - p2 = (u_k - u2) dpdu + (v_k - v2) dpdv for k = 0, 1.
+            let (du02, dv02) = (uv[i0].0 - uv[i2].0, uv[i0].1 - uv[i2].1);
+            let (du12, dv12) = (uv[i1].0 - uv[i2].0, uv[i1].1 - uv[i2].1);
+            let det = du02 * dv12 - dv02 * du12;
+            if det.abs() > 1e-12 {
+                let (dp02, dp12) = (p0 - p2, p1 - p2);
+                rec.set_tangents(
+                    (dv12 * dp02 - dv02 * dp12) / det,
+                    (du02 * dp12 - du12 * dp02) / det,
+                );
+            }
+        }
+        if !self.colors.is_empty() {
+            let c = &self.colors;
+            rec.color = b0 * c[i0] + b1 * c[i1] + b2 * c[i2];
+        }
+        rec.mat = self.mat.clone();
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: Ray, ray_root: Interval, rec: &mut HitRecord) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        // Median splits keep the depth below 33 for any u32 triangle count.
+        let mut stack = [0usize; 64];
+        let mut depth = 0;
+        let mut node = 0;
+        let mut closest = None;
+        let mut t_max = ray_root.max;
+        loop {
+            let n = &self.nodes[node];
+            count(Counter::BvhNodesVisited);
+            if n.bbox.hit(r, Interval::from(ray_root.min, t_max)) {
+                if n.count == 0 {
+                    let (near, far) = if r.direction()[n.axis as usize] < 0.0 {
+                        (n.offset as usize, node + 1)
+                    } else {
+                        (node + 1, n.offset as usize)
+                    };
+                    stack[depth] = far;
+                    depth += 1;
+                    node = near;
+                    continue;
+                }
+                count_n(Counter::IntersectionTests, n.count as u64);
+                for k in n.offset as usize..(n.offset + n.count) as usize {
+                    let [p0, p1, p2] = self.triangles[k].map(|v| self.positions[v as usize]);
+                    if let Some((t, b1, b2)) = intersect_triangle(r, p0, p1, p2) {
+                        if Interval::from(ray_root.min, t_max).surrounds(t) {
+                            t_max = t;
+                            closest = Some((k, b1, b2));
+                        }
+                    }
+                }
+            }
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+            node = stack[depth];
+        }
+        match closest {
+            Some((k, b1, b2)) => {
+                self.fill_record(k, r, t_max, b1, b2, rec);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match self.nodes.first() {
+            Some(root) => root.bbox.clone(),
+            None => Aabb::new(),
+        }
+    }
+
+    fn to_json(&self, out: &mut SceneWriter) -> Option<Json> {
+        let flat = |vs: &[Vec3]| -> Json {
+            let items: Vec<Json> = vs
+                .iter()
+                .flat_map(|v| [v.x().into(), v.y().into(), v.z().into()])
+                .collect();
+            items.into()
+        };
+        let uvs: Vec<Json> = self
+            .uvs
+            .iter()
+            .flat_map(|(u, v)| [(*u).into(), (*v).into()])
+            .collect();
+        let mut triangles = vec![[0; 3]; self.triangles.len()];
+        for (t, k) in self.triangles.iter().zip(&self.order) {
+            triangles[*k as usize] = *t;
+        }
+        let triangles: Vec<Json> = triangles.iter().flat_map(|t| t.map(Json::from)).collect();
+        Some(Json::object([
+            ("type", "triangle_mesh".into()),
+            ("positions", flat(&self.positions)),
+            ("normals", flat(&self.normals)),
+            ("uvs", uvs.into()),
+            ("colors", flat(&self.colors)),
+            ("triangles", triangles.into()),
+            ("material", out.material(&self.mat)),
+        ]))
+    }
+
+    fn for_each_material(&self, f: &mut MaterialVisitor) {
+        f(&self.mat);
+    }
+}
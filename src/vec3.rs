@@ -7,25 +7,31 @@ pub struct Vec3 {
 
 pub use Vec3 as Point3;
 
-use crate::{rand_from, rand_norm};
+use crate::{rand_from, rand_norm, PI};
+
+impl Default for Vec3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Vec3 {
     pub fn new() -> Self {
-        return Self { e: [0.0, 0.0, 0.0] };
+        Self { e: [0.0, 0.0, 0.0] }
     }
 
     pub fn from(x: f64, y: f64, z: f64) -> Vec3 {
-        return Vec3 { e: [x, y, z] };
+        Vec3 { e: [x, y, z] }
     }
 
     pub fn x(&self) -> f64 {
-        return self.e[0];
+        self.e[0]
     }
     pub fn y(&self) -> f64 {
-        return self.e[1];
+        self.e[1]
     }
     pub fn z(&self) -> f64 {
-        return self.e[2];
+        self.e[2]
     }
 
     pub fn length(&self) -> f64 {
@@ -110,7 +116,7 @@ impl ops::Mul<f64> for Vec3 {
 
     #[inline(always)]
     fn mul(self, rhs: f64) -> Vec3 {
-        return Vec3::from(self.e[0] * rhs, self.e[1] * rhs, self.e[2] * rhs);
+        Vec3::from(self.e[0] * rhs, self.e[1] * rhs, self.e[2] * rhs)
     }
 }
 
@@ -118,7 +124,7 @@ impl ops::Mul<Vec3> for f64 {
     type Output = Vec3;
     #[inline(always)]
     fn mul(self, rhs: Vec3) -> Vec3 {
-        return Vec3::from(self * rhs.e[0], self * rhs.e[1], self * rhs.e[2]);
+        Vec3::from(self * rhs.e[0], self * rhs.e[1], self * rhs.e[2])
     }
 }
 
@@ -127,7 +133,7 @@ impl ops::Div<f64> for Vec3 {
 
     #[inline(always)]
     fn div(self, rhs: f64) -> Vec3 {
-        return self * (1.0 / rhs);
+        self * (1.0 / rhs)
     }
 }
 
@@ -152,9 +158,9 @@ impl ops::MulAssign<f64> for Vec3 {
 impl ops::DivAssign<f64> for Vec3 {
     #[inline(always)]
     fn div_assign(&mut self, rhs: f64) {
-        self.e[0] *= (1.0 / rhs);
-        self.e[1] *= (1.0 / rhs);
-        self.e[2] *= (1.0 / rhs);
+        self.e[0] *= 1.0 / rhs;
+        self.e[1] *= 1.0 / rhs;
+        self.e[2] *= 1.0 / rhs;
     }
 }
 
@@ -162,14 +168,14 @@ impl ops::Index<usize> for Vec3 {
     type Output = f64;
     #[inline(always)]
     fn index(&self, index: usize) -> &f64 {
-        return &self.e[index];
+        &self.e[index]
     }
 }
 
 impl ops::IndexMut<usize> for Vec3 {
     #[inline(always)]
     fn index_mut(&mut self, index: usize) -> &mut f64 {
-        return &mut self.e[index];
+        &mut self.e[index]
     }
 }
 
@@ -177,13 +183,13 @@ impl ops::Neg for Vec3 {
     type Output = Vec3;
     #[inline(always)]
     fn neg(self) -> Vec3 {
-        return Vec3::from(-self.e[0], -self.e[1], -self.e[2]);
+        Vec3::from(-self.e[0], -self.e[1], -self.e[2])
     }
 }
 
 #[inline(always)]
 pub fn dot(u: Vec3, v: Vec3) -> f64 {
-    return u.e[0] * v.e[0] + u.e[1] * v.e[1] + u.e[2] * v.e[2];
+    u.e[0] * v.e[0] + u.e[1] * v.e[1] + u.e[2] * v.e[2]
 }
 
 #[inline(always)]
@@ -200,36 +206,28 @@ pub fn unit_vector(v: Vec3) -> Vec3 {
     v / v.length()
 }
 
+/// Maps a point of the unit square uniformly onto the unit sphere.
 #[inline(always)]
-pub fn rand_unit_vector() -> Vec3 {
-    loop {
-        let p = Vec3::rand_from(-1.0, 1.0);
-        let lsq = p.length_squared();
-        // eprintln!("     iter {}", lsq);
-        if lsq <= 1.0 && 1e-160 < lsq {
-            // eprintln!("solution {:?} {}", p, lsq);
-            return p / lsq.sqrt();
-        }
-    }
-}
-
-#[inline(always)]
-pub fn rand_outside(norm: Vec3) -> Vec3 {
-    let on_sphere = rand_unit_vector();
-    if dot(on_sphere, norm) > 0.0 {
-        return on_sphere;
+pub fn sample_unit_vector(u: (f64, f64)) -> Vec3 {
+    let z = 1.0 - 2.0 * u.0;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+    Vec3::from(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Maps a point of the unit square uniformly onto the unit disk in the xy
+/// plane, using Shirley's concentric mapping to preserve stratification.
+pub fn sample_disk(u: (f64, f64)) -> Vec3 {
+    let (ox, oy) = (2.0 * u.0 - 1.0, 2.0 * u.1 - 1.0);
+    if ox == 0.0 && oy == 0.0 {
+        return Vec3::new();
+    }
+    let (r, theta) = if ox.abs() > oy.abs() {
+        (ox, (PI / 4.0) * (oy / ox))
     } else {
-        return -on_sphere;
-    }
-}
-
-pub fn rand_circle() -> Vec3 {
-    loop {
-        let p = Vec3::from(rand_from(-1.0, 1.0), rand_from(-1.0, 1.0), 0.0);
-        if p.length_squared() < 1.0 {
-            return p;
-        }
-    }
+        (oy, (PI / 2.0) - (PI / 4.0) * (ox / oy))
+    };
+    Vec3::from(r * theta.cos(), r * theta.sin(), 0.0)
 }
 
 #[inline(always)]